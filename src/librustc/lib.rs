@@ -164,6 +164,7 @@ pub mod util {
     pub mod ppaux;
     pub mod nodemap;
     pub mod fs;
+    pub mod profiling;
     pub mod time_graph;
 }
 
@@ -331,6 +331,12 @@ declare_lint! {
      via the module system"
 }
 
+declare_lint! {
+    pub LARGE_STACK_FRAMES,
+    Allow,
+    "functions and moves whose estimated stack usage is above `-Z stack-size-limit`"
+}
+
 /// Some lints that are buffered from `libsyntax`. See `syntax::early_buffered_lints`.
 pub mod parser {
     declare_lint! {
@@ -398,6 +404,7 @@ impl LintPass for HardwiredLints {
             WHERE_CLAUSES_OBJECT_SAFETY,
             PROC_MACRO_DERIVE_RESOLUTION_FALLBACK,
             MACRO_USE_EXTERN_CRATE,
+            LARGE_STACK_FRAMES,
             parser::QUESTION_MARK_MACRO_SEP,
         )
     }
@@ -0,0 +1,439 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Lint configuration files, passed with `--lint-config`.
+//!
+//! A lint configuration file is a TOML file setting the lint levels of a
+//! crate, so that many crates can share a lint policy:
+//!
+//! ```toml
+//! # Levels for the whole crate. The `-A`, `-W`, `-D` and `-F` flags take
+//! # precedence over them.
+//! [lints]
+//! unused_imports = "deny"
+//! missing-docs = "warn"
+//! naming = "deny"
+//!
+//! # Lint groups, which can be used like the builtin ones in this file, in
+//! # flags and in attributes.
+//! [groups]
+//! naming = ["non_snake_case", "non_camel_case_types"]
+//!
+//! # Levels for the items defined in the files under a path, relative to the
+//! # directory of this file. They take precedence over the levels of the
+//! # crate and the `-A` and `-W` flags, but not over the `-D` and `-F` flags,
+//! # and the lint attributes in those files take precedence over them.
+//! [paths."src/generated"]
+//! warnings = "allow"
+//! ```
+//!
+//! Only the subset of TOML needed for this is supported: tables, and keys
+//! whose values are strings or arrays of strings.
+
+use lint::Level;
+
+use std::fs;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::Chars;
+
+/// The contents of a lint configuration file.
+#[derive(Clone, Debug, Hash)]
+pub struct LintConfig {
+    /// The path of the file, for diagnostics.
+    pub path: PathBuf,
+    /// The levels of the `[lints]` table, in the order of the file.
+    pub lints: Vec<(String, Level)>,
+    /// The groups of the `[groups]` table, with the names of their lints.
+    pub groups: Vec<(String, Vec<String>)>,
+    /// The levels of the `[paths."<path>"]` tables, with the paths joined to
+    /// the directory of the file.
+    pub paths: Vec<(PathBuf, Vec<(String, Level)>)>,
+}
+
+#[derive(Clone, Copy)]
+enum Table {
+    Lints,
+    Groups,
+    Path(usize),
+}
+
+enum Value {
+    String(String),
+    Array(Vec<String>),
+}
+
+type ParseResult<T> = Result<T, (usize, String)>;
+
+impl LintConfig {
+    pub fn from_file(path: &Path) -> Result<LintConfig, String> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            format!("couldn't read lint configuration file `{}`: {}", path.display(), e)
+        })?;
+        LintConfig::parse(path, &contents).map_err(|(line, msg)| {
+            format!("invalid lint configuration file `{}`, line {}: {}",
+                    path.display(), line, msg)
+        })
+    }
+
+    fn parse(path: &Path, contents: &str) -> ParseResult<LintConfig> {
+        let mut config = LintConfig {
+            path: path.to_path_buf(),
+            lints: vec![],
+            groups: vec![],
+            paths: vec![],
+        };
+        let mut parser = Parser {
+            chars: contents.chars().peekable(),
+            line: 1,
+        };
+        let mut tables: Vec<Vec<String>> = vec![];
+        let mut table = None;
+        loop {
+            parser.skip_whitespace(true);
+            match parser.chars.peek().cloned() {
+                None => return Ok(config),
+                Some('[') => {
+                    parser.chars.next();
+                    let header = parser.table_header()?;
+                    if tables.contains(&header) {
+                        return parser.error(format!("duplicate table `[{}]`", header.join(".")));
+                    }
+                    table = Some(match (header.get(0).map(|s| &s[..]), header.len()) {
+                        (Some("lints"), 1) => Table::Lints,
+                        (Some("groups"), 1) => Table::Groups,
+                        (Some("paths"), 2) => {
+                            let dir = path.parent().unwrap_or(Path::new(""));
+                            config.paths.push((dir.join(&header[1]), vec![]));
+                            Table::Path(config.paths.len() - 1)
+                        }
+                        _ => {
+                            return parser.error(format!(
+                                "unknown table `[{}]`, expected `[lints]`, `[groups]` or \
+                                 `[paths.\"<path>\"]`", header.join(".")));
+                        }
+                    });
+                    tables.push(header);
+                }
+                Some(_) => {
+                    let key = parser.key()?;
+                    parser.skip_whitespace(false);
+                    parser.expect('=')?;
+                    parser.skip_whitespace(false);
+                    let value = parser.value()?;
+                    parser.end_of_line()?;
+
+                    let name = key.replace("-", "_");
+                    let duplicate = match table {
+                        None => {
+                            return parser.error(format!("`{}` must be in a table", key));
+                        }
+                        Some(Table::Lints) => config.lints.iter().any(|l| l.0 == name),
+                        Some(Table::Groups) => config.groups.iter().any(|g| g.0 == name),
+                        Some(Table::Path(i)) => config.paths[i].1.iter().any(|l| l.0 == name),
+                    };
+                    if duplicate {
+                        return parser.error(format!("duplicate key `{}`", key));
+                    }
+                    match (table, value) {
+                        (Some(Table::Groups), Value::Array(lints)) => {
+                            let lints = lints.iter().map(|l| l.replace("-", "_")).collect();
+                            config.groups.push((name, lints));
+                        }
+                        (Some(Table::Groups), Value::String(_)) => {
+                            return parser.error(format!(
+                                "the lints of group `{}` must be an array of strings", key));
+                        }
+                        (Some(Table::Lints), Value::String(level)) => {
+                            config.lints.push((name, parser.level(&level)?));
+                        }
+                        (Some(Table::Path(i)), Value::String(level)) => {
+                            config.paths[i].1.push((name, parser.level(&level)?));
+                        }
+                        _ => {
+                            return parser.error(format!(
+                                "the level of `{}` must be a string", key));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, msg: String) -> ParseResult<T> {
+        Err((self.line, msg))
+    }
+
+    fn unexpected<T>(&mut self, expected: &str) -> ParseResult<T> {
+        let found = match self.chars.peek().cloned() {
+            None => "end of file".to_string(),
+            Some('\n') => "end of line".to_string(),
+            Some(c) => format!("`{}`", c),
+        };
+        self.error(format!("expected {}, found {}", expected, found))
+    }
+
+    fn expect(&mut self, c: char) -> ParseResult<()> {
+        if self.chars.peek() == Some(&c) {
+            self.chars.next();
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", c))
+        }
+    }
+
+    /// Skips spaces and comments, and newlines if `newlines` is true.
+    fn skip_whitespace(&mut self, newlines: bool) {
+        while let Some(c) = self.chars.peek().cloned() {
+            match c {
+                ' ' | '\t' | '\r' => {}
+                '\n' if newlines => self.line += 1,
+                '#' => {
+                    while self.chars.peek().map_or(false, |&c| c != '\n') {
+                        self.chars.next();
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+            self.chars.next();
+        }
+    }
+
+    fn end_of_line(&mut self) -> ParseResult<()> {
+        self.skip_whitespace(false);
+        match self.chars.peek().cloned() {
+            None | Some('\n') => Ok(()),
+            _ => self.unexpected("end of line"),
+        }
+    }
+
+    /// Parses the dotted keys of a table header, after its `[`.
+    fn table_header(&mut self) -> ParseResult<Vec<String>> {
+        let mut keys = vec![];
+        loop {
+            self.skip_whitespace(false);
+            keys.push(self.key()?);
+            self.skip_whitespace(false);
+            match self.chars.peek().cloned() {
+                Some('.') => {
+                    self.chars.next();
+                }
+                Some(']') => {
+                    self.chars.next();
+                    self.end_of_line()?;
+                    return Ok(keys);
+                }
+                _ => return self.unexpected("`.` or `]`"),
+            }
+        }
+    }
+
+    fn key(&mut self) -> ParseResult<String> {
+        match self.chars.peek().cloned() {
+            Some('"') | Some('\'') => return self.string(),
+            _ => {}
+        }
+        let mut key = String::new();
+        while let Some(c) = self.chars.peek().cloned() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                break;
+            }
+            key.push(c);
+            self.chars.next();
+        }
+        if key.is_empty() {
+            return self.unexpected("a key");
+        }
+        Ok(key)
+    }
+
+    fn value(&mut self) -> ParseResult<Value> {
+        match self.chars.peek().cloned() {
+            Some('"') | Some('\'') => return Ok(Value::String(self.string()?)),
+            Some('[') => {}
+            _ => return self.unexpected("a string or an array of strings"),
+        }
+        self.chars.next();
+        let mut strings = vec![];
+        loop {
+            self.skip_whitespace(true);
+            match self.chars.peek().cloned() {
+                Some('"') | Some('\'') => strings.push(self.string()?),
+                Some(']') => break,
+                _ => return self.unexpected("a string or `]`"),
+            }
+            self.skip_whitespace(true);
+            match self.chars.peek().cloned() {
+                Some(',') => {
+                    self.chars.next();
+                }
+                Some(']') => break,
+                _ => return self.unexpected("`,` or `]`"),
+            }
+        }
+        self.chars.next();
+        Ok(Value::Array(strings))
+    }
+
+    /// Parses a basic (`"..."`) or literal (`'...'`) string on a single line.
+    fn string(&mut self) -> ParseResult<String> {
+        let quote = self.chars.next().unwrap();
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                None | Some('\n') => return self.error("unterminated string".to_string()),
+                Some(c) if c == quote => return Ok(string),
+                Some('\\') if quote == '"' => {
+                    let c = match self.chars.next() {
+                        Some('\\') => '\\',
+                        Some('"') => '"',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some(c) => {
+                            return self.error(format!("unsupported escape `\\{}`", c));
+                        }
+                        None => return self.error("unterminated string".to_string()),
+                    };
+                    string.push(c);
+                }
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn level(&self, level: &str) -> ParseResult<Level> {
+        match Level::from_str(level) {
+            Some(level) => Ok(level),
+            None => self.error(format!("unknown lint level `{}`, expected `allow`, `warn`, \
+                                        `deny` or `forbid`", level)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LintConfig;
+    use lint::Level;
+    use std::path::{Path, PathBuf};
+
+    fn parse(contents: &str) -> LintConfig {
+        LintConfig::parse(Path::new("config/lints.toml"), contents).unwrap()
+    }
+
+    fn error(contents: &str) -> (usize, String) {
+        LintConfig::parse(Path::new("config/lints.toml"), contents).unwrap_err()
+    }
+
+    #[test]
+    fn accepted_syntax() {
+        let config = parse(r#"
+# A comment
+[lints]
+unused-imports = "deny" # dashes become underscores
+'missing_docs' = 'warn'
+"clippy::all" = "allow"
+
+[groups]
+naming = [
+    "non_snake_case", # a comment
+    "non-camel-case-types",
+]
+empty = []
+
+[ paths . "src/gen" ]
+warnings = "allow"
+"#);
+        assert_eq!(config.lints, vec![
+            ("unused_imports".to_string(), Level::Deny),
+            ("missing_docs".to_string(), Level::Warn),
+            ("clippy::all".to_string(), Level::Allow),
+        ]);
+        assert_eq!(config.groups, vec![
+            ("naming".to_string(),
+             vec!["non_snake_case".to_string(), "non_camel_case_types".to_string()]),
+            ("empty".to_string(), vec![]),
+        ]);
+        assert_eq!(config.paths, vec![
+            (PathBuf::from("config/src/gen"), vec![("warnings".to_string(), Level::Allow)]),
+        ]);
+    }
+
+    #[test]
+    fn escapes() {
+        let config = parse(r#"
+[paths."a\"b\\c"]
+"#);
+        assert_eq!(config.paths[0].0, Path::new("config").join("a\"b\\c"));
+    }
+
+    #[test]
+    fn empty_file() {
+        let config = parse("\n# nothing\n");
+        assert!(config.lints.is_empty() && config.groups.is_empty() && config.paths.is_empty());
+    }
+
+    #[test]
+    fn table_errors() {
+        assert_eq!(error("[lints]\n[lints]"), (2, "duplicate table `[lints]`".to_string()));
+        assert_eq!(error("[lint]"), (1, "unknown table `[lint]`, expected `[lints]`, \
+                                         `[groups]` or `[paths.\"<path>\"]`".to_string()));
+        assert_eq!(error("[paths]"), (1, "unknown table `[paths]`, expected `[lints]`, \
+                                          `[groups]` or `[paths.\"<path>\"]`".to_string()));
+        assert_eq!(error("[lints] x"), (1, "expected end of line, found `x`".to_string()));
+        assert_eq!(error("[lints"), (1, "expected `.` or `]`, found end of file".to_string()));
+        assert_eq!(error("a = \"deny\""), (1, "`a` must be in a table".to_string()));
+    }
+
+    #[test]
+    fn key_errors() {
+        assert_eq!(error("[lints]\n= \"deny\""), (2, "expected a key, found `=`".to_string()));
+        assert_eq!(error("[lints]\na \"deny\""), (2, "expected `=`, found `\"`".to_string()));
+        assert_eq!(error("[lints]\na = \"deny\"\na = \"warn\""),
+                   (3, "duplicate key `a`".to_string()));
+        assert_eq!(error("[lints]\nunused_x = \"deny\"\nunused-x = \"warn\""),
+                   (3, "duplicate key `unused-x`".to_string()));
+        assert_eq!(error("[lints]\na = \"deny\" b"),
+                   (2, "expected end of line, found `b`".to_string()));
+    }
+
+    #[test]
+    fn value_errors() {
+        assert_eq!(error("[lints]\na = deny"),
+                   (2, "expected a string or an array of strings, found `d`".to_string()));
+        assert_eq!(error("[lints]\na = \"loud\""),
+                   (2, "unknown lint level `loud`, expected `allow`, `warn`, `deny` or \
+                        `forbid`".to_string()));
+        assert_eq!(error("[lints]\na = [\"deny\"]"),
+                   (2, "the level of `a` must be a string".to_string()));
+        assert_eq!(error("[groups]\ng = \"a\""),
+                   (2, "the lints of group `g` must be an array of strings".to_string()));
+        assert_eq!(error("[groups]\ng = [\n    \"a\",\n    b\n]"),
+                   (4, "expected a string or `]`, found `b`".to_string()));
+        assert_eq!(error("[groups]\ng = [\"a\" \"b\"]"),
+                   (2, "expected `,` or `]`, found `\"`".to_string()));
+    }
+
+    #[test]
+    fn string_errors() {
+        assert_eq!(error("[lints]\na = \"deny"), (2, "unterminated string".to_string()));
+        assert_eq!(error("[lints]\na = 'deny\n'"), (2, "unterminated string".to_string()));
+        assert_eq!(error("[lints]\na = \"d\\qeny\""),
+                   (2, "unsupported escape `\\q`".to_string()));
+    }
+}
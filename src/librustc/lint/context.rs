@@ -42,6 +42,7 @@ use util::nodemap::FxHashMap;
 use std::default::Default as StdDefault;
 use syntax::ast;
 use syntax::edition;
+use syntax::sarif;
 use syntax_pos::{MultiSpan, Span, symbol::LocalInternedString};
 use errors::DiagnosticBuilder;
 use hir;
@@ -210,6 +211,13 @@ impl LintStore {
                     (Some(sess), true)  => sess.err(&msg[..]),
                 }
             }
+
+            // The rules of the SARIF log are described by the lints.
+            if let Some(sess) = sess {
+                if let config::ErrorOutputType::Sarif = sess.opts.error_format {
+                    sarif::register_lint(lint.name_lower(), lint.desc);
+                }
+            }
         }
     }
 
@@ -881,11 +889,13 @@ impl<'a, 'tcx> hir_visit::Visitor<'tcx> for LateContext<'a, 'tcx> {
 
 impl<'a> ast_visit::Visitor<'a> for EarlyContext<'a> {
     fn visit_item(&mut self, it: &'a ast::Item) {
+        let push = self.builder.push_path_override(it.span);
         self.with_lint_attrs(it.id, &it.attrs, |cx| {
             run_lints!(cx, check_item, it);
             ast_visit::walk_item(cx, it);
             run_lints!(cx, check_item_post, it);
-        })
+        });
+        self.builder.pop(push);
     }
 
     fn visit_foreign_item(&mut self, it: &'a ast::ForeignItem) {
@@ -9,26 +9,33 @@
 // except according to those terms.
 
 use std::cmp;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use errors::{Applicability, DiagnosticBuilder};
 use hir::HirId;
 use ich::StableHashingContext;
 use lint::builtin;
 use lint::context::CheckLintNameResult;
-use lint::{self, Lint, LintId, Level, LintSource};
+use lint::{self, Lint, LintConfig, LintId, Level, LintSource, LintStore};
 use rustc_data_structures::stable_hasher::{HashStable, ToStableHashKey,
                                            StableHasher, StableHasherResult};
 use session::Session;
 use syntax::ast;
 use syntax::attr;
-use syntax::codemap::MultiSpan;
+use syntax::codemap::{FileName, MultiSpan};
 use syntax::feature_gate;
 use syntax::symbol::Symbol;
+use syntax_pos::Span;
 use util::nodemap::FxHashMap;
 
 pub struct LintLevelSets {
     list: Vec<LintSet>,
     lint_cap: Level,
+    // The `[paths."<path>"]` tables of the lint configuration file, with the
+    // paths canonicalized, which are pushed by
+    // `LintLevelsBuilder::push_path_override`.
+    path_overrides: Vec<(PathBuf, FxHashMap<LintId, (Level, LintSource)>)>,
 }
 
 enum LintSet {
@@ -49,6 +56,7 @@ impl LintLevelSets {
         let mut me = LintLevelSets {
             list: Vec::new(),
             lint_cap: Level::Forbid,
+            path_overrides: Vec::new(),
         };
         me.process_command_line(sess);
         return me
@@ -63,6 +71,12 @@ impl LintLevelSets {
         let mut specs = FxHashMap();
         self.lint_cap = sess.opts.lint_cap.unwrap_or(Level::Forbid);
 
+        // The levels of the lint configuration file go first, so that the
+        // flags take precedence over them.
+        if let Some(ref config) = sess.opts.lint_config {
+            self.insert_config_specs(&store, &config.lints, &mut specs);
+        }
+
         for &(ref lint_name, level) in &sess.opts.lint_opts {
             store.check_lint_name_cmdline(sess, &lint_name, level);
 
@@ -82,9 +96,82 @@ impl LintLevelSets {
             }
         }
 
+        if let Some(ref config) = sess.opts.lint_config {
+            for &(ref path, ref lints) in &config.paths {
+                let mut path_specs = FxHashMap();
+                self.insert_config_specs(&store, lints, &mut path_specs);
+                // The paths of the file take precedence over its `[lints]`,
+                // but not over `-D` and `-F`, so that `-D warnings` holds
+                // for the whole crate.
+                path_specs.retain(|id, _| {
+                    match specs.get(id) {
+                        Some(&(Level::Deny, LintSource::CommandLine(_))) |
+                        Some(&(Level::Forbid, LintSource::CommandLine(_))) => false,
+                        _ => true,
+                    }
+                });
+                self.path_overrides.push((canonicalize(path), path_specs));
+            }
+        }
+
         self.list.push(LintSet::CommandLine {
             specs: specs,
         });
+
+        if let Some(ref config) = sess.opts.lint_config {
+            self.check_config_file(sess, &store, config);
+        }
+    }
+
+    fn insert_config_specs(&self,
+                           store: &LintStore,
+                           lints: &[(String, Level)],
+                           specs: &mut FxHashMap<LintId, (Level, LintSource)>) {
+        for &(ref lint_name, level) in lints {
+            let level = cmp::min(level, self.lint_cap);
+            let ids = match store.find_lints(&lint_name) {
+                Ok(ids) => ids,
+                Err(_) => continue, // errors handled in check_config_file below
+            };
+            let src = LintSource::ConfigFile(Symbol::intern(lint_name));
+            for id in ids {
+                specs.insert(id, (level, src));
+            }
+        }
+    }
+
+    /// Reports the unknown, renamed and removed lints named in the lint
+    /// configuration file, with the `unknown_lints` and
+    /// `renamed_and_removed_lints` lints.
+    fn check_config_file(&self, sess: &Session, store: &LintStore, config: &LintConfig) {
+        let names = config.lints.iter().map(|&(ref name, _)| name)
+            .chain(config.groups.iter().flat_map(|&(_, ref lints)| lints))
+            .chain(config.paths.iter().flat_map(|&(_, ref lints)| {
+                lints.iter().map(|&(ref name, _)| name)
+            }));
+        for name in names {
+            let mut parts = name.splitn(2, "::");
+            let result = match (parts.next(), parts.next()) {
+                (Some(tool), Some(lint)) => {
+                    store.check_lint_name(lint, Some(Symbol::intern(tool).as_str()))
+                }
+                _ => store.check_lint_name(name, None),
+            };
+            let (lint, msg) = match result {
+                // Lints of tools which aren't running are left to the tools.
+                CheckLintNameResult::Ok(_) | CheckLintNameResult::Tool(_) => continue,
+                CheckLintNameResult::Warning(msg, _) => {
+                    (builtin::RENAMED_AND_REMOVED_LINTS, msg)
+                }
+                CheckLintNameResult::NoLint => {
+                    (builtin::UNKNOWN_LINTS, format!("unknown lint: `{}`", name))
+                }
+            };
+            let (level, src) = self.get_lint_level(lint, 0, None, sess);
+            lint::struct_lint_level(sess, lint, level, src, None, &msg)
+                .note(&format!("in the lint configuration file `{}`", config.path.display()))
+                .emit();
+        }
     }
 
     fn get_lint_level(&self,
@@ -162,11 +249,15 @@ pub struct LintLevelsBuilder<'a> {
     sets: LintLevelSets,
     id_to_set: FxHashMap<HirId, u32>,
     cur: u32,
+    cur_path_override: Option<usize>,
+    // The canonical paths of the source files, for the path overrides.
+    canonical_paths: FxHashMap<PathBuf, PathBuf>,
     warn_about_weird_lints: bool,
 }
 
 pub struct BuilderPush {
     prev: u32,
+    prev_path_override: Option<usize>,
 }
 
 impl<'a> LintLevelsBuilder<'a> {
@@ -176,6 +267,8 @@ impl<'a> LintLevelsBuilder<'a> {
             sess,
             sets,
             cur: 0,
+            cur_path_override: None,
+            canonical_paths: FxHashMap(),
             id_to_set: FxHashMap(),
             warn_about_weird_lints: sess.buffered_lints.borrow().is_some(),
         }
@@ -349,6 +442,7 @@ impl<'a> LintLevelsBuilder<'a> {
                 LintSource::Default => id.to_string(),
                 LintSource::Node(name, _) => name.to_string(),
                 LintSource::CommandLine(name) => name.to_string(),
+                LintSource::ConfigFile(name) => name.to_string(),
             };
             let (lint_attr_name, lint_attr_span) = match *src {
                 LintSource::Node(name, span) => (name, span),
@@ -371,6 +465,9 @@ impl<'a> LintLevelsBuilder<'a> {
                 LintSource::CommandLine(_) => {
                     diag_builder.note("`forbid` lint level was set on command line")
                 }
+                LintSource::ConfigFile(_) => {
+                    diag_builder.note("`forbid` lint level was set in the lint configuration file")
+                }
             }.emit();
             // don't set a separate error for every lint in the group
             break
@@ -387,12 +484,65 @@ impl<'a> LintLevelsBuilder<'a> {
 
         BuilderPush {
             prev: prev,
+            prev_path_override: self.cur_path_override,
         }
     }
 
+    /// Pushes the levels which the lint configuration file sets for the file
+    /// of `span`, if any, for an item starting in that file. The paths are
+    /// compared once canonicalized, and the longest matching path wins.
+    ///
+    /// Like `push`, this returns a `BuilderPush` which should be passed to
+    /// `pop` at the end of the item.
+    pub fn push_path_override(&mut self, span: Span) -> BuilderPush {
+        let push = BuilderPush {
+            prev: self.cur,
+            prev_path_override: self.cur_path_override,
+        };
+        if self.sets.path_overrides.is_empty() {
+            return push
+        }
+        let filemap = self.sess.codemap().lookup_char_pos(span.lo()).file;
+        // The paths of `--remap-path-prefix` may not exist.
+        let file = match *filemap.unmapped_path.as_ref().unwrap_or(&filemap.name) {
+            FileName::Real(ref path) => {
+                self.canonical_paths.entry(path.clone())
+                    .or_insert_with(|| canonicalize(path))
+            }
+            _ => return push,
+        };
+        let matching = self.sets.path_overrides.iter()
+            .enumerate()
+            .filter(|&(_, &(ref path, _))| file.starts_with(path))
+            .max_by_key(|&(_, &(ref path, _))| path.components().count())
+            .map(|(i, _)| i);
+        // Items nested in an item of the same file already have its levels.
+        if matching.is_none() || matching == self.cur_path_override {
+            return push
+        }
+        let i = matching.unwrap();
+        // Like a `#[forbid]`, a forbidden lint can't be lowered for a path.
+        let specs = self.sets.path_overrides[i].1.iter()
+            .filter(|&(id, _)| {
+                self.sets.get_lint_id_level(*id, self.cur, None).0 != Some(Level::Forbid)
+            })
+            .map(|(id, spec)| (*id, *spec))
+            .collect::<FxHashMap<_, _>>();
+        self.cur_path_override = matching;
+        if specs.len() > 0 {
+            self.cur = self.sets.list.len() as u32;
+            self.sets.list.push(LintSet::Node {
+                specs: specs,
+                parent: push.prev,
+            });
+        }
+        push
+    }
+
     /// Called after `push` when the scope of a set of attributes are exited.
     pub fn pop(&mut self, push: BuilderPush) {
         self.cur = push.prev;
+        self.cur_path_override = push.prev_path_override;
     }
 
     /// Used to emit a lint-related diagnostic based on the current state of
@@ -464,9 +614,11 @@ impl<'a> HashStable<StableHashingContext<'a>> for LintLevelMap {
 
         id_to_set.hash_stable(hcx, hasher);
 
+        // The path overrides which apply are pushed onto `list`.
         let LintLevelSets {
             ref list,
             lint_cap,
+            path_overrides: _,
         } = *sets;
 
         lint_cap.hash_stable(hcx, hasher);
@@ -510,3 +662,8 @@ impl<HCX> ToStableHashKey<HCX> for LintId {
         self.lint_name_raw()
     }
 }
+
+/// The canonical form of `path`, or `path` itself if it doesn't exist.
+fn canonicalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -474,20 +474,27 @@ pub enum LintSource {
 
     /// Lint level was set by a command-line flag.
     CommandLine(Symbol),
+
+    /// Lint level was set by the lint configuration file, for the lint or
+    /// group with the given name.
+    ConfigFile(Symbol),
 }
 
 impl_stable_hash_for!(enum self::LintSource {
     Default,
     Node(name, span),
-    CommandLine(text)
+    CommandLine(text),
+    ConfigFile(text)
 });
 
 pub type LevelSource = (Level, LintSource);
 
 pub mod builtin;
+mod config;
 mod context;
 mod levels;
 
+pub use self::config::LintConfig;
 pub use self::levels::{LintLevelSets, LintLevelMap};
 
 pub struct LintBuffer {
@@ -578,6 +585,16 @@ pub fn struct_lint_level<'a>(sess: &'a Session,
                              hyphen_case_flag_val));
             }
         }
+        LintSource::ConfigFile(lint_config_name) => {
+            let level_str = level.as_str();
+            let msg = if lint_config_name.as_str() == name {
+                format!("`{} = \"{}\"` set in the lint configuration file", name, level_str)
+            } else {
+                format!("`{} = \"{}\"` implied by `{} = \"{}\"` in the lint configuration file",
+                        name, level_str, lint_config_name, level_str)
+            };
+            sess.diag_note_once(&mut err, DiagnosticMessageId::from(lint), &msg);
+        }
         LintSource::Node(lint_attr_name, src) => {
             sess.diag_span_note_once(&mut err, DiagnosticMessageId::from(lint),
                                      src, "lint level defined here");
@@ -677,9 +694,11 @@ impl<'a, 'tcx> intravisit::Visitor<'tcx> for LintLevelMapBuilder<'a, 'tcx> {
     }
 
     fn visit_item(&mut self, it: &'tcx hir::Item) {
+        let push = self.levels.push_path_override(it.span);
         self.with_lint_attrs(it.id, &it.attrs, |builder| {
             intravisit::walk_item(builder, it);
         });
+        self.levels.pop(push);
     }
 
     fn visit_foreign_item(&mut self, it: &'tcx hir::ForeignItem) {
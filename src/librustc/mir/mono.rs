@@ -209,6 +209,8 @@ pub struct Stats {
     pub llvm_insns: FxHashMap<String, usize>,
     // (ident, llvm-instructions)
     pub fn_stats: Vec<(String, usize)>,
+    // (symbol, llvm-instructions)
+    pub symbol_stats: Vec<(String, usize)>,
 }
 
 impl_stable_hash_for!(struct self::Stats {
@@ -220,7 +222,8 @@ impl_stable_hash_for!(struct self::Stats {
     n_closures,
     n_llvm_insns,
     llvm_insns,
-    fn_stats
+    fn_stats,
+    symbol_stats
 });
 
 impl Stats {
@@ -237,5 +240,6 @@ impl Stats {
             *self.llvm_insns.entry(k).or_insert(0) += v;
         }
         self.fn_stats.extend(stats.fn_stats);
+        self.symbol_stats.extend(stats.symbol_stats);
     }
 }
@@ -11,9 +11,12 @@
 use ty::AdtKind;
 use ty::layout::{Align, Size};
 
-use rustc_data_structures::fx::{FxHashSet};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use serialize::json::as_json;
 
 use std::cmp::{self, Ordering};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct VariantInfo {
@@ -67,13 +70,55 @@ pub struct TypeSizeInfo {
     pub variants: Vec<VariantInfo>,
 }
 
+#[derive(PartialEq, Eq, Hash, Debug)]
+pub struct StackFrameInfo {
+    pub function: String,
+    pub symbol: String,
+    pub size: u64,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct MonoItemInfo {
+    /// The path of the function or static the item is an instance of.
+    pub definition: String,
+    /// The item, with its type parameters.
+    pub instance: String,
+    pub symbol: String,
+    pub mir_size: usize,
+    /// The number of LLVM instructions codegened for the item, in all its
+    /// copies; `None` if it wasn't codegened in this session.
+    pub llvm_size: Option<usize>,
+    /// The number of codegen units the item is codegened in.
+    pub copies: usize,
+    /// The crates of the items which reference this one; empty for roots.
+    pub triggered_by: BTreeSet<String>,
+}
+
 #[derive(PartialEq, Eq, Debug)]
 pub struct CodeStats {
     type_sizes: FxHashSet<TypeSizeInfo>,
+    stack_frames: Vec<StackFrameInfo>,
+    mono_items: FxHashMap<String, MonoItemInfo>,
+}
+
+/// The mono items instantiated from a definition, for
+/// `-Z print-mono-bloat`.
+struct MonoItemGroup<'a> {
+    definition: &'a str,
+    items: Vec<&'a MonoItemInfo>,
+    mir_size: usize,
+    llvm_size: Option<usize>,
+    triggered_by: BTreeSet<&'a str>,
 }
 
 impl CodeStats {
-    pub fn new() -> Self { CodeStats { type_sizes: FxHashSet() } }
+    pub fn new() -> Self {
+        CodeStats {
+            type_sizes: FxHashSet(),
+            stack_frames: Vec::new(),
+            mono_items: FxHashMap(),
+        }
+    }
 
     pub fn record_type_size<S: ToString>(&mut self,
                                          kind: DataTypeKind,
@@ -191,4 +236,165 @@ impl CodeStats {
             }
         }
     }
+
+    pub fn record_stack_frame<S: ToString>(&mut self, function: S, symbol: S, size: u64) {
+        self.stack_frames.push(StackFrameInfo {
+            function: function.to_string(),
+            symbol: symbol.to_string(),
+            size,
+        });
+    }
+
+    fn sorted_stack_frames(&self) -> Vec<&StackFrameInfo> {
+        let mut sorted: Vec<_> = self.stack_frames.iter().collect();
+
+        // Primary sort: large-to-small.
+        // Secondary sort: function (dictionary order)
+        sorted.sort_by(|info1, info2| {
+            match info2.size.cmp(&info1.size) {
+                Ordering::Equal => info1.function.cmp(&info2.function),
+                other => other,
+            }
+        });
+        sorted
+    }
+
+    pub fn print_stack_sizes(&self) {
+        for info in self.sorted_stack_frames() {
+            println!("print-stack-size fn `{}`: {} bytes", info.function, info.size);
+        }
+    }
+
+    /// Write the stack frame sizes as a JSON array of objects with the
+    /// `function`, `symbol` and `size` (in bytes) of each function.
+    pub fn write_stack_sizes(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "[")?;
+        let frames = self.sorted_stack_frames();
+        for (i, info) in frames.iter().enumerate() {
+            writeln!(out, "  {{\"function\":{},\"symbol\":{},\"size\":{}}}{}",
+                     as_json(&info.function), as_json(&info.symbol), info.size,
+                     if i + 1 < frames.len() { "," } else { "" })?;
+        }
+        writeln!(out, "]")
+    }
+
+    pub fn record_mono_item(&mut self, info: MonoItemInfo) {
+        self.mono_items.insert(info.symbol.clone(), info);
+    }
+
+    /// Add `insns` LLVM instructions to the size of the item with the
+    /// symbol `symbol`, for one of its copies.
+    pub fn record_mono_item_llvm_size(&mut self, symbol: &str, insns: usize) {
+        if let Some(info) = self.mono_items.get_mut(symbol) {
+            *info.llvm_size.get_or_insert(0) += insns;
+        }
+    }
+
+    /// The mono items grouped by definition, from the largest group to the
+    /// smallest. Within a group, the items are sorted the same way.
+    fn mono_item_groups(&self) -> Vec<MonoItemGroup> {
+        let mut groups: BTreeMap<&str, MonoItemGroup> = BTreeMap::new();
+        for info in self.mono_items.values() {
+            let group = groups.entry(&info.definition[..]).or_insert_with(|| MonoItemGroup {
+                definition: &info.definition,
+                items: vec![],
+                mir_size: 0,
+                llvm_size: None,
+                triggered_by: BTreeSet::new(),
+            });
+            group.items.push(info);
+            group.mir_size += info.mir_size * info.copies;
+            if let Some(llvm_size) = info.llvm_size {
+                *group.llvm_size.get_or_insert(0) += llvm_size;
+            }
+            group.triggered_by.extend(info.triggered_by.iter().map(|krate| &krate[..]));
+        }
+
+        // Primary sort: large-to-small, by LLVM size, then by MIR size.
+        // Secondary sort: definition or instance (dictionary order)
+        let mut groups: Vec<_> = groups.into_iter().map(|(_, group)| group).collect();
+        groups.sort_by(|group1, group2| {
+            (group2.llvm_size, group2.mir_size).cmp(&(group1.llvm_size, group1.mir_size))
+                .then_with(|| group1.definition.cmp(group2.definition))
+        });
+        for group in &mut groups {
+            group.items.sort_by(|info1, info2| {
+                (info2.llvm_size, info2.mir_size * info2.copies)
+                    .cmp(&(info1.llvm_size, info1.mir_size * info1.copies))
+                    .then_with(|| info1.instance.cmp(&info2.instance))
+            });
+        }
+        groups
+    }
+
+    pub fn print_mono_bloat(&self, out: &mut dyn Write) -> io::Result<()> {
+        fn size(size: Option<usize>) -> String {
+            size.map_or("-".to_string(), |size| size.to_string())
+        }
+        fn crates<'a, I: Iterator<Item = &'a str>>(crates: I) -> String {
+            let crates = crates.collect::<Vec<_>>();
+            if crates.is_empty() {
+                "(root)".to_string()
+            } else {
+                crates.join(", ")
+            }
+        }
+
+        let groups = self.mono_item_groups();
+        writeln!(out, "mono-bloat: {} mono items instantiated from {} definitions",
+                 self.mono_items.len(), groups.len())?;
+        writeln!(out, "mono-bloat: sizes are in MIR statements and LLVM instructions, \
+                       for all the copies of an item")?;
+        writeln!(out)?;
+        writeln!(out, "{:>9} {:>10} {:>10} {:>6}  {}",
+                 "instances", "MIR size", "LLVM size", "copies", "definition / instance")?;
+        for group in &groups {
+            writeln!(out, "{:>9} {:>10} {:>10} {:>6}  {}  [triggered by {}]",
+                     group.items.len(), group.mir_size, size(group.llvm_size),
+                     group.items.iter().map(|info| info.copies).sum::<usize>(),
+                     group.definition, crates(group.triggered_by.iter().cloned()))?;
+            // A definition with one instance, like a non-generic function,
+            // is its own instance.
+            if group.items.len() == 1 && group.items[0].instance == group.definition {
+                continue;
+            }
+            for info in &group.items {
+                writeln!(out, "{:>9} {:>10} {:>10} {:>6}    {}  [triggered by {}]",
+                         "", info.mir_size * info.copies, size(info.llvm_size), info.copies,
+                         info.instance, crates(info.triggered_by.iter().map(|k| &k[..])))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the mono items grouped by definition, as a JSON array with an
+    /// object for each definition, and its instances in `items`.
+    pub fn write_mono_bloat_json(&self, out: &mut dyn Write) -> io::Result<()> {
+        fn size(size: Option<usize>) -> String {
+            size.map_or("null".to_string(), |size| size.to_string())
+        }
+        fn crates<'a, I: Iterator<Item = &'a str>>(crates: I) -> String {
+            let crates = crates.map(|krate| as_json(&krate).to_string()).collect::<Vec<_>>();
+            format!("[{}]", crates.join(","))
+        }
+
+        let groups = self.mono_item_groups();
+        writeln!(out, "[")?;
+        for (i, group) in groups.iter().enumerate() {
+            writeln!(out, "  {{\"definition\":{},\"instances\":{},\"mir_size\":{},\
+                           \"llvm_size\":{},\"triggered_by\":{},\"items\":[",
+                     as_json(&group.definition), group.items.len(), group.mir_size,
+                     size(group.llvm_size), crates(group.triggered_by.iter().cloned()))?;
+            for (j, info) in group.items.iter().enumerate() {
+                writeln!(out, "    {{\"instance\":{},\"symbol\":{},\"mir_size\":{},\
+                               \"llvm_size\":{},\"copies\":{},\"triggered_by\":{}}}{}",
+                         as_json(&info.instance), as_json(&info.symbol), info.mir_size,
+                         size(info.llvm_size), info.copies,
+                         crates(info.triggered_by.iter().map(|k| &k[..])),
+                         if j + 1 < group.items.len() { "," } else { "" })?;
+            }
+            writeln!(out, "  ]}}{}", if i + 1 < groups.len() { "," } else { "" })?;
+        }
+        writeln!(out, "]")
+    }
 }
@@ -224,6 +224,8 @@ pub enum ErrorOutputType {
     HumanReadable(ColorConfig),
     Json(bool),
     Short(ColorConfig),
+    /// A SARIF 2.1.0 log, for code-scanning tools.
+    Sarif,
 }
 
 impl Default for ErrorOutputType {
@@ -384,6 +386,8 @@ top_level_options!(
         debuginfo: DebugInfoLevel [TRACKED],
         lint_opts: Vec<(String, lint::Level)> [TRACKED],
         lint_cap: Option<lint::Level> [TRACKED],
+        // The parsed `--lint-config` file, so that editing the file is tracked
+        lint_config: Option<lint::LintConfig> [TRACKED],
         describe_lints: bool [UNTRACKED],
         output_types: OutputTypes [TRACKED],
         search_paths: SearchPaths [UNTRACKED],
@@ -609,6 +613,7 @@ pub fn basic_options() -> Options {
         debuginfo: NoDebugInfo,
         lint_opts: Vec::new(),
         lint_cap: None,
+        lint_config: None,
         describe_lints: false,
         output_types: OutputTypes(BTreeMap::new()),
         search_paths: SearchPaths::new(),
@@ -1209,6 +1214,8 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "immediately print bugs registered with `delay_span_bug`"),
     external_macro_backtrace: bool = (false, parse_bool, [UNTRACKED],
           "show macro backtraces even for non-local macros"),
+    apply_suggestions: bool = (false, parse_bool, [UNTRACKED],
+          "apply the machine-applicable suggestions to the files of the crate"),
     teach: bool = (false, parse_bool, [TRACKED],
           "show extended diagnostic help"),
     continue_parse_after_error: bool = (false, parse_bool, [TRACKED],
@@ -1219,6 +1226,13 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "enable incremental compilation support for queries (experimental)"),
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
         "print high-level information about incremental reuse (or the lack thereof)"),
+    incremental_sessions: bool = (false, parse_bool, [UNTRACKED],
+        "list the sessions in the incremental compilation directory, and stop"),
+    incremental_gc_age: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "delete the incremental compilation sessions older than this many days, and stop"),
+    incremental_gc_size: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "delete the oldest incremental compilation sessions until the incremental \
+         compilation directory takes at most this many MiB, and stop"),
     incremental_dump_hash: bool = (false, parse_bool, [UNTRACKED],
         "dump hash information in textual format to stdout"),
     incremental_verify_ich: bool = (false, parse_bool, [UNTRACKED],
@@ -1233,6 +1247,8 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "trace and profile the queries of the incremental compilation framework"),
     profile_queries_and_keys: bool = (false, parse_bool, [UNTRACKED],
           "trace and profile the queries and keys of the incremental compilation framework"),
+    self_profile: bool = (false, parse_bool, [UNTRACKED],
+          "profile passes, queries and codegen, write a trace-event file and print a summary"),
     no_analysis: bool = (false, parse_bool, [UNTRACKED],
           "parse and expand the source, but run no analysis"),
     extra_plugins: Vec<String> = (Vec::new(), parse_list, [TRACKED],
@@ -1253,8 +1269,20 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "show spans for compiler debugging (expr|pat|ty)"),
     print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
           "print layout information for each type encountered"),
+    print_stack_sizes: bool = (false, parse_bool, [UNTRACKED_WITH_WARNING(true,
+          "The output of `-Z print-stack-sizes` will only include the functions of \
+           re-codegened modules when used with incremental compilation")],
+          "print the estimated stack frame size of each function codegened, and write \
+           them to a `.stack-sizes.json` file"),
+    stack_size_limit: usize = (4096, parse_uint, [TRACKED],
+          "the estimated stack usage, in bytes, of a function or a move above which the \
+           `large_stack_frames` lint fires (default: 4096)"),
     print_mono_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
           "print the result of the monomorphization collection pass"),
+    print_mono_bloat: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "print the mono items grouped by the definition they're instantiated from, with \
+           their sizes and the crates which triggered them, as a `table` or as `json`; \
+           LLVM sizes are only known for re-codegened modules with incremental compilation"),
     mir_opt_level: usize = (1, parse_uint, [TRACKED],
           "set the MIR optimization level (0-3, default: 1)"),
     mutable_noalias: Option<bool> = (None, parse_opt_bool, [TRACKED],
@@ -1267,6 +1295,12 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "the directory the MIR is dumped into"),
     dump_mir_graphviz: bool = (false, parse_bool, [UNTRACKED],
           "in addition to `.mir` files, create graphviz `.dot` files"),
+    dump_mir_dataflow: bool = (false, parse_bool, [UNTRACKED],
+          "with `-Z dump-mir=borrowck`, create graphviz `.dot` files with the live locals, \
+           initialized places and borrows in scope on entry to each basic block"),
+    dump_mir_dataflow_errors: bool = (false, parse_bool, [UNTRACKED],
+          "in the `-Z dump-mir-dataflow` graphs, highlight the path from the borrow \
+           behind each borrow error to the error"),
     dump_mir_exclude_pass_number: bool = (false, parse_bool, [UNTRACKED],
           "if set, exclude the pass number when dumping MIR (used in tests)"),
     mir_emit_validate: usize = (0, parse_uint, [TRACKED],
@@ -1701,7 +1735,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "",
             "error-format",
             "How errors and other messages are produced",
-            "human|json|short",
+            "human|json|short|sarif",
         ),
         opt::opt_s(
             "",
@@ -1733,6 +1767,12 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "Remap source names in all output (compiler messages and output files)",
             "FROM=TO",
         ),
+        opt::opt(
+            "",
+            "lint-config",
+            "Read lint levels, lint groups and per-path lint levels from a TOML file",
+            "PATH",
+        ),
     ]);
     opts
 }
@@ -1847,13 +1887,14 @@ pub fn build_session_options_and_crate_config(
             Some("json") => ErrorOutputType::Json(false),
             Some("pretty-json") => ErrorOutputType::Json(true),
             Some("short") => ErrorOutputType::Short(color),
+            Some("sarif") => ErrorOutputType::Sarif,
             None => ErrorOutputType::HumanReadable(color),
 
             Some(arg) => early_error(
                 ErrorOutputType::HumanReadable(color),
                 &format!(
-                    "argument for --error-format must be `human`, `json` or \
-                     `short` (instead was `{}`)",
+                    "argument for --error-format must be `human`, `json`, `short` or \
+                     `sarif` (instead was `{}`)",
                     arg
                 ),
             ),
@@ -1867,6 +1908,10 @@ pub fn build_session_options_and_crate_config(
         .unwrap_or_else(|e| early_error(error_format, &e[..]));
 
     let (lint_opts, describe_lints, lint_cap) = get_cmd_lint_options(matches, error_format);
+    let lint_config = matches.opt_str("lint-config").map(|path| {
+        lint::LintConfig::from_file(Path::new(&path))
+            .unwrap_or_else(|e| early_error(error_format, &e))
+    });
 
     let mut debugging_opts = build_debugging_options(matches, error_format);
 
@@ -1877,6 +1922,13 @@ pub fn build_session_options_and_crate_config(
         );
     }
 
+    if !debugging_opts.unstable_options && error_format == ErrorOutputType::Sarif {
+        early_error(
+            ErrorOutputType::default(),
+            "--error-format=sarif is unstable",
+        );
+    }
+
     if debugging_opts.pgo_gen.is_some() && !debugging_opts.pgo_use.is_empty() {
         early_error(
             error_format,
@@ -2239,6 +2291,7 @@ pub fn build_session_options_and_crate_config(
             debuginfo,
             lint_opts,
             lint_cap,
+            lint_config,
             describe_lints,
             output_types: OutputTypes(output_types),
             search_paths,
@@ -2440,6 +2493,7 @@ mod dep_tracking {
     impl_dep_tracking_hash_via_hash!(Option<PanicStrategy>);
     impl_dep_tracking_hash_via_hash!(Option<RelroLevel>);
     impl_dep_tracking_hash_via_hash!(Option<lint::Level>);
+    impl_dep_tracking_hash_via_hash!(Option<lint::LintConfig>);
     impl_dep_tracking_hash_via_hash!(Option<PathBuf>);
     impl_dep_tracking_hash_via_hash!(Option<cstore::NativeLibraryKind>);
     impl_dep_tracking_hash_via_hash!(CrateType);
@@ -2768,6 +2822,33 @@ mod tests {
         assert_eq!(v2.dep_tracking_hash(), v2.clone().dep_tracking_hash());
     }
 
+    #[test]
+    fn test_lint_config_tracking_hash_different_values() {
+        let mut v1 = super::basic_options();
+        let mut v2 = super::basic_options();
+        let mut v3 = super::basic_options();
+
+        let config = lint::LintConfig {
+            path: PathBuf::from("lints.toml"),
+            lints: vec![(String::from("a"), lint::Deny)],
+            groups: vec![(String::from("g"), vec![String::from("a"), String::from("b")])],
+            paths: vec![(PathBuf::from("gen"), vec![(String::from("b"), lint::Allow)])],
+        };
+        v1.lint_config = Some(config.clone());
+        v2.lint_config = Some(config.clone());
+        v2.lint_config.as_mut().unwrap().paths[0].1[0].1 = lint::Warn;
+        v3.lint_config = None;
+
+        assert!(v1.dep_tracking_hash() != v2.dep_tracking_hash());
+        assert!(v1.dep_tracking_hash() != v3.dep_tracking_hash());
+        assert!(v2.dep_tracking_hash() != v3.dep_tracking_hash());
+
+        // Check clone
+        assert_eq!(v1.dep_tracking_hash(), v1.clone().dep_tracking_hash());
+        assert_eq!(v2.dep_tracking_hash(), v2.clone().dep_tracking_hash());
+        assert_eq!(v3.dep_tracking_hash(), v3.clone().dep_tracking_hash());
+    }
+
     #[test]
     fn test_search_paths_tracking_hash_different_order() {
         let mut v1 = super::basic_options();
@@ -3072,6 +3153,8 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.codegen_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_stack_sizes = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.borrowck_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.meta_stats = true;
@@ -3118,12 +3201,18 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.print_mono_items = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_mono_bloat = Some(String::from("abc"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir_dir = String::from("abc");
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir_graphviz = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_dataflow = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_dataflow_errors = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
 
         // Make sure changing a [TRACKED] option changes the hash
         opts = reference.clone();
@@ -3174,6 +3263,10 @@ mod tests {
         opts.debugging_opts.mir_opt_level = 3;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
 
+        opts = reference.clone();
+        opts.debugging_opts.stack_size_limit = 1024;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
         opts = reference.clone();
         opts.debugging_opts.relro_level = Some(RelroLevel::Full);
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
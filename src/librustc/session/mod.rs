@@ -8,8 +8,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-pub use self::code_stats::{CodeStats, DataTypeKind, FieldInfo};
-pub use self::code_stats::{SizeKind, TypeSizeInfo, VariantInfo};
+pub use self::code_stats::{CodeStats, DataTypeKind, FieldInfo, MonoItemInfo};
+pub use self::code_stats::{SizeKind, StackFrameInfo, TypeSizeInfo, VariantInfo};
 
 use hir::def_id::CrateNum;
 use ich::Fingerprint;
@@ -25,6 +25,7 @@ use ty::tls;
 use util::nodemap::{FxHashMap, FxHashSet};
 use util::common::{duration_to_secs_str, ErrorReported};
 use util::common::ProfileQueriesMsg;
+use util::profiling::SelfProfiler;
 
 use rustc_data_structures::sync::{self, Lrc, Lock, LockCell, OneThread, Once, RwLock};
 
@@ -32,7 +33,9 @@ use syntax::ast::NodeId;
 use errors::{self, DiagnosticBuilder, DiagnosticId};
 use errors::emitter::{Emitter, EmitterWriter};
 use syntax::edition::Edition;
+use syntax::fix::FixEmitter;
 use syntax::json::JsonEmitter;
+use syntax::sarif::SarifEmitter;
 use syntax::feature_gate;
 use syntax::symbol::Symbol;
 use syntax::parse;
@@ -133,6 +136,9 @@ pub struct Session {
     /// Used by -Z profile-queries in util::common
     pub profile_channel: Lock<Option<mpsc::Sender<ProfileQueriesMsg>>>,
 
+    /// The self-profiler, if -Z self-profile is enabled.
+    pub self_profiling: Option<SelfProfiler>,
+
     /// Some measurements that are being gathered during compilation.
     pub perf_stats: PerfStats,
 
@@ -986,8 +992,10 @@ pub fn build_session_with_codemap(
     // normal diagnostic warnings, since the warning lint can also be denied and changed
     // later via the source code.
     let warnings_allow = sopts
-        .lint_opts
+        .lint_config
         .iter()
+        .flat_map(|config| config.lints.iter())
+        .chain(sopts.lint_opts.iter())
         .filter(|&&(ref key, _)| *key == "warnings")
         .map(|&(_, ref level)| *level == lint::Allow)
         .last()
@@ -1036,7 +1044,29 @@ pub fn build_session_with_codemap(
             (config::ErrorOutputType::Short(_), Some(dst)) => {
                 Box::new(EmitterWriter::new(dst, Some(codemap.clone()), true, false))
             }
+            (config::ErrorOutputType::Sarif, None) => Box::new(
+                SarifEmitter::stderr(Some(registry), codemap.clone()),
+            ),
+            (config::ErrorOutputType::Sarif, Some(dst)) => Box::new(
+                SarifEmitter::new(dst, Some(registry), codemap.clone()),
+            ),
         };
+    let emitter: Box<dyn Emitter + sync::Send> = if sopts.debugging_opts.apply_suggestions {
+        let summary = match sopts.error_format {
+            config::ErrorOutputType::HumanReadable(_) |
+            config::ErrorOutputType::Short(_) => true,
+            config::ErrorOutputType::Json(_) |
+            config::ErrorOutputType::Sarif => false,
+        };
+        Box::new(FixEmitter::new(
+            emitter,
+            codemap.clone(),
+            local_crate_source_file.as_ref().map(|path| &**path),
+            summary,
+        ))
+    } else {
+        emitter
+    };
 
     let diagnostic_handler = errors::Handler::with_emitter_and_flags(
         emitter,
@@ -1086,6 +1116,12 @@ pub fn build_session_(
     let print_fuel_crate = sopts.debugging_opts.print_fuel.clone();
     let print_fuel = LockCell::new(0);
 
+    let self_profiling = if sopts.debugging_opts.self_profile {
+        Some(SelfProfiler::new())
+    } else {
+        None
+    };
+
     let working_dir = match env::current_dir() {
         Ok(dir) => dir,
         Err(e) => p_s.span_diagnostic
@@ -1126,6 +1162,7 @@ pub fn build_session_(
         incr_comp_session: OneThread::new(RefCell::new(IncrCompSession::NotInitialized)),
         ignored_attr_names: ich::compute_ignored_attr_names(),
         profile_channel: Lock::new(None),
+        self_profiling,
         perf_stats: PerfStats {
             symbol_hash_time: Lock::new(Duration::from_secs(0)),
             decode_def_path_tables_time: Lock::new(Duration::from_secs(0)),
@@ -1223,6 +1260,7 @@ pub fn early_error(output: config::ErrorOutputType, msg: &str) -> ! {
         config::ErrorOutputType::Short(color_config) => {
             Box::new(EmitterWriter::stderr(color_config, None, true, false))
         }
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::basic()),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Fatal);
@@ -1238,6 +1276,7 @@ pub fn early_warn(output: config::ErrorOutputType, msg: &str) {
         config::ErrorOutputType::Short(color_config) => {
             Box::new(EmitterWriter::stderr(color_config, None, true, false))
         }
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::early()),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Warning);
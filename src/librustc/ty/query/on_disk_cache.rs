@@ -117,23 +117,54 @@ impl<'sess> OnDiskCache<'sess> {
     pub fn new(sess: &'sess Session, data: Vec<u8>, start_pos: usize) -> OnDiskCache<'sess> {
         debug_assert!(sess.opts.incremental.is_some());
 
-        // Wrapping in a scope so we can borrow `data`
-        let footer: Footer = {
-            let mut decoder = opaque::Decoder::new(&data[..], start_pos);
-
-            // Decode the *position* of the footer which can be found in the
-            // last 8 bytes of the file.
-            decoder.set_position(data.len() - IntEncodedWithFixedSize::ENCODED_SIZE);
-            let query_result_index_pos = IntEncodedWithFixedSize::decode(&mut decoder)
-                .expect("Error while trying to decode query result index position.")
-                .0 as usize;
-
-            // Decoder the file footer which contains all the lookup tables, etc.
-            decoder.set_position(query_result_index_pos);
-            decode_tagged(&mut decoder, TAG_FILE_FOOTER)
-                .expect("Error while trying to decode query result index position.")
+        let footer = Self::decode_footer(&data, start_pos)
+            .expect("Error while trying to decode query result index position.");
+        Self::with_footer(sess, data, footer)
+    }
+
+    /// Like `new`, for a file which may be truncated or corrupt, for which
+    /// it returns what's wrong with the file instead of panicking.
+    pub fn try_new(sess: &'sess Session,
+                   data: Vec<u8>,
+                   start_pos: usize)
+                   -> Result<OnDiskCache<'sess>, String> {
+        let footer = Self::decode_footer(&data, start_pos)?;
+        Ok(Self::with_footer(sess, data, footer))
+    }
+
+    fn decode_footer(data: &[u8], start_pos: usize) -> Result<Footer, String> {
+        // The *position* of the footer can be found in the last 8 bytes of
+        // the file.
+        let footer_pos_pos = match data.len().checked_sub(IntEncodedWithFixedSize::ENCODED_SIZE) {
+            Some(pos) if pos >= start_pos => pos,
+            _ => return Err("the file is truncated".to_owned()),
         };
+        let mut decoder = opaque::Decoder::new(data, start_pos);
+        decoder.set_position(footer_pos_pos);
+        let footer_pos = IntEncodedWithFixedSize::decode(&mut decoder)?.0 as usize;
+        if footer_pos < start_pos || footer_pos >= footer_pos_pos {
+            return Err(format!("the position of the footer, {}, is outside of the file",
+                               footer_pos));
+        }
 
+        // Decode the file footer which contains all the lookup tables, etc.
+        // Like `decode_tagged`, but its tag and length are checked instead of
+        // asserted.
+        decoder.set_position(footer_pos);
+        let tag = u128::decode(&mut decoder)?;
+        if tag != TAG_FILE_FOOTER {
+            return Err("the footer has the wrong tag".to_owned());
+        }
+        let footer = Footer::decode(&mut decoder)?;
+        let end_pos = decoder.position();
+        let len: u64 = Decodable::decode(&mut decoder)?;
+        if (end_pos - footer_pos) as u64 != len {
+            return Err("the footer has the wrong length".to_owned());
+        }
+        Ok(footer)
+    }
+
+    fn with_footer(sess: &'sess Session, data: Vec<u8>, footer: Footer) -> OnDiskCache<'sess> {
         OnDiskCache {
             serialized_data: data,
             file_index_to_stable_id: footer.file_index_to_stable_id,
@@ -165,6 +196,17 @@ impl<'sess> OnDiskCache<'sess> {
         }
     }
 
+    /// The dep-nodes of the query results cached in the previous session.
+    pub fn cached_query_results<'a>(&'a self)
+                                    -> impl Iterator<Item=SerializedDepNodeIndex> + 'a {
+        self.query_result_index.keys().cloned()
+    }
+
+    /// The number of dep-nodes with diagnostics cached in the previous session.
+    pub fn cached_diagnostics_count(&self) -> usize {
+        self.prev_diagnostics_index.len()
+    }
+
     pub fn serialize<'a, 'tcx, E>(&self,
                                   tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                   encoder: &mut E)
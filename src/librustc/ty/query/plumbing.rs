@@ -25,6 +25,7 @@ use ty::query::job::{QueryJob, QueryResult, QueryInfo};
 use ty::item_path;
 
 use util::common::{profq_msg, ProfileQueriesMsg, QueryMsg};
+use util::profiling::ProfileCategory;
 
 use rustc_data_structures::fx::{FxHashMap};
 use rustc_data_structures::sync::{Lrc, Lock};
@@ -91,6 +92,15 @@ macro_rules! profq_query_msg {
     }}
 }
 
+// If enabled, time an activity for -Z self-profile, until the result is dropped
+macro_rules! self_profile_activity {
+    ($tcx:expr, $category:expr, $name:expr) => {
+        $tcx.sess.self_profiling.as_ref().map(|profiler| {
+            profiler.start_activity($category, $name)
+        })
+    }
+}
+
 /// A type representing the responsibility to execute the job in the `job` field.
 /// This will poison the relevant query if dropped.
 pub(super) struct JobOwner<'a, 'tcx: 'a, Q: QueryDescription<'tcx> + 'a> {
@@ -379,6 +389,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
 
         if dep_node.kind.is_anon() {
             profq_msg!(self, ProfileQueriesMsg::ProviderBegin);
+            let activity = self_profile_activity!(self, ProfileCategory::Query, Q::NAME);
 
             let res = job.start(self, |tcx| {
                 tcx.dep_graph.with_anon_task(dep_node.kind, || {
@@ -386,6 +397,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
                 })
             });
 
+            drop(activity);
             profq_msg!(self, ProfileQueriesMsg::ProviderEnd);
             let ((result, dep_node_index), diagnostics) = res;
 
@@ -402,6 +414,9 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
         if !dep_node.kind.is_input() {
             if let Some(dep_node_index) = self.try_mark_green_and_read(&dep_node) {
                 profq_msg!(self, ProfileQueriesMsg::CacheHit);
+                if let Some(ref profiler) = self.sess.self_profiling {
+                    profiler.record_instant(ProfileCategory::IncrementalCacheHit, Q::NAME);
+                }
                 return self.load_from_disk_and_cache_in_memory::<Q>(key,
                                                                     job,
                                                                     dep_node_index,
@@ -436,6 +451,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
                         self.sess.opts.debugging_opts.incremental_queries {
             let prev_dep_node_index =
                 self.dep_graph.prev_dep_node_index_of(dep_node);
+            let _activity = self_profile_activity!(self, ProfileCategory::IncrementalLoad, Q::NAME);
             let result = Q::try_load_from_disk(self.global_tcx(),
                                                     prev_dep_node_index);
 
@@ -460,6 +476,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             // The diagnostics for this query have already been
             // promoted to the current session during
             // try_mark_green(), so we can ignore them here.
+            let _activity = self_profile_activity!(self, ProfileCategory::Query, Q::NAME);
             let (result, _) = job.start(self, |tcx| {
                 // The dep-graph for this computation is already in
                 // place
@@ -523,6 +540,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
                 key, dep_node);
 
         profq_msg!(self, ProfileQueriesMsg::ProviderBegin);
+        let activity = self_profile_activity!(self, ProfileCategory::Query, Q::NAME);
         let res = job.start(self, |tcx| {
             if dep_node.kind.is_eval_always() {
                 tcx.dep_graph.with_eval_always_task(dep_node,
@@ -536,6 +554,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
                                         Q::compute)
             }
         });
+        drop(activity);
         profq_msg!(self, ProfileQueriesMsg::ProviderEnd);
 
         let ((result, dep_node_index), diagnostics) = res;
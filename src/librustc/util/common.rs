@@ -29,6 +29,7 @@ use dep_graph::{DepNode};
 use proc_macro;
 use lazy_static;
 use session::Session;
+use util::profiling::ProfileCategory;
 
 // The name of the associated type for `Fn` return types
 pub const FN_OUTPUT_NAME: &'static str = "Output";
@@ -162,6 +163,10 @@ pub fn time<T, F>(sess: &Session, what: &str, f: F) -> T where
 pub fn time_ext<T, F>(do_it: bool, sess: Option<&Session>, what: &str, f: F) -> T where
     F: FnOnce() -> T,
 {
+    let _activity = sess.and_then(|sess| sess.self_profiling.as_ref()).map(|profiler| {
+        profiler.start_activity(ProfileCategory::Pass, what.to_owned())
+    });
+
     if !do_it { return f(); }
 
     let old = TIME_DEPTH.with(|slot| {
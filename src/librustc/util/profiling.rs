@@ -0,0 +1,344 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The self-profiler of the compiler, for `-Z self-profile`.
+//!
+//! The profiler records activities, each with a category, a name, the thread
+//! it ran on, and when it started and ended: the passes timed with
+//! `util::common::time`, the executions of query providers, the loading of
+//! query results from the incremental cache, and the work on codegen units,
+//! both on the main thread and on the LLVM worker threads. Incremental cache
+//! hits are recorded as instant events.
+//!
+//! At the end of the compilation the events are written as a Chrome
+//! trace-event file, which can be opened in `chrome://tracing` or any other
+//! viewer of the format, and a summary is printed with the time spent in each
+//! category and the activities taking the most time. Activities nest, and the
+//! summary is in terms of self time, the time of an activity minus the time
+//! of the activities it contains.
+
+use serialize::json::as_json;
+use util::common::duration_to_secs_str;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+/// How many activities are listed in the summary.
+const SUMMARY_ACTIVITIES: usize = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProfileCategory {
+    /// A pass timed with `util::common::time`.
+    Pass,
+    /// The execution of a query provider.
+    Query,
+    /// A query whose result from the previous session was reused.
+    IncrementalCacheHit,
+    /// Loading the result of a query from the incremental cache.
+    IncrementalLoad,
+    /// Codegen of a codegen unit, or LLVM work on a module.
+    Codegen,
+}
+
+impl ProfileCategory {
+    fn name(self) -> &'static str {
+        match self {
+            ProfileCategory::Pass => "pass",
+            ProfileCategory::Query => "query",
+            ProfileCategory::IncrementalCacheHit => "incr-cache-hit",
+            ProfileCategory::IncrementalLoad => "incr-load",
+            ProfileCategory::Codegen => "codegen",
+        }
+    }
+}
+
+struct Event {
+    category: ProfileCategory,
+    name: Cow<'static, str>,
+    /// The index of the thread in `ProfileData::threads`.
+    thread: usize,
+    /// The start, since the start of the profiler.
+    start: Duration,
+    /// `None` for instant events.
+    duration: Option<Duration>,
+}
+
+struct ProfileData {
+    /// The threads of the events so far, with their names.
+    threads: Vec<(ThreadId, Option<String>)>,
+    events: Vec<Event>,
+}
+
+impl ProfileData {
+    fn current_thread(&mut self) -> usize {
+        let thread = thread::current();
+        match self.threads.iter().position(|&(id, _)| id == thread.id()) {
+            Some(index) => index,
+            None => {
+                self.threads.push((thread.id(), thread.name().map(|name| name.to_owned())));
+                self.threads.len() - 1
+            }
+        }
+    }
+}
+
+/// A handle to the profiler, which can be shared between threads.
+#[derive(Clone)]
+pub struct SelfProfiler {
+    start: Instant,
+    data: Arc<Mutex<ProfileData>>,
+}
+
+/// An activity being timed, until this is dropped.
+pub struct ProfilerActivity {
+    profiler: SelfProfiler,
+    category: ProfileCategory,
+    name: Option<Cow<'static, str>>,
+    start: Instant,
+}
+
+impl Drop for ProfilerActivity {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        let start = self.start - self.profiler.start;
+        let name = self.name.take().unwrap();
+        self.profiler.record(self.category, name, start, Some(duration));
+    }
+}
+
+/// The time spent in the activities with the same category and name.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+struct Totals {
+    count: usize,
+    total: Duration,
+    self_time: Duration,
+}
+
+impl SelfProfiler {
+    pub fn new() -> SelfProfiler {
+        SelfProfiler {
+            start: Instant::now(),
+            data: Arc::new(Mutex::new(ProfileData {
+                threads: vec![],
+                events: vec![],
+            })),
+        }
+    }
+
+    /// Start timing an activity on the current thread.
+    pub fn start_activity<N>(&self, category: ProfileCategory, name: N) -> ProfilerActivity
+        where N: Into<Cow<'static, str>>
+    {
+        ProfilerActivity {
+            profiler: self.clone(),
+            category,
+            name: Some(name.into()),
+            start: Instant::now(),
+        }
+    }
+
+    /// Record an event without a duration on the current thread.
+    pub fn record_instant<N>(&self, category: ProfileCategory, name: N)
+        where N: Into<Cow<'static, str>>
+    {
+        let start = self.start.elapsed();
+        self.record(category, name.into(), start, None);
+    }
+
+    fn record(&self,
+              category: ProfileCategory,
+              name: Cow<'static, str>,
+              start: Duration,
+              duration: Option<Duration>) {
+        let mut data = self.data.lock().unwrap();
+        let thread = data.current_thread();
+        data.events.push(Event { category, name, thread, start, duration });
+    }
+
+    /// Write the events so far as a Chrome trace-event file.
+    pub fn write_trace(&self, out: &mut dyn Write) -> io::Result<()> {
+        let data = self.data.lock().unwrap();
+        let pid = process::id();
+
+        writeln!(out, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+        for (tid, &(_, ref name)) in data.threads.iter().enumerate() {
+            let name = match *name {
+                Some(ref name) => name.clone(),
+                None => format!("thread {}", tid),
+            };
+            writeln!(out, "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{},\"tid\":{},\
+                           \"args\":{{\"name\":{}}}}},",
+                     pid, tid, as_json(&name))?;
+        }
+        for event in &data.events {
+            write!(out, "{{\"name\":{},\"cat\":\"{}\",\"pid\":{},\"tid\":{},\"ts\":{}",
+                   as_json(&&*event.name), event.category.name(), pid, event.thread,
+                   micros(event.start))?;
+            match event.duration {
+                Some(duration) => write!(out, ",\"ph\":\"X\",\"dur\":{}}},", micros(duration))?,
+                None => write!(out, ",\"ph\":\"i\",\"s\":\"t\"}},")?,
+            }
+            writeln!(out)?;
+        }
+        // The last event, which makes the commas after the others valid.
+        writeln!(out, "{{\"name\":\"end\",\"ph\":\"i\",\"s\":\"g\",\"pid\":{},\"tid\":0,\
+                       \"ts\":{}}}]}}",
+                 pid, micros(self.start.elapsed()))
+    }
+
+    /// Print the time spent in each category, and the activities with the
+    /// most self time.
+    pub fn print_summary(&self, out: &mut dyn Write) -> io::Result<()> {
+        let data = self.data.lock().unwrap();
+        let totals = totals(&data.events);
+
+        let mut categories: HashMap<ProfileCategory, Totals> = HashMap::new();
+        for (&(category, _), t) in &totals {
+            let c = categories.entry(category).or_insert_with(Totals::default);
+            c.count += t.count;
+            c.total += t.total;
+            c.self_time += t.self_time;
+        }
+        let mut categories = categories.into_iter().collect::<Vec<_>>();
+        categories.sort_by_key(|&(category, _)| category);
+
+        writeln!(out, "self-profile: {}s in total, on {} threads",
+                 duration_to_secs_str(self.start.elapsed()), data.threads.len())?;
+        writeln!(out)?;
+        writeln!(out, "{:<16} {:>10} {:>10}", "category", "self time", "count")?;
+        for (category, t) in categories {
+            writeln!(out, "{:<16} {:>10} {:>10}",
+                     category.name(), duration_to_secs_str(t.self_time), t.count)?;
+        }
+
+        let mut activities = totals.into_iter()
+                                   .filter(|&(_, t)| t.total > Duration::from_secs(0))
+                                   .collect::<Vec<_>>();
+        activities.sort_by(|a, b| b.1.self_time.cmp(&a.1.self_time).then(a.0.cmp(&b.0)));
+        writeln!(out)?;
+        writeln!(out, "{:<16} {:>10} {:>10} {:>10}  {}",
+                 "category", "self time", "total time", "count", "activity")?;
+        for ((category, name), t) in activities.into_iter().take(SUMMARY_ACTIVITIES) {
+            writeln!(out, "{:<16} {:>10} {:>10} {:>10}  {}",
+                     category.name(),
+                     duration_to_secs_str(t.self_time),
+                     duration_to_secs_str(t.total),
+                     t.count,
+                     name)?;
+        }
+        Ok(())
+    }
+}
+
+/// The totals of the events, by category and name.
+fn totals(events: &[Event]) -> HashMap<(ProfileCategory, &str), Totals> {
+    // The self time of each activity is its duration minus the durations of
+    // the activities directly inside it, which are on the same thread, start
+    // after it and end before it does.
+    let mut order = (0..events.len()).filter(|&i| events[i].duration.is_some())
+                                     .collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&events[a], &events[b]);
+        (a.thread, a.start, b.duration).cmp(&(b.thread, b.start, a.duration))
+    });
+    let mut self_times = events.iter()
+                               .map(|event| event.duration.unwrap_or(Duration::from_secs(0)))
+                               .collect::<Vec<_>>();
+    let mut stack: Vec<usize> = vec![];
+    for &i in &order {
+        let event = &events[i];
+        while let Some(&parent) = stack.last() {
+            let parent = &events[parent];
+            let end = parent.start + parent.duration.unwrap();
+            if parent.thread != event.thread || end <= event.start {
+                stack.pop();
+            } else {
+                break;
+            }
+        }
+        if let Some(&parent) = stack.last() {
+            let duration = event.duration.unwrap();
+            self_times[parent] = self_times[parent].checked_sub(duration)
+                                                   .unwrap_or(Duration::from_secs(0));
+        }
+        stack.push(i);
+    }
+
+    let mut totals: HashMap<(ProfileCategory, &str), Totals> = HashMap::new();
+    for (event, self_time) in events.iter().zip(self_times) {
+        let t = totals.entry((event.category, &*event.name)).or_insert_with(Totals::default);
+        t.count += 1;
+        t.total += event.duration.unwrap_or(Duration::from_secs(0));
+        t.self_time += self_time;
+    }
+    totals
+}
+
+/// A duration in microseconds, the unit of trace events.
+fn micros(d: Duration) -> String {
+    format!("{}.{:03}", d.as_secs() * 1_000_000 + (d.subsec_nanos() / 1000) as u64,
+            d.subsec_nanos() % 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{totals, Event, ProfileCategory, Totals};
+    use std::time::Duration;
+
+    fn event(thread: usize, start: u64, duration: Option<u64>, name: &'static str) -> Event {
+        Event {
+            category: ProfileCategory::Query,
+            name: name.into(),
+            thread,
+            start: Duration::from_millis(start),
+            duration: duration.map(Duration::from_millis),
+        }
+    }
+
+    fn totals_of(count: usize, total: u64, self_time: u64) -> Totals {
+        Totals {
+            count,
+            total: Duration::from_millis(total),
+            self_time: Duration::from_millis(self_time),
+        }
+    }
+
+    #[test]
+    fn self_time_excludes_nested_activities() {
+        let events = [
+            event(0, 0, Some(10), "outer"),
+            event(0, 1, Some(3), "inner"),
+            event(0, 5, Some(2), "inner"),
+            event(0, 5, None, "hit"),
+            // Overlaps `outer`, but on another thread.
+            event(1, 2, Some(4), "inner"),
+        ];
+        let totals = totals(&events);
+        assert_eq!(totals[&(ProfileCategory::Query, "outer")], totals_of(1, 10, 5));
+        assert_eq!(totals[&(ProfileCategory::Query, "inner")], totals_of(3, 9, 9));
+        assert_eq!(totals[&(ProfileCategory::Query, "hit")], totals_of(1, 0, 0));
+    }
+
+    #[test]
+    fn activities_with_the_same_start_nest() {
+        let events = [
+            event(0, 0, Some(2), "inner"),
+            event(0, 0, Some(4), "outer"),
+        ];
+        let totals = totals(&events);
+        assert_eq!(totals[&(ProfileCategory::Query, "outer")], totals_of(1, 4, 2));
+        assert_eq!(totals[&(ProfileCategory::Query, "inner")], totals_of(1, 2, 2));
+    }
+}
@@ -24,6 +24,7 @@ use rustc::session::config::{self, OutputFilenames, OutputType, Passes, SomePass
                              AllPasses, Sanitizer, Lto};
 use rustc::session::Session;
 use rustc::util::nodemap::FxHashMap;
+use rustc::util::profiling::{ProfileCategory, SelfProfiler};
 use time_graph::{self, TimeGraph, Timeline};
 use llvm::{self, DiagnosticInfo, PassManager, SMDiagnostic};
 use {CodegenResults, ModuleSource, ModuleCodegen, CompiledModule, ModuleKind};
@@ -365,6 +366,8 @@ pub struct CodegenContext {
     // A reference to the TimeGraph so we can register timings. None means that
     // measuring is disabled.
     time_graph: Option<TimeGraph>,
+    // The self-profiler of the session, if -Z self-profile is enabled
+    self_profiling: Option<SelfProfiler>,
     // The assembler command if no_integrated_as option is enabled, None otherwise
     assembler_cmd: Option<Arc<AssemblerCommand>>,
 }
@@ -1497,6 +1500,7 @@ fn start_executing_work(tcx: TyCtxt,
         coordinator_send,
         diag_emitter: shared_emitter.clone(),
         time_graph,
+        self_profiling: sess.self_profiling.clone(),
         output_filenames: tcx.output_filenames(LOCAL_CRATE),
         regular_module_config: modules_config,
         metadata_module_config: metadata_config,
@@ -2008,6 +2012,9 @@ fn spawn_work(cgcx: CodegenContext, work: WorkItem) {
         // as a diagnostic was already sent off to the main thread - just
         // surface that there was an error in this worker.
         bomb.result = {
+            let _activity = cgcx.self_profiling.as_ref().map(|profiler| {
+                profiler.start_activity(ProfileCategory::Codegen, work.name())
+            });
             let timeline = cgcx.time_graph.as_ref().map(|tg| {
                 tg.start(time_graph::TimelineId(cgcx.worker),
                          LLVM_WORK_PACKAGE_KIND,
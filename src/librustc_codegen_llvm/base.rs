@@ -45,8 +45,9 @@ use rustc::dep_graph::{DepNode, DepConstructor};
 use rustc::middle::cstore::{self, LinkMeta, LinkagePreference};
 use rustc::middle::exported_symbols;
 use rustc::util::common::{time, print_time_passes_entry};
+use rustc::util::profiling::ProfileCategory;
 use rustc::session::config::{self, NoDebugInfo};
-use rustc::session::Session;
+use rustc::session::{MonoItemInfo, Session};
 use rustc_incremental;
 use allocator;
 use mir::place::PlaceRef;
@@ -54,7 +55,7 @@ use attributes;
 use builder::{Builder, MemFlags};
 use callee;
 use common::{C_bool, C_bytes_in_context, C_i32, C_usize};
-use rustc_mir::monomorphize::collector::{self, MonoItemCollectionMode};
+use rustc_mir::monomorphize::collector::{self, InliningMap, MonoItemCollectionMode};
 use rustc_mir::monomorphize::item::DefPathBasedNames;
 use common::{self, C_struct_in_context, C_array, val_ty};
 use consts;
@@ -75,6 +76,7 @@ use CrateInfo;
 use rustc_data_structures::sync::Lrc;
 
 use std::any::Any;
+use std::collections::BTreeSet;
 use std::ffi::CString;
 use std::sync::Arc;
 use std::time::{Instant, Duration};
@@ -523,7 +525,24 @@ pub fn codegen_instance<'a, 'tcx>(cx: &CodegenCx<'a, 'tcx>, instance: Instance<'
     }
 
     let mir = cx.tcx.instance_mir(instance.def);
+    let istart = cx.stats.borrow().n_llvm_insns;
+    cx.frame_size.set(0);
     mir::codegen_mir(cx, lldecl, &mir, instance, sig);
+
+    if cx.sess().opts.debugging_opts.print_stack_sizes {
+        let mut instance_name = String::new();
+        DefPathBasedNames::new(cx.tcx, true, true)
+            .push_instance_as_string(instance, &mut instance_name);
+        let symbol_name = cx.tcx.symbol_name(instance).as_str().to_string();
+        cx.sess().code_stats.borrow_mut()
+            .record_stack_frame(instance_name, symbol_name, cx.frame_size.get());
+    }
+    if cx.sess().opts.debugging_opts.print_mono_bloat.is_some() {
+        let symbol_name = cx.tcx.symbol_name(instance).as_str().to_string();
+        let mut stats = cx.stats.borrow_mut();
+        let insns = stats.n_llvm_insns - istart;
+        stats.symbol_stats.push((symbol_name, insns));
+    }
 }
 
 pub fn set_link_section(llval: &Value, attrs: &CodegenFnAttrs) {
@@ -881,6 +900,10 @@ pub fn codegen_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                         llmod_id,
                     };
                     tcx.dep_graph.mark_loaded_from_cache(dep_node_index, true);
+                    if let Some(ref profiler) = tcx.sess.self_profiling {
+                        profiler.record_instant(ProfileCategory::IncrementalCacheHit,
+                                                format!("reuse {}", cgu.name()));
+                    }
                     write::submit_codegened_module_to_llvm(tcx, module, 0);
                     // Continue to next cgu, this one is done.
                     continue
@@ -896,6 +919,9 @@ pub fn codegen_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                              write::CODEGEN_WORK_PACKAGE_KIND,
                              &format!("codegen {}", cgu.name()))
         });
+        let _activity = tcx.sess.self_profiling.as_ref().map(|profiler| {
+            profiler.start_activity(ProfileCategory::Codegen, format!("codegen {}", cgu.name()))
+        });
         let start_time = Instant::now();
         all_stats.extend(tcx.compile_codegen_unit(*cgu.name()));
         total_codegen_time += start_time.elapsed();
@@ -938,6 +964,13 @@ pub fn codegen_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         }
     }
 
+    if tcx.sess.opts.debugging_opts.print_mono_bloat.is_some() {
+        let mut code_stats = tcx.sess.code_stats.borrow_mut();
+        for (symbol, insns) in all_stats.symbol_stats.drain(..) {
+            code_stats.record_mono_item_llvm_size(&symbol, insns);
+        }
+    }
+
     ongoing_codegen.check_for_errors(tcx.sess);
 
     assert_and_save_dep_graph(tcx);
@@ -954,6 +987,60 @@ fn assert_and_save_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
          || rustc_incremental::save_dep_graph(tcx));
 }
 
+/// Record the mono items, with their sizes and the crates which triggered
+/// them, for `-Z print-mono-bloat`. Their LLVM sizes are recorded as they
+/// are codegened.
+fn record_mono_bloat<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                               items: &FxHashSet<MonoItem<'tcx>>,
+                               inlining_map: &InliningMap<'tcx>,
+                               codegen_units: &[Arc<CodegenUnit<'tcx>>]) {
+    let def_id = |item: MonoItem<'tcx>| match item {
+        MonoItem::Fn(instance) => instance.def_id(),
+        MonoItem::Static(def_id) => def_id,
+        MonoItem::GlobalAsm(node_id) => tcx.hir.local_def_id(node_id),
+    };
+
+    let mut triggered_by: FxHashMap<MonoItem<'tcx>, BTreeSet<String>> = FxHashMap();
+    inlining_map.iter_accesses(|accessor, accessed| {
+        let krate = tcx.crate_name(def_id(accessor).krate).to_string();
+        for &item in accessed {
+            triggered_by.entry(item).or_insert_with(BTreeSet::new).insert(krate.clone());
+        }
+    });
+
+    let mut copies: FxHashMap<MonoItem<'tcx>, usize> = FxHashMap();
+    for cgu in codegen_units {
+        for &item in cgu.items().keys() {
+            *copies.entry(item).or_insert(0) += 1;
+        }
+    }
+
+    let names = DefPathBasedNames::new(tcx, true, false);
+    let mut code_stats = tcx.sess.code_stats.borrow_mut();
+    for &item in items {
+        let mut definition = String::new();
+        names.push_def_path(def_id(item), &mut definition);
+        let instance = match item {
+            MonoItem::Fn(instance) => {
+                let mut output = String::new();
+                names.push_instance_as_string(instance, &mut output);
+                output
+            }
+            MonoItem::Static(_) => definition.clone(),
+            MonoItem::GlobalAsm(_) => item.to_string(tcx),
+        };
+        code_stats.record_mono_item(MonoItemInfo {
+            definition,
+            instance,
+            symbol: item.symbol_name(tcx).to_string(),
+            mir_size: item.size_estimate(&tcx),
+            llvm_size: None,
+            copies: copies.get(&item).cloned().unwrap_or(0),
+            triggered_by: triggered_by.remove(&item).unwrap_or_default(),
+        });
+    }
+}
+
 fn collect_and_partition_mono_items<'a, 'tcx>(
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
     cnum: CrateNum,
@@ -1020,6 +1107,10 @@ fn collect_and_partition_mono_items<'a, 'tcx>(
         }
     }).collect();
 
+    if tcx.sess.opts.debugging_opts.print_mono_bloat.is_some() {
+        record_mono_bloat(tcx, &items, &inlining_map, &codegen_units);
+    }
+
     if tcx.sess.opts.debugging_opts.print_mono_items.is_some() {
         let mut item_to_cgus = FxHashMap();
 
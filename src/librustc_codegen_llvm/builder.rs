@@ -105,7 +105,8 @@ impl Builder<'a, 'll, 'tcx> {
     }
 
     fn count_insn(&self, category: &str) {
-        if self.cx.sess().codegen_stats() {
+        if self.cx.sess().codegen_stats() ||
+           self.cx.sess().opts.debugging_opts.print_mono_bloat.is_some() {
             self.cx.stats.borrow_mut().n_llvm_insns += 1;
         }
         if self.cx.sess().count_llvm_insns() {
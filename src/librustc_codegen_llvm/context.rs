@@ -53,6 +53,9 @@ pub struct CodegenCx<'a, 'tcx: 'a> {
     pub llmod: &'a llvm::Module,
     pub llcx: &'a llvm::Context,
     pub stats: RefCell<Stats>,
+    /// The bytes of stack allocated so far by the function being codegened,
+    /// for `-Z print-stack-sizes`
+    pub frame_size: Cell<u64>,
     pub codegen_unit: Arc<CodegenUnit<'tcx>>,
 
     /// Cache instances of monomorphic and polymorphic items
@@ -290,6 +293,7 @@ impl<'a, 'tcx> CodegenCx<'a, 'tcx> {
             llmod,
             llcx,
             stats: RefCell::new(Stats::default()),
+            frame_size: Cell::new(0),
             codegen_unit,
             instances: RefCell::new(FxHashMap()),
             vtables: RefCell::new(FxHashMap()),
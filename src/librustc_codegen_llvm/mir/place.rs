@@ -78,6 +78,10 @@ impl PlaceRef<'ll, 'tcx> {
                   -> PlaceRef<'ll, 'tcx> {
         debug!("alloca({:?}: {:?})", name, layout);
         let tmp = bx.alloca(layout.llvm_type(bx.cx), name, layout.align);
+        if bx.sess().opts.debugging_opts.print_stack_sizes {
+            let offset = Size::from_bytes(bx.cx.frame_size.get()).abi_align(layout.align);
+            bx.cx.frame_size.set((offset + layout.size).bytes());
+        }
         Self::new_sized(tmp, layout, layout.align)
     }
 
@@ -346,12 +346,22 @@ pub fn compile_input(
         sess.code_stats.borrow().print_type_sizes();
     }
 
+    if sess.opts.debugging_opts.print_stack_sizes {
+        write_stack_sizes(sess, &outputs);
+    }
+
+    if let Some(ref format) = sess.opts.debugging_opts.print_mono_bloat {
+        print_mono_bloat(sess, format);
+    }
+
     codegen_backend.join_codegen_and_link(ongoing_codegen, sess, &dep_graph, &outputs)?;
 
     if sess.opts.debugging_opts.perf_stats {
         sess.print_perf_stats();
     }
 
+    write_self_profile(sess, &outputs);
+
     controller_entry_point!(
         compilation_done,
         sess,
@@ -917,6 +927,26 @@ where
             ls.register_group(Some(sess), true, name, to);
         }
 
+        // The groups of the lint configuration file can use the groups of
+        // plugins, and the groups defined before them in the file.
+        if let Some(ref config) = sess.opts.lint_config {
+            for &(ref name, ref lints) in &config.groups {
+                if ls.find_lints(name).is_ok() {
+                    sess.err(&format!("lint group `{}` of the lint configuration file has \
+                                       the name of an existing lint or lint group", name));
+                    continue;
+                }
+                // Unknown lints are reported when the lint levels are computed.
+                let to = lints.iter()
+                    .flat_map(|lint| ls.find_lints(lint).unwrap_or_default())
+                    .collect();
+                // The lint store lives as long as the process, so the few
+                // names of the file's groups are leaked on purpose.
+                let name: &'static str = Box::leak(name.clone().into_boxed_str());
+                ls.register_group(Some(sess), true, name, to);
+            }
+        }
+
         *sess.plugin_llvm_passes.borrow_mut() = llvm_passes;
         *sess.plugin_attributes.borrow_mut() = attributes.clone();
     })?;
@@ -1447,13 +1477,18 @@ fn write_out_deps(sess: &Session, outputs: &OutputFilenames, out_filenames: &[Pa
     let result = (|| -> io::Result<()> {
         // Build a list of files used to compile the output and
         // write Makefile-compatible dependency rules
-        let files: Vec<String> = sess.codemap()
+        let mut files: Vec<String> = sess.codemap()
             .files()
             .iter()
             .filter(|fmap| fmap.is_real_file())
             .filter(|fmap| !fmap.is_imported())
             .map(|fmap| escape_dep_filename(&fmap.name))
             .collect();
+        // The lint configuration file isn't in the codemap, as it's read
+        // while parsing the options.
+        if let Some(ref config) = sess.opts.lint_config {
+            files.push(escape_dep_filename(&FileName::Real(config.path.clone())));
+        }
         let mut file = fs::File::create(&deps_filename)?;
         for path in out_filenames {
             write!(file, "{}: {}\n\n", path.display(), files.join(" "))?;
@@ -1480,6 +1515,69 @@ fn write_out_deps(sess: &Session, outputs: &OutputFilenames, out_filenames: &[Pa
     }
 }
 
+fn write_self_profile(sess: &Session, outputs: &OutputFilenames) {
+    // Write out the trace-event file and the summary of -Z self-profile
+    let profiler = match sess.self_profiling {
+        Some(ref profiler) => profiler,
+        None => return,
+    };
+    let trace_filename = outputs.with_extension("trace.json");
+
+    let result = fs::File::create(&trace_filename).and_then(|file| {
+        let mut file = io::BufWriter::new(file);
+        profiler.write_trace(&mut file)?;
+        file.flush()
+    });
+    if let Err(e) = result {
+        sess.err(&format!(
+            "error writing self-profile trace to `{}`: {}",
+            trace_filename.display(),
+            e
+        ));
+    }
+
+    let stdout = io::stdout();
+    drop(profiler.print_summary(&mut stdout.lock()));
+}
+
+fn write_stack_sizes(sess: &Session, outputs: &OutputFilenames) {
+    // Print the stack frame sizes of -Z print-stack-sizes, and write them out
+    let code_stats = sess.code_stats.borrow();
+    code_stats.print_stack_sizes();
+
+    let filename = outputs.with_extension("stack-sizes.json");
+    let result = fs::File::create(&filename).and_then(|file| {
+        let mut file = io::BufWriter::new(file);
+        code_stats.write_stack_sizes(&mut file)?;
+        file.flush()
+    });
+    if let Err(e) = result {
+        sess.err(&format!(
+            "error writing stack sizes to `{}`: {}",
+            filename.display(),
+            e
+        ));
+    }
+}
+
+fn print_mono_bloat(sess: &Session, format: &str) {
+    // Print the mono items of -Z print-mono-bloat, as a table or as JSON
+    let code_stats = sess.code_stats.borrow();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let result = match format.trim() {
+        "json" => code_stats.write_mono_bloat_json(&mut out),
+        format => {
+            if format != "table" {
+                sess.warn(&format!("unknown `-Z print-mono-bloat` format `{}`, \
+                                    falling back to `table`", format));
+            }
+            code_stats.print_mono_bloat(&mut out)
+        }
+    };
+    drop(result);
+}
+
 pub fn collect_crate_types(session: &Session, attrs: &[ast::Attribute]) -> Vec<config::CrateType> {
     // Unconditionally collect crate types from attributes to make them used
     let attr_types: Vec<config::CrateType> = attrs
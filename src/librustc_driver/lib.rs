@@ -104,6 +104,7 @@ use std::str;
 use std::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
 use std::sync::{Once, ONCE_INIT};
 use std::thread;
+use std::time::Duration;
 
 use syntax::ast;
 use syntax::codemap::{CodeMap, FileLoader, RealFileLoader};
@@ -868,7 +869,7 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
                     None,
                     odir,
                     ofile
-                );
+                ).and_then(|| RustcDefaultCalls::manage_incremental_sessions(&sess));
 
                 if should_stop == Compilation::Stop {
                     return None;
@@ -891,6 +892,7 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
                      -> Compilation {
         RustcDefaultCalls::print_crate_info(codegen_backend, sess, Some(input), odir, ofile)
             .and_then(|| RustcDefaultCalls::list_metadata(sess, cstore, matches, input))
+            .and_then(|| RustcDefaultCalls::manage_incremental_sessions(sess))
     }
 
     fn build_controller(self: Box<Self>,
@@ -1017,6 +1019,41 @@ impl RustcDefaultCalls {
         return Compilation::Continue;
     }
 
+    /// Handles `-Z incremental-sessions`, `-Z incremental-gc-age` and
+    /// `-Z incremental-gc-size`, which work on the whole incremental directory
+    /// instead of compiling.
+    fn manage_incremental_sessions(sess: &Session) -> Compilation {
+        let opts = &sess.opts.debugging_opts;
+        let gc = opts.incremental_gc_age.is_some() || opts.incremental_gc_size.is_some();
+        if !opts.incremental_sessions && !gc {
+            return Compilation::Continue;
+        }
+        let incr_dir = match sess.opts.incremental {
+            Some(ref incr_dir) => incr_dir,
+            None => early_error(sess.opts.error_format,
+                                "-Z incremental-sessions and -Z incremental-gc-* need \
+                                 an incremental compilation directory, given with \
+                                 -C incremental"),
+        };
+
+        let mut result = Ok(());
+        if gc {
+            let max_age = opts.incremental_gc_age.map(|days| {
+                Duration::from_secs(days as u64 * 24 * 60 * 60)
+            });
+            let max_size = opts.incremental_gc_size.map(|mib| mib as u64 * 1024 * 1024);
+            result = rustc_incremental::garbage_collect_incremental_dir(sess, max_age, max_size);
+        }
+        if opts.incremental_sessions {
+            result = result.and_then(|()| rustc_incremental::print_incremental_sessions(sess));
+        }
+        if let Err(e) = result {
+            early_error(sess.opts.error_format,
+                        &format!("failed to read the incremental compilation directory `{}`: {}",
+                                 incr_dir.display(), e));
+        }
+        Compilation::Stop
+    }
 
     fn print_crate_info(codegen_backend: &dyn CodegenBackend,
                         sess: &Session,
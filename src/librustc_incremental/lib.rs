@@ -46,3 +46,5 @@ pub use persist::prepare_session_directory;
 pub use persist::finalize_session_directory;
 pub use persist::delete_workproduct_files;
 pub use persist::garbage_collect_session_directories;
+pub use persist::garbage_collect_incremental_dir;
+pub use persist::print_incremental_sessions;
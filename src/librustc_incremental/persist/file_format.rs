@@ -19,6 +19,7 @@
 //! compiler versions don't change frequently for the typical user, being
 //! conservative here practically has no downside.
 
+use std::fmt;
 use std::io::{self, Read};
 use std::path::Path;
 use std::fs;
@@ -49,6 +50,25 @@ pub fn write_file_header(stream: &mut Encoder) {
     stream.emit_raw_bytes(rustc_version.as_bytes());
 }
 
+/// Why the header of a file doesn't match the one this compiler would write.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeaderMismatch {
+    WrongFileMagic,
+    WrongHeaderFormatVersion,
+    /// A different compiler version, whose version string is given.
+    DifferentCompilerVersion(String),
+}
+
+impl fmt::Display for HeaderMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HeaderMismatch::WrongFileMagic => write!(f, "Wrong FILE_MAGIC"),
+            HeaderMismatch::WrongHeaderFormatVersion => write!(f, "Wrong HEADER_FORMAT_VERSION"),
+            HeaderMismatch::DifferentCompilerVersion(_) => write!(f, "Different compiler version"),
+        }
+    }
+}
+
 /// Reads the contents of a file with a file header as defined in this module.
 ///
 /// - Returns `Ok(Some(data, pos))` if the file existed and was generated by a
@@ -65,8 +85,20 @@ pub fn read_file(report_incremental_info: bool, path: &Path)
         return Ok(None);
     }
 
-    let data = fs::read(path)?;
+    match check_file_header(fs::read(path)?)? {
+        Ok(data_and_pos) => Ok(Some(data_and_pos)),
+        Err(mismatch) => {
+            report_format_mismatch(report_incremental_info, path, &mismatch.to_string());
+            Ok(None)
+        }
+    }
+}
 
+/// Checks the header of the contents of a file, like `read_file`, but tells
+/// why the file can't be used by this compiler if it can't.
+pub fn check_file_header(data: Vec<u8>)
+    -> io::Result<Result<(Vec<u8>, usize), HeaderMismatch>>
+{
     let mut file = io::Cursor::new(data);
 
     // Check FILE_MAGIC
@@ -75,8 +107,7 @@ pub fn read_file(report_incremental_info: bool, path: &Path)
         let mut file_magic = [0u8; 4];
         file.read_exact(&mut file_magic)?;
         if file_magic != FILE_MAGIC {
-            report_format_mismatch(report_incremental_info, path, "Wrong FILE_MAGIC");
-            return Ok(None)
+            return Ok(Err(HeaderMismatch::WrongFileMagic))
         }
     }
 
@@ -89,8 +120,7 @@ pub fn read_file(report_incremental_info: bool, path: &Path)
                                     ((header_format_version[1] as u16) << 8);
 
         if header_format_version != HEADER_FORMAT_VERSION {
-            report_format_mismatch(report_incremental_info, path, "Wrong HEADER_FORMAT_VERSION");
-            return Ok(None)
+            return Ok(Err(HeaderMismatch::WrongHeaderFormatVersion))
         }
     }
 
@@ -104,13 +134,13 @@ pub fn read_file(report_incremental_info: bool, path: &Path)
         file.read_exact(&mut buffer)?;
 
         if buffer != rustc_version().as_bytes() {
-            report_format_mismatch(report_incremental_info, path, "Different compiler version");
-            return Ok(None);
+            let version = String::from_utf8_lossy(&buffer).into_owned();
+            return Ok(Err(HeaderMismatch::DifferentCompilerVersion(version)));
         }
     }
 
     let post_header_start_pos = file.position() as usize;
-    Ok(Some((file.into_inner(), post_header_start_pos)))
+    Ok(Ok((file.into_inner(), post_header_start_pos)))
 }
 
 fn report_format_mismatch(report_incremental_info: bool, file: &Path, message: &str) {
@@ -129,9 +129,9 @@ use std::time::{UNIX_EPOCH, SystemTime, Duration};
 use rand::{thread_rng, Rng};
 
 const LOCK_FILE_EXT: &'static str = ".lock";
-const DEP_GRAPH_FILENAME: &'static str = "dep-graph.bin";
-const WORK_PRODUCTS_FILENAME: &'static str = "work-products.bin";
-const QUERY_CACHE_FILENAME: &'static str = "query-cache.bin";
+pub(super) const DEP_GRAPH_FILENAME: &'static str = "dep-graph.bin";
+pub(super) const WORK_PRODUCTS_FILENAME: &'static str = "work-products.bin";
+pub(super) const QUERY_CACHE_FILENAME: &'static str = "query-cache.bin";
 
 // We encode integers using the following base, so they are shorter than decimal
 // or hexadecimal numbers (we want short file and directory names). Since these
@@ -566,11 +566,11 @@ fn find_source_directory_in_iter<I>(iter: I,
     best_candidate.1
 }
 
-fn is_finalized(directory_name: &str) -> bool {
+pub(super) fn is_finalized(directory_name: &str) -> bool {
     !directory_name.ends_with("-working")
 }
 
-fn is_session_directory(directory_name: &str) -> bool {
+pub(super) fn is_session_directory(directory_name: &str) -> bool {
     directory_name.starts_with("s-") &&
     !directory_name.ends_with(LOCK_FILE_EXT)
 }
@@ -579,8 +579,8 @@ fn is_session_directory_lock_file(file_name: &str) -> bool {
     file_name.starts_with("s-") && file_name.ends_with(LOCK_FILE_EXT)
 }
 
-fn extract_timestamp_from_session_dir(directory_name: &str)
-                                      -> Result<SystemTime, ()> {
+pub(super) fn extract_timestamp_from_session_dir(directory_name: &str)
+                                                 -> Result<SystemTime, ()> {
     if !is_session_directory(directory_name) {
         return Err(())
     }
@@ -639,7 +639,7 @@ fn assert_no_characters_lost(s: &str) {
     }
 }
 
-fn is_old_enough_to_be_collected(timestamp: SystemTime) -> bool {
+pub(super) fn is_old_enough_to_be_collected(timestamp: SystemTime) -> bool {
     timestamp < SystemTime::now() - Duration::from_secs(10)
 }
 
@@ -885,7 +885,7 @@ fn all_except_most_recent(deletion_candidates: Vec<(SystemTime, PathBuf, Option<
 /// to circumvent this limitation, we canonicalize the path of the directory
 /// before passing it to std::fs::remove_dir_all(). This will convert the path
 /// into the '\\?\' format, which supports much longer paths.
-fn safe_remove_dir_all(p: &Path) -> io::Result<()> {
+pub(super) fn safe_remove_dir_all(p: &Path) -> io::Result<()> {
     if p.exists() {
         let canonicalized = try!(p.canonicalize());
         std_fs::remove_dir_all(canonicalized)
@@ -894,7 +894,7 @@ fn safe_remove_dir_all(p: &Path) -> io::Result<()> {
     }
 }
 
-fn safe_remove_file(p: &Path) -> io::Result<()> {
+pub(super) fn safe_remove_file(p: &Path) -> io::Result<()> {
     if p.exists() {
         let canonicalized = try!(p.canonicalize());
         std_fs::remove_file(canonicalized)
@@ -0,0 +1,437 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Inspection and garbage collection of a whole incremental compilation
+//! directory, for `-Z incremental-sessions`, `-Z incremental-gc-age` and
+//! `-Z incremental-gc-size`.
+//!
+//! The directory has a directory for each crate compiled into it, and each of
+//! those has the session directories of the crate, as described in `fs.rs`.
+//! The compiler only ever looks at the sessions of the crate it compiles, and
+//! only cleans up the sessions it knows to be obsolete. These functions look
+//! at all of them: what's in each session, and whether the next compilation
+//! of its crate would start from it. They also delete sessions by age or to
+//! fit a size budget, which the compiler never does on its own.
+//!
+//! Whether the next compilation reuses what's in a session also depends on its
+//! command-line arguments, which aren't known here, so only what's wrong with
+//! the files of the session is reported.
+//!
+//! Finalized sessions share their files with the sessions they were copied
+//! from through hard links where possible, so the sizes reported here, which
+//! count every file of every session, are an upper bound of the disk space
+//! the sessions take.
+
+use rustc::dep_graph::SerializedDepGraph;
+use rustc::session::Session;
+use rustc::ty::query::OnDiskCache;
+use rustc_data_structures::flock;
+use rustc_serialize::Decodable as RustcDecodable;
+use rustc_serialize::opaque::Decoder;
+
+use std::collections::BTreeMap;
+use std::fs as std_fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::data::SerializedWorkProduct;
+use super::file_format::{self, HeaderMismatch};
+use super::fs::*;
+
+struct SessionDirectory {
+    path: PathBuf,
+    name: String,
+    timestamp: SystemTime,
+    finalized: bool,
+    size: u64,
+}
+
+/// The crate directories in `incr_dir`, with their sessions, newest first.
+fn crate_directories(incr_dir: &Path) -> io::Result<Vec<(PathBuf, Vec<SessionDirectory>)>> {
+    let mut crates = vec![];
+    for entry in incr_dir.read_dir()? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            let sessions = session_directories(&entry.path())?;
+            crates.push((entry.path(), sessions));
+        }
+    }
+    crates.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(crates)
+}
+
+fn session_directories(crate_dir: &Path) -> io::Result<Vec<SessionDirectory>> {
+    let mut sessions = vec![];
+    for entry in crate_dir.read_dir()? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !entry.file_type()?.is_dir() {
+            continue
+        }
+        // Directories with malformed names aren't ours.
+        let timestamp = match extract_timestamp_from_session_dir(&name) {
+            Ok(timestamp) => timestamp,
+            Err(()) => continue,
+        };
+        sessions.push(SessionDirectory {
+            path: entry.path(),
+            finalized: is_finalized(&name),
+            size: directory_size(&entry.path())?,
+            name,
+            timestamp,
+        });
+    }
+    sessions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    Ok(sessions)
+}
+
+fn directory_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in path.read_dir()? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            directory_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Lock the session exclusively, if no running compilation uses it. Sessions
+/// without a lock file can always be collected.
+fn try_lock(session: &SessionDirectory) -> Option<Option<flock::Lock>> {
+    let lock_file_path = lock_file_path(&session.path);
+    if !lock_file_path.exists() {
+        return Some(None);
+    }
+    flock::Lock::new(&lock_file_path,
+                     false,  // don't wait
+                     false,  // don't create the lock-file
+                     true)   // get an exclusive lock
+        .ok()
+        .map(Some)
+}
+
+/// Print what's in every session of every crate in the incremental
+/// directory, and why it would or wouldn't be used.
+pub fn print_incremental_sessions(sess: &Session) -> io::Result<()> {
+    let incr_dir = sess.opts.incremental.as_ref().unwrap();
+    let crates = crate_directories(incr_dir)?;
+
+    let sessions = crates.iter().map(|&(_, ref sessions)| sessions.len()).sum::<usize>();
+    let size = crates.iter()
+                     .flat_map(|&(_, ref sessions)| sessions)
+                     .map(|session| session.size)
+                     .sum();
+    println!("incremental directory `{}`: {} crate{}, {} session{}, {}",
+             incr_dir.display(),
+             crates.len(), if crates.len() == 1 { "" } else { "s" },
+             sessions, if sessions == 1 { "" } else { "s" },
+             format_size(size));
+
+    for &(ref crate_dir, ref sessions) in &crates {
+        let size = sessions.iter().map(|session| session.size).sum();
+        println!();
+        println!("{} ({})",
+                 crate_dir.file_name().unwrap().to_string_lossy(),
+                 format_size(size));
+
+        // The session the next compilation of the crate copies.
+        let current = sessions.iter().find(|session| session.finalized);
+
+        for session in sessions {
+            println!("  {}: {}, {} old, {}",
+                     session.name,
+                     if session.finalized { "finalized" } else { "working" },
+                     format_age(session.timestamp),
+                     format_size(session.size));
+
+            // Working sessions may be in the middle of being written.
+            let usable = if session.finalized {
+                print_session_contents(sess, session)
+            } else {
+                Ok(())
+            };
+            let status = if !session.finalized {
+                match try_lock(session) {
+                    None => "in use by a running compilation".to_owned(),
+                    Some(_) => "abandoned by a compilation that failed or was interrupted; \
+                                deleted by the next compilation of the crate".to_owned(),
+                }
+            } else if !ptr_eq(current, session) {
+                format!("superseded by {}; deleted by the next compilation of the crate",
+                        current.unwrap().name)
+            } else {
+                match usable {
+                    Ok(()) => "current; the next compilation starts from it".to_owned(),
+                    Err(reason) => format!("unusable, {}; the next compilation starts \
+                                            from scratch", reason),
+                }
+            };
+            println!("    status: {}", status);
+        }
+    }
+    Ok(())
+}
+
+fn ptr_eq(a: Option<&SessionDirectory>, b: &SessionDirectory) -> bool {
+    a.map_or(false, |a| a as *const _ == b as *const _)
+}
+
+/// Print the dep-graph, the query cache and the work products of `session`.
+/// Returns why the next compilation can't use the session, if its dep-graph
+/// is missing or can't be read, or if its query cache can't be decoded or
+/// doesn't match the dep-graph.
+fn print_session_contents(sess: &Session, session: &SessionDirectory) -> Result<(), String> {
+    let mut usable = Ok(());
+
+    let dep_graph_path = in_incr_comp_dir(&session.path, DEP_GRAPH_FILENAME);
+    let dep_graph = match read_file(&dep_graph_path) {
+        Ok((data, start_pos)) => {
+            let mut decoder = Decoder::new(&data, start_pos);
+            // The hash of the command-line arguments comes first, which only
+            // the compilation using the session can compare with its own.
+            let decoded = u64::decode(&mut decoder).and_then(|_| {
+                SerializedDepGraph::decode(&mut decoder)
+            });
+            match decoded {
+                Ok(graph) => {
+                    println!("    dep-graph: {} nodes, {} edges, {}",
+                             graph.nodes.len(),
+                             graph.edge_list_data.len(),
+                             format_size(data.len() as u64));
+                    Some(graph)
+                }
+                Err(e) => {
+                    usable = Err(format!("its dep-graph can't be decoded: {}", e));
+                    None
+                }
+            }
+        }
+        Err(reason) => {
+            usable = Err(format!("its dep-graph {}", reason));
+            None
+        }
+    };
+
+    let query_cache_path = in_incr_comp_dir(&session.path, QUERY_CACHE_FILENAME);
+    // Sessions without cached query results have no query cache.
+    if query_cache_path.exists() {
+        let query_cache = read_file(&query_cache_path).and_then(|(data, start_pos)| {
+            let size = data.len() as u64;
+            OnDiskCache::try_new(sess, data, start_pos)
+                .map(|cache| (cache, size))
+                .map_err(|e| format!("can't be decoded: {}", e))
+        });
+        match query_cache {
+            Ok((cache, size)) => {
+                print_query_cache(&cache, size, dep_graph.as_ref(), &mut usable);
+            }
+            Err(reason) => {
+                println!("    query cache: {}", reason);
+                if usable.is_ok() {
+                    usable = Err(format!("its query cache {}", reason));
+                }
+            }
+        }
+    }
+
+    let work_products_path = in_incr_comp_dir(&session.path, WORK_PRODUCTS_FILENAME);
+    if let Ok((data, start_pos)) = read_file(&work_products_path) {
+        let mut decoder = Decoder::new(&data, start_pos);
+        let work_products: Result<Vec<SerializedWorkProduct>, _> =
+            RustcDecodable::decode(&mut decoder);
+        let work_products = match work_products {
+            Ok(work_products) => work_products,
+            Err(e) => {
+                println!("    work products: can't be decoded: {}", e);
+                return usable;
+            }
+        };
+        let files = work_products.iter()
+                                 .flat_map(|swp| &swp.work_product.saved_files)
+                                 .map(|&(_, ref file_name)| in_incr_comp_dir(&session.path,
+                                                                             file_name))
+                                 .collect::<Vec<_>>();
+        let size = files.iter()
+                        .filter_map(|path| path.metadata().ok())
+                        .map(|metadata| metadata.len())
+                        .sum();
+        println!("    work products: {} codegen units, {} files, {}",
+                 work_products.len(), files.len(), format_size(size));
+        for path in files.iter().filter(|path| !path.exists()) {
+            println!("      missing `{}`, whose codegen unit is recompiled",
+                     path.file_name().unwrap().to_string_lossy());
+        }
+    }
+
+    usable
+}
+
+/// Print the number of results in the query cache of a session, by kind if
+/// its dep-graph could be read.
+fn print_query_cache(cache: &OnDiskCache,
+                     size: u64,
+                     dep_graph: Option<&SerializedDepGraph>,
+                     usable: &mut Result<(), String>) {
+    let mut results = 0;
+    let mut unknown_nodes = 0;
+    let mut by_kind = BTreeMap::new();
+    for index in cache.cached_query_results() {
+        results += 1;
+        if let Some(graph) = dep_graph {
+            match graph.nodes.get(index) {
+                Some(node) => *by_kind.entry(format!("{:?}", node.kind)).or_insert(0) += 1,
+                None => unknown_nodes += 1,
+            }
+        }
+    }
+    println!("    query cache: {} results, {} diagnostics, {}",
+             results, cache.cached_diagnostics_count(), format_size(size));
+    let mut by_kind = by_kind.into_iter().collect::<Vec<_>>();
+    by_kind.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    if !by_kind.is_empty() {
+        let kinds = by_kind.iter()
+                           .map(|&(ref kind, count)| format!("{}: {}", kind, count))
+                           .collect::<Vec<_>>();
+        println!("      {}", kinds.join(", "));
+    }
+    if unknown_nodes > 0 {
+        println!("      {} results for nodes missing from the dep-graph", unknown_nodes);
+        if usable.is_ok() {
+            *usable = Err("its query cache doesn't match its dep-graph".to_owned());
+        }
+    }
+}
+
+/// Read a file of a session, or tell why it can't be used.
+fn read_file(path: &Path) -> Result<(Vec<u8>, usize), String> {
+    if !path.exists() {
+        return Err("is missing".to_owned());
+    }
+    let data = std_fs::read(path).map_err(|e| format!("can't be read: {}", e))?;
+    match file_format::check_file_header(data) {
+        Ok(Ok(data_and_pos)) => Ok(data_and_pos),
+        Ok(Err(HeaderMismatch::DifferentCompilerVersion(version))) => {
+            Err(format!("was written by another compiler version, `{}`", version))
+        }
+        Ok(Err(mismatch)) => Err(format!("has an invalid header: {}", mismatch)),
+        Err(e) => Err(format!("can't be read: {}", e)),
+    }
+}
+
+/// Delete the sessions older than `max_age`, then the oldest sessions left
+/// until the incremental directory is at most `max_size` bytes. Sessions in
+/// use by a running compilation are never deleted.
+pub fn garbage_collect_incremental_dir(sess: &Session,
+                                       max_age: Option<Duration>,
+                                       max_size: Option<u64>)
+                                       -> io::Result<()> {
+    let incr_dir = sess.opts.incremental.as_ref().unwrap();
+    let crates = crate_directories(incr_dir)?;
+
+    let mut size: u64 = crates.iter()
+                              .flat_map(|&(_, ref sessions)| sessions)
+                              .map(|session| session.size)
+                              .sum();
+
+    // The sessions that can be deleted, oldest first, with their locks.
+    let mut candidates = vec![];
+    for &(ref crate_dir, ref sessions) in &crates {
+        for session in sessions {
+            if !session.finalized && !is_old_enough_to_be_collected(session.timestamp) {
+                // See `garbage_collect_session_directories`.
+                continue
+            }
+            if let Some(lock) = try_lock(session) {
+                candidates.push((crate_dir, session, lock));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| a.1.timestamp.cmp(&b.1.timestamp));
+
+    let now = SystemTime::now();
+    let mut deleted = 0;
+    let mut freed = 0;
+    for (crate_dir, session, lock) in candidates {
+        let too_old = max_age.map_or(false, |max_age| {
+            now.duration_since(session.timestamp).map_or(false, |age| age > max_age)
+        });
+        let too_big = max_size.map_or(false, |max_size| size > max_size);
+        if !too_old && !too_big {
+            continue
+        }
+
+        if let Err(err) = safe_remove_dir_all(&session.path) {
+            sess.warn(&format!("Failed to garbage collect incremental compilation \
+                                session directory `{}`: {}",
+                               session.path.display(),
+                               err));
+            continue
+        }
+        let _ = safe_remove_file(&lock_file_path(&session.path));
+        drop(lock);
+        // Leave no empty crate directories behind.
+        let _ = std_fs::remove_dir(crate_dir);
+
+        println!("deleted {}/{} ({} old, {})",
+                 crate_dir.file_name().unwrap().to_string_lossy(),
+                 session.name,
+                 format_age(session.timestamp),
+                 format_size(session.size));
+        deleted += 1;
+        freed += session.size;
+        size -= session.size;
+    }
+
+    println!("deleted {} session{}, freed {}, {} left",
+             deleted, if deleted == 1 { "" } else { "s" },
+             format_size(freed),
+             format_size(size));
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn format_age(timestamp: SystemTime) -> String {
+    let secs = SystemTime::now().duration_since(timestamp)
+                                .map(|age| age.as_secs())
+                                .unwrap_or(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+#[test]
+fn test_format_size() {
+    assert_eq!(format_size(0), "0 B");
+    assert_eq!(format_size(1023), "1023 B");
+    assert_eq!(format_size(1024), "1.0 KiB");
+    assert_eq!(format_size(1536), "1.5 KiB");
+    assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+}
@@ -211,7 +211,13 @@ pub fn load_query_result_cache<'sess>(sess: &'sess Session) -> OnDiskCache<'sess
     }
 
     match load_data(sess.opts.debugging_opts.incremental_info, &query_cache_path(sess)) {
-        LoadResult::Ok{ data: (bytes, start_pos) } => OnDiskCache::new(sess, bytes, start_pos),
+        LoadResult::Ok{ data: (bytes, start_pos) } => {
+            // A truncated or corrupt cache is as good as none: the results
+            // which aren't in it are computed again.
+            OnDiskCache::try_new(sess, bytes, start_pos).unwrap_or_else(|_| {
+                OnDiskCache::new_empty(sess.codemap())
+            })
+        }
         _ => OnDiskCache::new_empty(sess.codemap())
     }
 }
@@ -15,6 +15,7 @@
 mod data;
 mod dirty_clean;
 mod fs;
+mod inspect;
 mod load;
 mod save;
 mod work_product;
@@ -24,6 +25,8 @@ pub use self::fs::finalize_session_directory;
 pub use self::fs::garbage_collect_session_directories;
 pub use self::fs::in_incr_comp_dir;
 pub use self::fs::prepare_session_directory;
+pub use self::inspect::garbage_collect_incremental_dir;
+pub use self::inspect::print_incremental_sessions;
 pub use self::load::dep_graph_tcx_init;
 pub use self::load::load_dep_graph;
 pub use self::load::load_query_result_cache;
@@ -152,6 +152,7 @@ pub fn register_builtins(store: &mut lint::LintStore, sess: Option<&Session>) {
         UnusedBrokenConst: UnusedBrokenConst,
         TrivialConstraints: TrivialConstraints,
         TypeLimits: TypeLimits::new(),
+        LossyCasts: LossyCasts,
         MissingDoc: MissingDoc::new(),
         MissingDebugImplementations: MissingDebugImplementations::new(),
     ]], ['tcx]);
@@ -191,6 +192,13 @@ pub fn register_builtins(store: &mut lint::LintStore, sess: Option<&Session>) {
                     UNUSED_LABELS,
                     UNUSED_PARENS);
 
+    add_lint_group!(sess,
+                    "lossy_casts",
+                    CAST_TRUNCATION,
+                    CAST_SIGN_LOSS,
+                    CAST_FLOAT_TO_INT,
+                    CAST_PTR_TO_NARROW_INT);
+
     add_lint_group!(sess,
                     "rust_2018_idioms",
                     BARE_TRAIT_OBJECTS,
@@ -10,9 +10,11 @@
 
 #![allow(non_snake_case)]
 
+use rustc::hir::def::Def;
 use rustc::hir::map as hir_map;
+use rustc::mir::interpret::GlobalId;
 use rustc::ty::subst::Substs;
-use rustc::ty::{self, AdtKind, ParamEnv, Ty, TyCtxt};
+use rustc::ty::{self, AdtKind, ParamEnv, Ty, TyCtxt, TypeFoldable};
 use rustc::ty::layout::{self, IntegerExt, LayoutOf};
 use util::nodemap::FxHashSet;
 use lint::{LateContext, LintContext, LintArray};
@@ -25,6 +27,7 @@ use syntax::{ast, attr};
 use rustc_target::spec::abi::Abi;
 use syntax_pos::Span;
 use syntax::codemap;
+use syntax::errors::{Applicability, DiagnosticBuilder};
 
 use rustc::hir;
 
@@ -876,3 +879,329 @@ impl<'a, 'tcx> LateLintPass<'a, 'tcx> for VariantSizeDifferences {
         }
     }
 }
+
+declare_lint! {
+    CAST_TRUNCATION,
+    Allow,
+    "`as` casts to a narrower integer type"
+}
+
+declare_lint! {
+    CAST_SIGN_LOSS,
+    Allow,
+    "`as` casts between integer types that may change the sign of the value"
+}
+
+declare_lint! {
+    CAST_FLOAT_TO_INT,
+    Allow,
+    "`as` casts from floating point to integer types"
+}
+
+declare_lint! {
+    CAST_PTR_TO_NARROW_INT,
+    Allow,
+    "`as` casts from pointers to integer types narrower than a pointer"
+}
+
+declare_lint! {
+    CAST_LOSSLESS,
+    Allow,
+    "`as` casts which `From` can do without losing information"
+}
+
+/// Lints `as` casts that can lose information: the first four lints above,
+/// which make up the `lossy_casts` group. `CAST_LOSSLESS` points out the
+/// casts which can't, and can be written with `From` instead.
+///
+/// Casts of constant operands are only linted when the value doesn't
+/// survive the cast, so `300 as u16` or `MAX_LEN as u8` stay quiet while
+/// `300 as u8` doesn't. The widths of `isize`, `usize` and pointers are the
+/// widths on the target, so a cast from `usize` to `u32` is only linted on
+/// targets with 64-bit pointers.
+pub struct LossyCasts;
+
+impl LintPass for LossyCasts {
+    fn get_lints(&self) -> LintArray {
+        lint_array!(CAST_TRUNCATION,
+                    CAST_SIGN_LOSS,
+                    CAST_FLOAT_TO_INT,
+                    CAST_PTR_TO_NARROW_INT,
+                    CAST_LOSSLESS)
+    }
+}
+
+impl<'a, 'tcx> LateLintPass<'a, 'tcx> for LossyCasts {
+    fn check_expr(&mut self, cx: &LateContext<'a, 'tcx>, e: &'tcx hir::Expr) {
+        let expr = match e.node {
+            hir::ExprKind::Cast(ref expr, _) => expr,
+            _ => return,
+        };
+        let src_ty = cx.tables.expr_ty(expr);
+        let dst_ty = cx.tables.expr_ty(e);
+        if has_lossless_from(src_ty, dst_ty) {
+            let mut err = cx.struct_span_lint(
+                CAST_LOSSLESS,
+                e.span,
+                &format!("casting `{}` to `{}` can't lose information", src_ty, dst_ty),
+            );
+            if let Ok(snippet) = cx.sess().codemap().span_to_snippet(expr.span) {
+                err.span_suggestion_with_applicability(
+                    e.span,
+                    "use `From`, which only converts without losing information",
+                    format!("{}::from({})", dst_ty, snippet),
+                    Applicability::MachineApplicable,
+                );
+            }
+            err.emit();
+            return;
+        }
+        let (dst_bits, dst_signed) = match int_width(cx, dst_ty) {
+            Some(width) => width,
+            None => return,
+        };
+
+        match src_ty.sty {
+            ty::TyInt(_) | ty::TyUint(_) => {
+                let (src_bits, src_signed) = int_width(cx, src_ty).unwrap();
+                let (lint, msg) = if dst_bits < src_bits {
+                    (CAST_TRUNCATION, "may truncate the value")
+                } else if src_signed && !dst_signed {
+                    (CAST_SIGN_LOSS, "may lose the sign of the value")
+                } else if !src_signed && dst_signed && dst_bits == src_bits {
+                    (CAST_SIGN_LOSS, "may wrap around to a negative value")
+                } else {
+                    return;
+                };
+                if let Some(v) = const_int(cx, expr, src_ty) {
+                    if fits_in(v, dst_bits, dst_signed) {
+                        return;
+                    }
+                }
+
+                let mut err = cx.struct_span_lint(
+                    lint,
+                    e.span,
+                    &format!("casting `{}` to `{}` {}", src_ty, dst_ty, msg),
+                );
+                note_target_width(cx, &mut err, src_ty);
+                note_target_width(cx, &mut err, dst_ty);
+                // `TryFrom` is unstable, and has to be in scope. The type of
+                // the expression changes to a `Result`, so the code around it
+                // has to change too.
+                let try_from = cx.tcx.features().declared_lib_features.iter()
+                    .any(|&(ref name, _)| name == "try_from");
+                if try_from {
+                    if let Ok(snippet) = cx.sess().codemap().span_to_snippet(expr.span) {
+                        err.span_suggestion_with_applicability(
+                            e.span,
+                            "use `TryFrom` to check that the value fits, which gives a `Result`",
+                            format!("{}::try_from({})", dst_ty, snippet),
+                            Applicability::MaybeIncorrect,
+                        );
+                    }
+                }
+                err.emit();
+            }
+            ty::TyChar => {
+                // Unicode scalar values are at most 0x10FFFF, which takes 21
+                // bits.
+                if dst_bits > 21 {
+                    return;
+                }
+                if let hir::ExprKind::Lit(ref lit) = expr.node {
+                    if let ast::LitKind::Char(c) = lit.node {
+                        if fits_in(c as i128, dst_bits, dst_signed) {
+                            return;
+                        }
+                    }
+                }
+
+                let mut err = cx.struct_span_lint(
+                    CAST_TRUNCATION,
+                    e.span,
+                    &format!("casting `{}` to `{}` may truncate the value", src_ty, dst_ty),
+                );
+                note_target_width(cx, &mut err, dst_ty);
+                err.emit();
+            }
+            ty::TyFloat(_) => {
+                if let hir::ExprKind::Lit(ref lit) = expr.node {
+                    if float_lit_fits_in(lit, dst_bits, dst_signed) {
+                        return;
+                    }
+                }
+
+                let mut err = cx.struct_span_lint(
+                    CAST_FLOAT_TO_INT,
+                    e.span,
+                    &format!("casting `{}` to `{}` may lose the value", src_ty, dst_ty),
+                );
+                note_target_width(cx, &mut err, dst_ty);
+                err.note("the fractional part of the value is discarded");
+                if cx.sess().opts.debugging_opts.saturating_float_casts {
+                    err.note(&format!("values out of the range of `{}` saturate to its bounds, \
+                                       and NaN becomes 0", dst_ty));
+                } else {
+                    err.note(&format!("the result is undefined for values out of the range \
+                                       of `{}`, and for NaN", dst_ty));
+                }
+                err.emit();
+            }
+            ty::TyRawPtr(_) | ty::TyFnPtr(_) | ty::TyFnDef(..) => {
+                let ptr_bits = cx.tcx.data_layout.pointer_size.bits();
+                if dst_bits >= ptr_bits {
+                    return;
+                }
+                let mut err = cx.struct_span_lint(
+                    CAST_PTR_TO_NARROW_INT,
+                    e.span,
+                    &format!("casting `{}` to `{}` truncates the address", src_ty, dst_ty),
+                );
+                err.note(&format!("pointers are {} bits wide on the target", ptr_bits));
+                err.help("cast to `usize` to keep the whole address");
+                err.emit();
+            }
+            _ => {}
+        }
+
+        /// Whether `std` has a `From` impl for `dst` from `src`, which only
+        /// exist for the conversions that can't lose information on any
+        /// target.
+        fn has_lossless_from(src: Ty, dst: Ty) -> bool {
+            use syntax::ast::{FloatTy, IntTy, UintTy};
+
+            match (&src.sty, &dst.sty) {
+                (&ty::TyBool, &ty::TyInt(_)) |
+                (&ty::TyBool, &ty::TyUint(_)) |
+                (&ty::TyUint(UintTy::U8), &ty::TyChar) |
+                (&ty::TyFloat(FloatTy::F32), &ty::TyFloat(FloatTy::F64)) => true,
+                // `isize` and `usize` can be 16 bits wide, so only the types
+                // narrower than that convert to them, and they convert to
+                // none.
+                (&ty::TyInt(IntTy::Isize), _) |
+                (&ty::TyUint(UintTy::Usize), _) => false,
+                (&ty::TyInt(s), &ty::TyInt(IntTy::Isize)) => s.bit_width().unwrap() < 32,
+                (&ty::TyUint(s), &ty::TyInt(IntTy::Isize)) => s.bit_width().unwrap() < 16,
+                (&ty::TyUint(s), &ty::TyUint(UintTy::Usize)) => s.bit_width().unwrap() < 32,
+                (&ty::TyInt(s), &ty::TyInt(d)) => s.bit_width() < d.bit_width(),
+                (&ty::TyUint(s), &ty::TyUint(d)) => s.bit_width() < d.bit_width(),
+                (&ty::TyUint(s), &ty::TyInt(d)) => s.bit_width() < d.bit_width(),
+                // The integers which fit in the mantissa of the float.
+                (&ty::TyInt(s), &ty::TyFloat(d)) => {
+                    let mantissa = if d == FloatTy::F32 { 24 } else { 53 };
+                    s.bit_width().unwrap() < mantissa
+                }
+                (&ty::TyUint(s), &ty::TyFloat(d)) => {
+                    let mantissa = if d == FloatTy::F32 { 24 } else { 53 };
+                    s.bit_width().unwrap() < mantissa
+                }
+                _ => false,
+            }
+        }
+
+        /// The width in bits of the integer type `ty` on the target, and
+        /// whether it's signed.
+        fn int_width(cx: &LateContext, ty: Ty) -> Option<(u64, bool)> {
+            let (ity, signed) = match ty.sty {
+                ty::TyInt(t) => (attr::IntType::SignedInt(t), true),
+                ty::TyUint(t) => (attr::IntType::UnsignedInt(t), false),
+                _ => return None,
+            };
+            Some((layout::Integer::from_attr(cx.tcx, ity).size().bits(), signed))
+        }
+
+        fn note_target_width(cx: &LateContext, err: &mut DiagnosticBuilder, ty: Ty) {
+            match ty.sty {
+                ty::TyInt(ast::IntTy::Isize) | ty::TyUint(ast::UintTy::Usize) => {
+                    let (bits, _) = int_width(cx, ty).unwrap();
+                    err.note(&format!("`{}` is {} bits wide on the target", ty, bits));
+                }
+                _ => {}
+            }
+        }
+
+        /// Whether `v` is in the range of an integer type of `bits` bits.
+        fn fits_in(v: i128, bits: u64, signed: bool) -> bool {
+            if bits == 128 {
+                // `v` is negative for `u128` values above `i128::MAX`, for
+                // which `const_int` gives up.
+                return signed || v >= 0;
+            }
+            if signed {
+                let max = (1i128 << (bits - 1)) - 1;
+                v >= -max - 1 && v <= max
+            } else {
+                v >= 0 && v < 1i128 << bits
+            }
+        }
+
+        /// The value of `expr`, of type `ty`, if it's an integer literal, a
+        /// path to a constant, or the negation of either.
+        fn const_int<'a, 'tcx>(cx: &LateContext<'a, 'tcx>,
+                               expr: &hir::Expr,
+                               ty: Ty<'tcx>) -> Option<i128> {
+            let (bits, signed) = int_width(cx, ty)?;
+            let v = const_bits(cx, expr, ty)?;
+            let shift = 128 - bits;
+            if signed {
+                Some(((v << shift) as i128) >> shift)
+            } else if bits == 128 && v > i128::max_value() as u128 {
+                None
+            } else {
+                Some(((v << shift) >> shift) as i128)
+            }
+        }
+
+        fn const_bits<'a, 'tcx>(cx: &LateContext<'a, 'tcx>,
+                                expr: &hir::Expr,
+                                ty: Ty<'tcx>) -> Option<u128> {
+            match expr.node {
+                hir::ExprKind::Lit(ref lit) => match lit.node {
+                    // The literal is in range, or `OVERFLOWING_LITERALS`
+                    // fires, so it can be truncated to `ty`.
+                    ast::LitKind::Int(v, _) => Some(v),
+                    _ => None,
+                },
+                hir::ExprKind::Unary(hir::UnNeg, ref inner) => {
+                    const_bits(cx, inner, ty).map(u128::wrapping_neg)
+                }
+                hir::ExprKind::Path(ref qpath) => {
+                    let def_id = match cx.tables.qpath_def(qpath, expr.hir_id) {
+                        Def::Const(def_id) | Def::AssociatedConst(def_id) => def_id,
+                        _ => return None,
+                    };
+                    let substs = cx.tables.node_substs(expr.hir_id);
+                    if substs.needs_subst() {
+                        return None;
+                    }
+                    let instance = ty::Instance::resolve(cx.tcx, cx.param_env, def_id, substs)?;
+                    let cid = GlobalId { instance, promoted: None };
+                    // Errors are reported where the constant is defined.
+                    let value = cx.tcx.const_eval(cx.param_env.and(cid)).ok()?;
+                    value.to_bits(cx.tcx, cx.param_env.and(ty))
+                }
+                _ => None,
+            }
+        }
+
+        /// Whether the float literal `lit` is an integer in the range of an
+        /// integer type of `bits` bits.
+        fn float_lit_fits_in(lit: &ast::Lit, bits: u64, signed: bool) -> bool {
+            let v: f64 = match lit.node {
+                ast::LitKind::Float(v, _) |
+                ast::LitKind::FloatUnsuffixed(v) => match v.as_str().parse() {
+                    Ok(v) => v,
+                    Err(_) => return false,
+                },
+                _ => return false,
+            };
+            // Only integers that can be represented exactly, so that the
+            // comparisons below are exact.
+            if v.fract() != 0.0 || v.abs() > (1u64 << 53) as f64 {
+                return false;
+            }
+            fits_in(v as i128, bits, signed)
+        }
+    }
+}
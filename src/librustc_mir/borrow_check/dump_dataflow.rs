@@ -0,0 +1,152 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `-Z dump-mir-dataflow`: graphviz graphs of the MIR being borrow checked,
+//! with the dataflow state on entry to each basic block. With
+//! `-Z dump-mir-dataflow-errors`, the graphs also highlight the path from
+//! the borrow behind each borrow error to the error.
+
+use borrow_check::borrow_set::BorrowSet;
+use dataflow::move_paths::MoveData;
+use dataflow::{Borrows, FlowAtLocation, MaybeInitializedPlaces};
+use rustc::mir::{BasicBlock, Location, Mir};
+use rustc::ty::TyCtxt;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::io;
+use transform::MirSource;
+use util::liveness::{self, IdentityMap, LivenessMode};
+use util::pretty::{self, dump_enabled};
+use util::{write_mir_fn_graphviz_annotated, GraphvizAnnotations};
+
+const PASS_NAME: &str = "borrowck";
+
+/// Whether the graph of `source` should be dumped.
+crate fn dump_dataflow_enabled(tcx: TyCtxt<'_, '_, '_>, source: MirSource) -> bool {
+    tcx.sess.opts.debugging_opts.dump_mir_dataflow && dump_enabled(tcx, PASS_NAME, source)
+}
+
+/// Annotates each block of `mir` with the locals live on entry to it, and
+/// with the places maybe initialized and the borrows in scope on entry to it
+/// according to `flow_inits` and `flow_borrows`.
+crate fn annotate_dataflow<'tcx>(
+    mir: &Mir<'tcx>,
+    move_data: &MoveData<'tcx>,
+    borrow_set: &BorrowSet<'tcx>,
+    flow_inits: &FlowAtLocation<MaybeInitializedPlaces<'_, '_, 'tcx>>,
+    flow_borrows: &FlowAtLocation<Borrows<'_, '_, 'tcx>>,
+) -> GraphvizAnnotations {
+    // Like NLL, count both regular uses and drops as uses.
+    let mode = LivenessMode {
+        include_regular_use: true,
+        include_drops: true,
+    };
+    let map = IdentityMap::new(mir);
+    let liveness = liveness::liveness_of_locals(mir, mode, &map);
+
+    let mut annotations = GraphvizAnnotations::default();
+    for block in mir.basic_blocks().indices() {
+        // `simulate_block` walks the block backwards, so the last set is the
+        // one on entry.
+        let mut live = String::new();
+        liveness.simulate_block(mir, block, &map, |location, locals| {
+            if location.statement_index == 0 {
+                live = join(locals.iter().map(|local| format!("{:?}", local)));
+            }
+        });
+        let init = join(flow_inits.iter_entry_of(block).map(|mpi| &move_data.move_paths[mpi]));
+        let borrows = join(flow_borrows.iter_entry_of(block).map(|index| {
+            let borrow = &borrow_set[index];
+            format!("{} at {:?}", borrow, borrow.reserve_location)
+        }));
+        annotations.rows.insert(block, vec![
+            ("live".to_string(), live),
+            ("init".to_string(), init),
+            ("borrows".to_string(), borrows),
+        ]);
+    }
+    annotations
+}
+
+/// Highlights the path in the CFG from the borrow to the error of each
+/// `(borrow, error, description)` of `errors`.
+crate fn annotate_error_paths(
+    mir: &Mir<'_>,
+    errors: &[(Location, Location, &'static str)],
+    annotations: &mut GraphvizAnnotations,
+) {
+    for &(borrow, error, description) in errors {
+        annotations.rows.entry(borrow.block).or_insert_with(Vec::new).push((
+            "borrow".to_string(),
+            format!("at {:?}, behind the {} at {:?}", borrow, description, error),
+        ));
+        annotations.rows.entry(error.block).or_insert_with(Vec::new).push((
+            "error".to_string(),
+            format!("{} at {:?}", description, error),
+        ));
+        if let Some(path) = cfg_path(mir, borrow, error) {
+            annotations.highlighted_blocks.extend(path.iter().cloned());
+            annotations.highlighted_edges.extend(path.windows(2).map(|pair| (pair[0], pair[1])));
+        }
+    }
+}
+
+crate fn dump_graphviz(
+    tcx: TyCtxt<'_, '_, '_>,
+    source: MirSource,
+    mir: &Mir<'_>,
+    annotations: &GraphvizAnnotations,
+) {
+    let _: io::Result<()> = do catch {
+        let mut file = pretty::create_dump_file(tcx, "dataflow.dot", None, PASS_NAME, &0, source)?;
+        write_mir_fn_graphviz_annotated(tcx, source.def_id, mir, annotations, &mut file)?;
+    };
+}
+
+/// The blocks of a shortest path in the CFG from `from` to `to`, both
+/// included. If `to` comes before `from` in the same block, the path goes
+/// around a loop.
+fn cfg_path(mir: &Mir<'_>, from: Location, to: Location) -> Option<Vec<BasicBlock>> {
+    if from.block == to.block && from.statement_index <= to.statement_index {
+        return Some(vec![from.block]);
+    }
+
+    // Breadth-first search, remembering the predecessor of each block.
+    let mut predecessors = FxHashMap();
+    let mut visited = FxHashSet();
+    let mut queue = VecDeque::new();
+    queue.push_back(from.block);
+    while let Some(block) = queue.pop_front() {
+        for &successor in mir[block].terminator().successors() {
+            if !visited.insert(successor) {
+                continue;
+            }
+            predecessors.insert(successor, block);
+            if successor == to.block {
+                let mut path = vec![to.block];
+                let mut block = block;
+                while block != from.block {
+                    path.push(block);
+                    block = predecessors[&block];
+                }
+                path.push(from.block);
+                path.reverse();
+                return Some(path);
+            }
+            queue.push_back(successor);
+        }
+    }
+    None
+}
+
+fn join<T: Display>(items: impl Iterator<Item = T>) -> String {
+    items.map(|item| item.to_string()).collect::<Vec<_>>().join(", ")
+}
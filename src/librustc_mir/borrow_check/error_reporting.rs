@@ -153,6 +153,7 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
         (place, span): (&Place<'tcx>, Span),
         borrow: &BorrowData<'tcx>,
     ) {
+        self.record_borrow_error(context, borrow, "move out while borrowed");
         let tcx = self.tcx;
         let value_msg = match self.describe_place(place) {
             Some(name) => format!("`{}`", name),
@@ -182,6 +183,7 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
         (place, span): (&Place<'tcx>, Span),
         borrow: &BorrowData<'tcx>,
     ) {
+        self.record_borrow_error(context, borrow, "use while mutably borrowed");
         let tcx = self.tcx;
         let mut err = tcx.cannot_use_when_mutably_borrowed(
             span,
@@ -197,6 +199,18 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
         err.buffer(&mut self.errors_buffer);
     }
 
+    /// Records the locations of a borrow error for `-Z dump-mir-dataflow-errors`.
+    fn record_borrow_error(
+        &mut self,
+        context: Context,
+        borrow: &BorrowData<'tcx>,
+        description: &'static str,
+    ) {
+        if self.tcx.sess.opts.debugging_opts.dump_mir_dataflow_errors {
+            self.borrow_error_locations.push((borrow.reserve_location, context.loc, description));
+        }
+    }
+
     /// Finds the span of arguments of a closure (within `maybe_closure_span`) and its usage of
     /// the local assigned at `location`.
     /// This is done by searching in statements succeeding `location`
@@ -274,6 +288,7 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
         gen_borrow_kind: BorrowKind,
         issued_borrow: &BorrowData<'tcx>,
     ) {
+        self.record_borrow_error(context, issued_borrow, "conflicting borrow");
         let issued_span = self.retrieve_borrow_span(issued_borrow);
 
         let new_closure_span = self.find_closure_span(span, context.loc);
@@ -426,6 +441,7 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
 
         self.access_place_error_reported
             .insert((root_place.clone(), borrow_span));
+        self.record_borrow_error(context, borrow, "value dropped while borrowed");
 
         match &self.describe_place(&borrow.borrowed_place) {
             Some(name) => {
@@ -516,6 +532,7 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
         (place, span): (&Place<'tcx>, Span),
         loan: &BorrowData<'tcx>,
     ) {
+        self.record_borrow_error(context, loan, "assignment to borrowed value");
         let tcx = self.tcx;
         let mut err = tcx.cannot_assign_to_borrowed(
             span,
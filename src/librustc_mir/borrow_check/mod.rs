@@ -44,6 +44,7 @@ use dataflow::MoveDataParamEnv;
 use dataflow::{do_dataflow, DebugFormatted};
 use dataflow::{EverInitializedPlaces, MovingOutStatements};
 use dataflow::{MaybeInitializedPlaces, MaybeUninitializedPlaces};
+use transform::MirSource;
 use util::borrowck_errors::{BorrowckErrors, Origin};
 
 use self::borrow_set::{BorrowData, BorrowSet};
@@ -56,6 +57,7 @@ use self::mutability_errors::AccessKind;
 use self::path_utils::*;
 
 crate mod borrow_set;
+mod dump_dataflow;
 mod error_reporting;
 mod flows;
 mod location;
@@ -231,6 +233,19 @@ fn do_mir_borrowck<'a, 'gcx, 'tcx>(
         |rs, i| DebugFormatted::new(&rs.location(i)),
     ));
 
+    let source = MirSource::item(def_id);
+    let dataflow_annotations = if dump_dataflow::dump_dataflow_enabled(tcx, source) {
+        Some(dump_dataflow::annotate_dataflow(
+            mir,
+            &mdpe.move_data,
+            &borrow_set,
+            &flow_inits,
+            &flow_borrows,
+        ))
+    } else {
+        None
+    };
+
     let movable_generator = match tcx.hir.get(id) {
         hir::map::Node::NodeExpr(&hir::Expr {
             node: hir::ExprKind::Closure(.., Some(hir::GeneratorMovability::Static)),
@@ -262,6 +277,7 @@ fn do_mir_borrowck<'a, 'gcx, 'tcx>(
         used_mut_upvars: SmallVec::new(),
         borrow_set,
         dominators,
+        borrow_error_locations: Vec::new(),
     };
 
     let mut state = Flows::new(
@@ -277,6 +293,11 @@ fn do_mir_borrowck<'a, 'gcx, 'tcx>(
     }
     mbcx.analyze_results(&mut state); // entry point for DataflowResultsConsumer
 
+    if let Some(mut annotations) = dataflow_annotations {
+        dump_dataflow::annotate_error_paths(mir, &mbcx.borrow_error_locations, &mut annotations);
+        dump_dataflow::dump_graphviz(tcx, source, mir, &annotations);
+    }
+
     // For each non-user used mutable variable, check if it's been assigned from
     // a user-declared local. If so, then put that local into the used_mut set.
     // Note that this set is expected to be small - only upvars from closures
@@ -426,6 +447,11 @@ pub struct MirBorrowckCtxt<'cx, 'gcx: 'tcx, 'tcx: 'cx> {
 
     /// Dominators for MIR
     dominators: Dominators<BasicBlock>,
+
+    /// The locations of the borrow and of the error, and a description of
+    /// the error, for each borrow error reported, when
+    /// `-Z dump-mir-dataflow-errors` is set.
+    borrow_error_locations: Vec<(Location, Location, &'static str)>,
 }
 
 // Check that:
@@ -116,6 +116,12 @@ where
         self.curr_state.contains(x)
     }
 
+    /// Returns an iterator over the elements present on entry to `bb`,
+    /// regardless of the current state.
+    pub fn iter_entry_of(&self, bb: BasicBlock) -> Iter<BD::Idx> {
+        self.base_results.sets().on_entry_set_for(bb.index()).iter()
+    }
+
     /// Returns an iterator over the elements present in the current state.
     pub fn iter_incoming(&self) -> iter::Peekable<Iter<BD::Idx>> {
         self.curr_state.iter().peekable()
@@ -0,0 +1,105 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Dead store elimination.
+//!
+//! This removes the assignments to locals that are never read afterwards:
+//!
+//!     DEST = RVALUE
+//!     ...           // no use of DEST
+//!     DEST = RVALUE2
+//!
+//! becomes:
+//!
+//!     NOP
+//!     ...
+//!     DEST = RVALUE2
+//!
+//! Removing a store can make the stores to the locals it read dead too, so
+//! this runs until there are no dead stores left.
+//!
+//! Liveness only sees the uses of a local through a reference at the point of
+//! the borrow, so the locals that are ever borrowed are left alone, and so,
+//! conservatively, are the locals needing drop.
+
+use rustc::mir::{Local, Location, Mir, Place, StatementKind, RETURN_PLACE};
+use rustc::ty::{self, TyCtxt};
+use transform::{MirPass, MirSource};
+use util::def_use::DefUseAnalysis;
+use util::liveness::{self, IdentityMap, LiveVarSet, LivenessMode};
+
+pub struct DeadStoreElimination;
+
+impl MirPass for DeadStoreElimination {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        // Like copy propagation, this messes up debug info, so we only run
+        // when the MIR optimization level is > 1.
+        if tcx.sess.opts.debugging_opts.mir_opt_level <= 1 {
+            return;
+        }
+
+        let param_env = tcx.param_env(source.def_id);
+        let mut def_use_analysis = DefUseAnalysis::new(mir);
+        loop {
+            def_use_analysis.analyze(mir);
+            let dead_stores = find_dead_stores(tcx, param_env, mir, &def_use_analysis);
+            if dead_stores.is_empty() {
+                break;
+            }
+            for location in dead_stores {
+                debug!("Deleting dead store at {:?}", location);
+                mir.make_statement_nop(location);
+            }
+        }
+    }
+}
+
+fn find_dead_stores<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                              param_env: ty::ParamEnv<'tcx>,
+                              mir: &Mir<'tcx>,
+                              def_use_analysis: &DefUseAnalysis<'tcx>)
+                              -> Vec<Location> {
+    // A dropped local isn't dead: count drops as uses.
+    let mode = LivenessMode {
+        include_regular_use: true,
+        include_drops: true,
+    };
+    let map = IdentityMap::new(mir);
+    let liveness = liveness::liveness_of_locals(mir, mode, &map);
+
+    let is_removable = |local: Local| {
+        // The return place is read by the caller.
+        local != RETURN_PLACE &&
+            !def_use_analysis.local_info(local).borrowed &&
+            !mir.local_decls[local].ty.needs_drop(tcx, param_env)
+    };
+
+    let mut dead_stores = vec![];
+    for (block, data) in mir.basic_blocks().iter_enumerated() {
+        // `simulate_block` walks the block backwards, passing the locals live
+        // before each statement, so the locals live after a statement are
+        // the ones passed just before it.
+        let mut live_after: LiveVarSet<Local> = liveness.outs[block].clone();
+        liveness.simulate_block(mir, block, &map, |location, live| {
+            if let Some(statement) = data.statements.get(location.statement_index) {
+                if let StatementKind::Assign(Place::Local(local), _) = statement.kind {
+                    if !live_after.contains(&local) && is_removable(local) {
+                        dead_stores.push(location);
+                    }
+                }
+            }
+            live_after.overwrite(live);
+        });
+    }
+    dead_stores
+}
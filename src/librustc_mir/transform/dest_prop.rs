@@ -0,0 +1,226 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Call destination propagation.
+//!
+//! This makes calls return into the place their result is moved to, instead
+//! of a temporary. We look for patterns that look like:
+//!
+//!     TMP = CALL(ARGS) -> BB
+//!   BB:
+//!     DEST = move TMP
+//!
+//! where `TMP` is a local whose only def is the call and whose only use is
+//! the move, and `DEST` is a local or a field of a local. We replace that with:
+//!
+//!     DEST = CALL(ARGS) -> BB
+//!   BB:
+//!     NOP
+//!
+//! which saves a copy of the result, as large values are returned in memory.
+//!
+//! The callee may write to the destination while it runs, so `DEST` must not
+//! be read by the call, be borrowed, or be used by the cleanup blocks run when
+//! the call unwinds.
+
+use rustc::mir::{BasicBlock, Local, LocalKind, Location, Mir, Operand, Place, ProjectionElem};
+use rustc::mir::{Rvalue, StatementKind, TerminatorKind};
+use rustc::mir::visit::PlaceContext;
+use rustc::ty::TyCtxt;
+use transform::{MirPass, MirSource};
+use util::def_use::DefUseAnalysis;
+use util::is_disaligned;
+
+pub struct DestinationPropagation;
+
+impl MirPass for DestinationPropagation {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        // Like copy propagation, this messes up debug info, so we only run
+        // when the MIR optimization level is > 1.
+        if tcx.sess.opts.debugging_opts.mir_opt_level <= 1 {
+            return;
+        }
+
+        let param_env = tcx.param_env(source.def_id);
+        let mut def_use_analysis = DefUseAnalysis::new(mir);
+        def_use_analysis.analyze(mir);
+
+        // Each candidate has its own call and temporary, and the temporary of
+        // one can't be the destination of another, as it would have two defs:
+        // they can all be propagated with the same def-use chains.
+        let mut candidates = vec![];
+        for block in mir.basic_blocks().indices() {
+            if let Some(candidate) = Candidate::find(mir, &def_use_analysis, block) {
+                // Calls write their result as a whole, which they can't do
+                // to a field of a packed struct.
+                if !is_disaligned(tcx, &*mir, param_env, &candidate.dest) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        for candidate in candidates {
+            candidate.perform(mir, &def_use_analysis);
+        }
+    }
+}
+
+struct Candidate<'tcx> {
+    /// The block whose terminator is the call.
+    call: BasicBlock,
+    /// The move of `temp` to `dest`.
+    assignment: Location,
+    temp: Local,
+    dest: Place<'tcx>,
+}
+
+impl<'tcx> Candidate<'tcx> {
+    fn find(mir: &Mir<'tcx>, def_use_analysis: &DefUseAnalysis<'tcx>, call: BasicBlock)
+            -> Option<Candidate<'tcx>> {
+        if mir[call].is_cleanup {
+            return None;
+        }
+        let call_location = Location {
+            block: call,
+            statement_index: mir[call].statements.len(),
+        };
+        let (temp, target) = match mir[call].terminator().kind {
+            TerminatorKind::Call { destination: Some((Place::Local(temp), target)), .. } => {
+                (temp, target)
+            }
+            _ => return None,
+        };
+        debug!("Considering call destination: {:?} at {:?}", temp, call_location);
+        match mir.local_kind(temp) {
+            LocalKind::Var | LocalKind::Temp => {}
+            LocalKind::Arg | LocalKind::ReturnPointer => {
+                debug!("  Can't propagate destination: {:?} is not a temporary", temp);
+                return None;
+            }
+        }
+        if mir.predecessors_for(target).len() != 1 {
+            debug!("  Can't propagate destination: return block has other predecessors");
+            return None;
+        }
+
+        // The move must come first in the return block, past storage
+        // statements of other locals.
+        let mut assignment = None;
+        for (statement_index, statement) in mir[target].statements.iter().enumerate() {
+            let location = Location { block: target, statement_index };
+            match statement.kind {
+                StatementKind::Assign(ref dest, Rvalue::Use(Operand::Move(Place::Local(src))))
+                        if src == temp => {
+                    assignment = Some((location, dest));
+                    break;
+                }
+                StatementKind::StorageLive(local) |
+                StatementKind::StorageDead(local) if local != temp => {}
+                StatementKind::Nop => {}
+                _ => break,
+            }
+        }
+        let (assignment, dest) = match assignment {
+            Some(assignment) => assignment,
+            None => {
+                debug!("  Can't propagate destination: not moved at the start of {:?}", target);
+                return None;
+            }
+        };
+
+        // The call and the move must be the only defs and uses of the
+        // temporary.
+        let temp_info = def_use_analysis.local_info(temp);
+        let only_call_and_move = !temp_info.borrowed &&
+            temp_info.defs_and_uses.iter().all(|place_use| {
+                match place_use.context {
+                    PlaceContext::Call => place_use.location == call_location,
+                    PlaceContext::Move => place_use.location == assignment,
+                    PlaceContext::StorageLive | PlaceContext::StorageDead => true,
+                    _ => false,
+                }
+            });
+        if !only_call_and_move {
+            debug!("  Can't propagate destination: {:?} has other defs or uses", temp);
+            return None;
+        }
+
+        let dest_local = match field_base(dest) {
+            Some(local) => local,
+            None => {
+                debug!("  Can't propagate destination: {:?} is not a field of a local", dest);
+                return None;
+            }
+        };
+        let dest_info = def_use_analysis.local_info(dest_local);
+        if dest_info.borrowed {
+            debug!("  Can't propagate destination: {:?} is borrowed", dest_local);
+            return None;
+        }
+        for place_use in &dest_info.defs_and_uses {
+            if place_use.location == call_location {
+                debug!("  Can't propagate destination: {:?} is used by the call", dest_local);
+                return None;
+            }
+            // Only storage statements can be there, but its storage must
+            // already be live at the call.
+            if place_use.location.block == target &&
+                    place_use.location.statement_index < assignment.statement_index {
+                debug!("  Can't propagate destination: {:?} is used before the move", dest_local);
+                return None;
+            }
+            if mir[place_use.location.block].is_cleanup && !place_use.context.is_storage_marker() {
+                debug!("  Can't propagate destination: {:?} is used on unwind", dest_local);
+                return None;
+            }
+        }
+
+        Some(Candidate {
+            call,
+            assignment,
+            temp,
+            dest: dest.clone(),
+        })
+    }
+
+    fn perform(self, mir: &mut Mir<'tcx>, def_use_analysis: &DefUseAnalysis<'tcx>) {
+        debug!("Propagating {:?} to the destination of the call in {:?}", self.dest, self.call);
+        for place_use in &def_use_analysis.local_info(self.temp).defs_and_uses {
+            if place_use.context.is_storage_marker() {
+                mir.make_statement_nop(place_use.location);
+            }
+        }
+        match mir[self.call].terminator_mut().kind {
+            TerminatorKind::Call { destination: Some((ref mut destination, _)), .. } => {
+                *destination = self.dest;
+            }
+            _ => bug!("call destination propagation: {:?} is not a call", self.call),
+        }
+        mir.make_statement_nop(self.assignment);
+    }
+}
+
+/// The local of `place`, if it's a local or a field of a local.
+fn field_base(place: &Place) -> Option<Local> {
+    match *place {
+        Place::Local(local) => Some(local),
+        Place::Projection(ref proj) => {
+            match proj.elem {
+                ProjectionElem::Field(..) => field_base(&proj.base),
+                _ => None,
+            }
+        }
+        Place::Promoted(_) |
+        Place::Static(..) => None,
+    }
+}
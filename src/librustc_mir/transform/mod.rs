@@ -41,11 +41,14 @@ pub mod dump_mir;
 pub mod deaggregator;
 pub mod instcombine;
 pub mod copy_prop;
+pub mod dest_prop;
+pub mod dead_store_elimination;
 pub mod const_prop;
 pub mod generator;
 pub mod inline;
 pub mod lower_128bit;
 pub mod uniform_array_move_out;
+pub mod stack_usage;
 
 pub(crate) fn provide(providers: &mut Providers) {
     self::qualify_consts::provide(providers);
@@ -217,6 +220,9 @@ fn mir_validated<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> &'tcx
         // What we need to run borrowck etc.
         qualify_consts::QualifyAndPromoteConstants,
         simplify::SimplifyCfg::new("qualify-consts"),
+        // Borrowck computes the MIR of every function, so this lints them
+        // without codegen, and whether they're instantiated or not.
+        stack_usage::StackUsage,
     ];
     tcx.alloc_steal_mir(mir)
 }
@@ -275,6 +281,8 @@ fn optimized_mir<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> &'tcx
         simplify_branches::SimplifyBranches::new("after-const-prop"),
         deaggregator::Deaggregator,
         copy_prop::CopyPropagation,
+        dest_prop::DestinationPropagation,
+        dead_store_elimination::DeadStoreElimination,
         remove_noop_landing_pads::RemoveNoopLandingPads,
         simplify::SimplifyCfg::new("final"),
         simplify::SimplifyLocals,
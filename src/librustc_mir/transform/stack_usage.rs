@@ -0,0 +1,151 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Lints functions whose stack frame, and moves whose value, is larger than
+//! `-Z stack-size-limit`, with the `large_stack_frames` lint.
+//!
+//! This runs on the MIR which borrowck checks, so the functions are linted by
+//! `cargo check` too, and generic functions whether they're instantiated or
+//! not. The size of a frame is estimated as the sum of the sizes of the
+//! locals of the function, before optimizations. LLVM keeps some of them in
+//! registers, and can share a stack slot between locals which aren't live at
+//! the same time, so the actual frame is usually smaller;
+//! `-Z print-stack-sizes` reports the frames as they are codegened. Locals
+//! whose layout depends on type parameters aren't counted.
+
+use rustc::hir;
+use rustc::lint;
+use rustc::lint::builtin::LARGE_STACK_FRAMES;
+use rustc::mir::*;
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc::ty::layout::{LayoutOf, Size};
+use rustc::util::nodemap::FxHashSet;
+use transform::{MirPass, MirSource};
+
+use std::cmp;
+
+pub struct StackUsage;
+
+impl MirPass for StackUsage {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        if source.promoted.is_some() {
+            return;
+        }
+        let node_id = tcx.hir.as_local_node_id(source.def_id).unwrap();
+        match tcx.hir.body_owner_kind(node_id) {
+            hir::BodyOwnerKind::Fn => {}
+            hir::BodyOwnerKind::Const | hir::BodyOwnerKind::Static(_) => return,
+        }
+        // Computing the layouts of all the locals isn't free, so don't
+        // bother when the lint is allowed for the whole function.
+        if tcx.lint_level_at_node(LARGE_STACK_FRAMES, node_id).0 == lint::Allow {
+            return;
+        }
+        let source_scope_local_data = match mir.source_scope_local_data {
+            ClearCrossCrate::Set(ref data) => data,
+            ClearCrossCrate::Clear => return,
+        };
+
+        let limit = tcx.sess.opts.debugging_opts.stack_size_limit as u64;
+        let param_env = tcx.param_env(source.def_id);
+        let size_of = |ty: Ty<'tcx>| {
+            // The layout of a generator comes from its optimized MIR, which
+            // is computed after this pass, so it's left out like the types
+            // whose layout isn't known.
+            if let ty::TyGenerator(..) = ty.sty {
+                return None;
+            }
+            tcx.layout_of(param_env.and(ty)).ok()
+        };
+
+        let mut frame = Size::ZERO;
+        let mut locals = vec![];
+        for local in mir.vars_and_temps_iter() {
+            if let Some(layout) = size_of(mir.local_decls[local].ty) {
+                frame = frame.abi_align(layout.align) + layout.size;
+                locals.push((layout.size, local));
+            }
+        }
+        if frame.bytes() > limit {
+            let mut err = tcx.struct_span_lint_node(
+                LARGE_STACK_FRAMES,
+                node_id,
+                tcx.def_span(source.def_id),
+                &format!("the stack frame of this function is estimated to be {} bytes, \
+                          above the limit of {} bytes", frame.bytes(), limit));
+            // Point at the largest locals, unless they're small next to the
+            // limit, or they come from a macro expansion elsewhere.
+            locals.sort_by_key(|&(size, _)| cmp::Reverse(size));
+            let largest = locals.iter().filter(|&&(size, local)| {
+                size.bytes() >= limit / 8 &&
+                    mir.span.contains(mir.local_decls[local].source_info.span)
+            });
+            for &(size, local) in largest.take(3) {
+                let decl = &mir.local_decls[local];
+                let label = match decl.name {
+                    Some(name) => format!("`{}` takes {} bytes", name, size.bytes()),
+                    None => format!("this temporary takes {} bytes", size.bytes()),
+                };
+                err.span_label(decl.source_info.span, label);
+            }
+            err.note("the estimate is the sum of the sizes of the locals of the function, \
+                      before optimizations");
+            err.emit();
+        }
+
+        // The same move can appear more than once, through temporaries.
+        let mut linted = FxHashSet();
+        let mut check_operand = |operand: &Operand<'tcx>, source_info: SourceInfo| {
+            let place = match *operand {
+                Operand::Move(ref place) => place,
+                _ => return,
+            };
+            let ty = place.ty(mir, tcx).to_ty(tcx);
+            let size = match size_of(ty) {
+                Some(layout) => layout.size.bytes(),
+                None => return,
+            };
+            if size > limit && linted.insert(source_info.span) {
+                tcx.lint_node(
+                    LARGE_STACK_FRAMES,
+                    source_scope_local_data[source_info.scope].lint_root,
+                    source_info.span,
+                    &format!("moving {} bytes, above the limit of {} bytes", size, limit));
+            }
+        };
+        for data in mir.basic_blocks() {
+            for statement in &data.statements {
+                if let StatementKind::Assign(_, ref rvalue) = statement.kind {
+                    match *rvalue {
+                        Rvalue::Use(ref operand) |
+                        Rvalue::Repeat(ref operand, _) => {
+                            check_operand(operand, statement.source_info);
+                        }
+                        Rvalue::Aggregate(_, ref operands) => {
+                            for operand in operands {
+                                check_operand(operand, statement.source_info);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            let terminator = data.terminator();
+            if let TerminatorKind::Call { ref args, .. } = terminator.kind {
+                for operand in args {
+                    check_operand(operand, terminator.source_info);
+                }
+            }
+        }
+    }
+}
@@ -10,7 +10,7 @@
 
 //! Def-use analysis.
 
-use rustc::mir::{Local, Location, Mir};
+use rustc::mir::{Local, Location, Mir, Place, ProjectionElem, Rvalue};
 use rustc::mir::visit::{PlaceContext, MutVisitor, Visitor};
 use rustc_data_structures::indexed_vec::IndexVec;
 use std::marker::PhantomData;
@@ -25,6 +25,10 @@ pub struct DefUseAnalysis<'tcx> {
 #[derive(Clone)]
 pub struct Info<'tcx> {
     pub defs_and_uses: Vec<Use<'tcx>>,
+    /// Whether the local, or a part of it not behind a pointer, is borrowed.
+    /// The local can then be used through the reference, which doesn't
+    /// appear in `defs_and_uses`.
+    pub borrowed: bool,
 }
 
 #[derive(Clone)]
@@ -94,17 +98,42 @@ impl<'tcx> Visitor<'tcx> for DefUseFinder<'tcx> {
             location,
         });
     }
+
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        if let Rvalue::Ref(_, _, ref place) = *rvalue {
+            if let Some(local) = borrowed_local(place) {
+                self.info[local].borrowed = true;
+            }
+        }
+        self.super_rvalue(rvalue, location)
+    }
+}
+
+fn borrowed_local(place: &Place) -> Option<Local> {
+    match *place {
+        Place::Local(local) => Some(local),
+        Place::Promoted(_) |
+        Place::Static(..) => None,
+        Place::Projection(ref proj) => {
+            match proj.elem {
+                ProjectionElem::Deref => None,
+                _ => borrowed_local(&proj.base),
+            }
+        }
+    }
 }
 
 impl<'tcx> Info<'tcx> {
     fn new() -> Info<'tcx> {
         Info {
             defs_and_uses: vec![],
+            borrowed: false,
         }
     }
 
     fn clear(&mut self) {
         self.defs_and_uses.clear();
+        self.borrowed = false;
     }
 
     pub fn def_count(&self) -> usize {
@@ -15,6 +15,7 @@ use rustc::ty::TyCtxt;
 use std::fmt::Debug;
 use std::io::{self, Write};
 
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::indexed_vec::Idx;
 
 use super::pretty::dump_mir_def_ids;
@@ -33,12 +34,35 @@ pub fn write_mir_graphviz<'tcx, W>(tcx: TyCtxt<'_, '_, 'tcx>,
     Ok(())
 }
 
+/// Extra information drawn over the graph of a MIR by
+/// `write_mir_fn_graphviz_annotated`, such as dataflow state.
+#[derive(Default)]
+pub struct GraphvizAnnotations {
+    /// Rows of `(title, text)` written above the statements of each block.
+    pub rows: FxHashMap<BasicBlock, Vec<(String, String)>>,
+    /// Blocks whose rows are highlighted, and edges drawn in red, e.g. to
+    /// show the path behind an error.
+    pub highlighted_blocks: FxHashSet<BasicBlock>,
+    pub highlighted_edges: FxHashSet<(BasicBlock, BasicBlock)>,
+}
+
 /// Write a graphviz DOT graph of the MIR.
 pub fn write_mir_fn_graphviz<'tcx, W>(tcx: TyCtxt<'_, '_, 'tcx>,
                                       def_id: DefId,
                                       mir: &Mir,
                                       w: &mut W) -> io::Result<()>
     where W: Write
+{
+    write_mir_fn_graphviz_annotated(tcx, def_id, mir, &GraphvizAnnotations::default(), w)
+}
+
+/// Write a graphviz DOT graph of the MIR, with `annotations` drawn over it.
+pub fn write_mir_fn_graphviz_annotated<'tcx, W>(tcx: TyCtxt<'_, '_, 'tcx>,
+                                                def_id: DefId,
+                                                mir: &Mir,
+                                                annotations: &GraphvizAnnotations,
+                                                w: &mut W) -> io::Result<()>
+    where W: Write
 {
     writeln!(w, "digraph Mir_{} {{", tcx.hir.as_local_node_id(def_id).unwrap())?;
 
@@ -52,12 +76,12 @@ pub fn write_mir_fn_graphviz<'tcx, W>(tcx: TyCtxt<'_, '_, 'tcx>,
 
     // Nodes
     for (block, _) in mir.basic_blocks().iter_enumerated() {
-        write_node(block, mir, w)?;
+        write_node(block, mir, annotations, w)?;
     }
 
     // Edges
     for (source, _) in mir.basic_blocks().iter_enumerated() {
-        write_edges(source, mir, w)?;
+        write_edges(source, mir, annotations, w)?;
     }
     writeln!(w, "}}")
 }
@@ -112,21 +136,47 @@ pub fn write_node_label<W: Write, INIT, FINI>(block: BasicBlock,
 }
 
 /// Write a graphviz DOT node for the given basic block.
-fn write_node<W: Write>(block: BasicBlock, mir: &Mir, w: &mut W) -> io::Result<()> {
+fn write_node<W: Write>(block: BasicBlock,
+                        mir: &Mir,
+                        annotations: &GraphvizAnnotations,
+                        w: &mut W) -> io::Result<()> {
+    let attrs = if annotations.highlighted_blocks.contains(&block) {
+        r#"align="left" balign="left" bgcolor="lightpink""#
+    } else {
+        r#"align="left" balign="left""#
+    };
+    let rows = |w: &mut W| -> io::Result<()> {
+        if let Some(rows) = annotations.rows.get(&block) {
+            for &(ref title, ref text) in rows {
+                write!(w, r#"<tr><td {}>{}: {}</td></tr>"#,
+                       attrs, dot::escape_html(title), dot::escape_html(text))?;
+            }
+        }
+        Ok(())
+    };
+
     // Start a new node with the label to follow, in one of DOT's pseudo-HTML tables.
     write!(w, r#"    {} [shape="none", label=<"#, node(block))?;
-    write_node_label(block, mir, w, 1, |_| Ok(()), |_| Ok(()))?;
+    write_node_label(block, mir, w, 1, rows, |_| Ok(()))?;
     // Close the node label and the node itself.
     writeln!(w, ">];")
 }
 
 /// Write graphviz DOT edges with labels between the given basic block and all of its successors.
-fn write_edges<W: Write>(source: BasicBlock, mir: &Mir, w: &mut W) -> io::Result<()> {
+fn write_edges<W: Write>(source: BasicBlock,
+                         mir: &Mir,
+                         annotations: &GraphvizAnnotations,
+                         w: &mut W) -> io::Result<()> {
     let terminator = mir[source].terminator();
     let labels = terminator.kind.fmt_successor_labels();
 
     for (&target, label) in terminator.successors().zip(labels) {
-        writeln!(w, r#"    {} -> {} [label="{}"];"#, node(source), node(target), label)?;
+        let attrs = if annotations.highlighted_edges.contains(&(source, target)) {
+            r#", color="red", penwidth="2""#
+        } else {
+            ""
+        };
+        writeln!(w, r#"    {} -> {} [label="{}"{}];"#, node(source), node(target), label, attrs)?;
     }
 
     Ok(())
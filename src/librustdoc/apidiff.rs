@@ -0,0 +1,1101 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Comparing the public API of a crate with an earlier version of it, with
+//! `--api-diff <old.json>`.
+//!
+//! The earlier version is read from the output of `--output-format json`, and
+//! the crate being documented is converted to the same layout. Ids change from
+//! one compilation to the next, so items are matched by path instead: every
+//! item gets a key like `foo::Bar`, `impl<T> foo::Bar<T>::new` for an item of
+//! an inherent impl, or `impl Clone for foo::Bar` for a trait impl, and a
+//! signature in Rust syntax that is compared as text.
+//!
+//! Each difference is classified as in RFC 1105: a *major* change can break
+//! code that compiled against the earlier version, a *minor* one can't. As in
+//! the RFC, the breakage that nearly any addition can cause through glob
+//! imports and method resolution doesn't count.
+//!
+//! The classification is conservative. Among the changes to signatures, only
+//! loosened bounds and new type parameters with defaults are known to be
+//! minor; any other change is major, even one that can't break code, like
+//! replacing a type with an alias of it.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use errors;
+use serialize::json::Json;
+
+use clean;
+use html::render::RenderInfo;
+use json::{crate_json, FORMAT_VERSION};
+
+/// Compares the public API of `krate` with the one documented in `old`, and
+/// prints the differences.
+///
+/// Returns the exit code: 1 if there are major changes or if `old` can't be
+/// read, 0 otherwise.
+crate fn run(
+    old: &Path,
+    krate: &clean::Crate,
+    renderinfo: &RenderInfo,
+    diag: &errors::Handler,
+) -> isize {
+    let old_json = match load(old) {
+        Ok(json) => json,
+        Err(e) => {
+            diag.struct_err(&format!("couldn't read `{}`: {}", old.display(), e)).emit();
+            return 1;
+        }
+    };
+    let version = get(&old_json, "format_version").as_u64();
+    if version != Some(FORMAT_VERSION) {
+        diag.struct_err(&format!("`{}` isn't in the JSON format of this rustdoc",
+                                 old.display()))
+            .note(&format!("it must be written by a rustdoc whose \
+                            `--output-format json` has version {}", FORMAT_VERSION))
+            .emit();
+        return 1;
+    }
+    let new_json = crate_json(krate, renderinfo);
+
+    let old_api = Api::new(&old_json);
+    let new_api = Api::new(&new_json);
+    let changes = compare(&old_api, &new_api);
+
+    for change in &changes {
+        println!("{}", change);
+    }
+    let major = changes.iter().filter(|c| c.severity == Severity::Major).count();
+    let minor = changes.len() - major;
+    if changes.is_empty() {
+        println!("no changes to the public API");
+    } else {
+        println!("{} major and {} minor change{}", major, minor,
+                 if changes.len() == 1 { "" } else { "s" });
+    }
+    if major > 0 {
+        println!("the next release must be a new major version");
+        1
+    } else {
+        0
+    }
+}
+
+fn load(path: &Path) -> Result<Json, String> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .map_err(|e| e.to_string())?;
+    contents.parse::<Json>().map_err(|e| format!("invalid JSON: {}", e))
+}
+
+static NULL: Json = Json::Null;
+
+/// The member `key` of `json`, or null if there is none. The earlier version
+/// comes from a file, so missing members are tolerated everywhere.
+fn get<'a>(json: &'a Json, key: &str) -> &'a Json {
+    json.find(key).unwrap_or(&NULL)
+}
+
+fn string(json: &Json) -> &str {
+    json.as_string().unwrap_or("")
+}
+
+fn array(json: &Json) -> &[Json] {
+    match json.as_array() {
+        Some(array) => array,
+        None => &[],
+    }
+}
+
+fn boolean(json: &Json) -> bool {
+    json.as_boolean().unwrap_or(false)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Severity {
+    Minor,
+    Major,
+}
+
+enum ChangeKind {
+    Added,
+    Removed,
+    Changed { old: String, new: String },
+}
+
+struct Change {
+    severity: Severity,
+    kind: ChangeKind,
+    /// The kind of the item, as in the JSON output.
+    item_kind: String,
+    key: String,
+    /// Why the change has the severity it has, when it isn't obvious.
+    note: Option<&'static str>,
+}
+
+impl ::std::fmt::Display for Change {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Major => "major",
+            Severity::Minor => "minor",
+        };
+        let action = match self.kind {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed { .. } => "changed",
+        };
+        write!(f, "{}: {} {} `{}`", severity, action, describe(&self.item_kind), self.key)?;
+        if let ChangeKind::Changed { ref old, ref new } = self.kind {
+            // Changes to attributes and kinds don't always show in signatures.
+            if old != new {
+                // Line up the lines of multi-line signatures, like those of
+                // macros.
+                write!(f, "\n    was: {}", old.replace('\n', "\n         "))?;
+                write!(f, "\n    now: {}", new.replace('\n', "\n         "))?;
+            }
+        }
+        if let Some(note) = self.note {
+            write!(f, "\n    note: {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// How a kind of item is called in the report.
+fn describe(kind: &str) -> &str {
+    match kind {
+        "mod" => "module",
+        "externcrate" => "extern crate",
+        "import" => "re-export",
+        "fn" => "function",
+        "type" => "type alias",
+        "tymethod" => "required method",
+        "structfield" => "field",
+        "associatedtype" => "associated type",
+        "constant" => "constant",
+        "associatedconstant" => "associated constant",
+        "foreigntype" => "foreign type",
+        "existential" => "existential type",
+        kind => kind,
+    }
+}
+
+struct ApiItem<'a> {
+    kind: &'a str,
+    /// The key of the item this one belongs to, for fields, variants and
+    /// associated items.
+    parent: Option<String>,
+    signature: String,
+    /// For the items whose bounds can be loosened without breaking code, the
+    /// signature without the generics, and the generics.
+    generics: Option<(String, Generics)>,
+    json: &'a Json,
+}
+
+/// The generic parameters and where clauses of an item, along with those of
+/// its impl for an item of an inherent impl.
+#[derive(Clone, Default)]
+struct Generics {
+    params: Vec<GenericParam>,
+    predicates: Vec<String>,
+}
+
+#[derive(Clone)]
+struct GenericParam {
+    name: String,
+    bounds: Vec<String>,
+    default: Option<String>,
+}
+
+impl Generics {
+    /// Whether code that compiles with the generics `old` still compiles with
+    /// these: the parameters of `old` have the same names and defaults and no
+    /// new bounds, the new parameters have defaults and there is no new where
+    /// clause. A `?Sized` bound loosens the others, so it must stay.
+    fn loosens(&self, old: &Generics) -> bool {
+        let is_maybe = |bound: &&String| bound.contains('?');
+        self.params.len() >= old.params.len() &&
+            old.params.iter().zip(&self.params).all(|(old, new)| {
+                old.name == new.name && old.default == new.default &&
+                    new.bounds.iter().all(|bound| old.bounds.contains(bound)) &&
+                    old.bounds.iter().filter(is_maybe).all(|bound| new.bounds.contains(bound))
+            }) &&
+            self.params[old.params.len()..].iter().all(|param| param.default.is_some()) &&
+            self.predicates.iter().all(|pred| old.predicates.contains(pred)) &&
+            old.predicates.iter().filter(is_maybe).all(|pred| self.predicates.contains(pred))
+    }
+}
+
+impl<'a> ApiItem<'a> {
+    /// Whether the item has fields that can't be seen from other crates.
+    fn fields_stripped(&self) -> bool {
+        boolean(get(get(self.json, "inner"), "fields_stripped"))
+    }
+
+    fn is_non_exhaustive(&self) -> bool {
+        array(get(self.json, "attrs")).iter().any(|attr| string(attr) == "#[non_exhaustive]")
+    }
+
+    /// Whether an associated item of a trait must be defined by every impl.
+    fn is_required(&self) -> bool {
+        match self.kind {
+            "tymethod" => true,
+            "associatedtype" | "associatedconstant" => {
+                get(get(self.json, "inner"), "default").is_null()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The public items of one version of a crate, by key.
+struct Api<'a> {
+    krate: &'a Json,
+    items: BTreeMap<String, ApiItem<'a>>,
+}
+
+impl<'a> Api<'a> {
+    fn new(krate: &'a Json) -> Api<'a> {
+        let mut api = Api {
+            krate,
+            items: BTreeMap::new(),
+        };
+        if let Some(root) = api.lookup(get(krate, "root")) {
+            let name = string(get(krate, "crate_name")).to_owned();
+            api.walk(root, name, None);
+        }
+        api
+    }
+
+    fn lookup(&self, id: &Json) -> Option<&'a Json> {
+        get(self.krate, "index").find(string(id))
+    }
+
+    /// The items whose ids are in the member `key` of `inner`.
+    fn children(&self, inner: &Json, key: &str) -> Vec<&'a Json> {
+        array(get(inner, key)).iter().filter_map(|id| self.lookup(id)).collect()
+    }
+
+    fn add(&mut self, item: &'a Json, key: String, parent: Option<&str>, signature: String,
+           generics: Option<(String, Generics)>) {
+        self.items.insert(key, ApiItem {
+            kind: string(get(item, "kind")),
+            parent: parent.map(|p| p.to_owned()),
+            signature,
+            generics,
+            json: item,
+        });
+    }
+
+    /// Adds `item` and the public items it contains.
+    fn walk(&mut self, item: &'a Json, key: String, parent: Option<&str>) {
+        let inner = get(item, "inner");
+        let kind = string(get(item, "kind"));
+        match kind {
+            "mod" => {
+                for child in self.children(inner, "items") {
+                    if string(get(child, "kind")) == "impl" {
+                        self.walk_impl(child);
+                    } else if is_public(child) {
+                        let child_key = format!("{}::{}", key, child_name(child));
+                        self.walk(child, child_key, None);
+                    }
+                }
+            }
+            "struct" | "union" | "variant" => {
+                for field in self.children(inner, "fields") {
+                    // The fields of variants are as public as the enum.
+                    if is_public(field) || kind == "variant" {
+                        let field_key = format!("{}::{}", key, child_name(field));
+                        self.walk(field, field_key, Some(&key));
+                    }
+                }
+            }
+            "enum" => {
+                for variant in self.children(inner, "variants") {
+                    let variant_key = format!("{}::{}", key, child_name(variant));
+                    self.walk(variant, variant_key, Some(&key));
+                }
+            }
+            "trait" => {
+                for trait_item in self.children(inner, "items") {
+                    let item_key = format!("{}::{}", key, child_name(trait_item));
+                    self.walk(trait_item, item_key, Some(&key));
+                }
+            }
+            _ => {}
+        }
+        let signature = self.signature(item);
+        // Loosening the bounds of a trait or of its items breaks the impls
+        // which rely on them.
+        let generics = match kind {
+            "struct" | "union" | "enum" | "type" | "fn" => {
+                let skeleton = self.signature(&without_generics(item));
+                Some((skeleton, self.generics(get(inner, "generics"))))
+            }
+            _ => None,
+        };
+        self.add(item, key, parent, signature, generics);
+    }
+
+    /// Adds a trait impl and its associated types and constants, or the public
+    /// items of an inherent impl.
+    ///
+    /// The methods of a trait impl are left out: their signatures are fixed
+    /// by the trait. The items of an inherent impl are keyed by the impl's
+    /// parameters and self type, since a type can have several inherent impls
+    /// with items of the same name, like `impl Foo<u8>` and `impl Foo<u16>`.
+    fn walk_impl(&mut self, item: &'a Json) {
+        let inner = get(item, "inner");
+        // Blanket impls come from the crates of their traits.
+        if !get(inner, "blanket_impl").is_null() {
+            return;
+        }
+        let for_ = get(inner, "for");
+        let trait_ = get(inner, "trait");
+        let header = self.impl_header(inner);
+        let generics = self.generics(get(inner, "generics"));
+        let skeleton = self.impl_header(get(&without_generics(item), "inner"));
+
+        if trait_.is_null() {
+            // Removing the type removes its impls.
+            let type_path = self.type_path(for_);
+            let params = generics.params.iter().map(|p| &p.name[..]).collect::<Vec<_>>();
+            let impl_key = format!("impl{} {}",
+                                   if params.is_empty() {
+                                       String::new()
+                                   } else {
+                                       format!("<{}>", params.join(", "))
+                                   },
+                                   self.ty(for_));
+            for child in self.children(inner, "items") {
+                if is_public(child) {
+                    let key = format!("{}::{}", impl_key, child_name(child));
+                    let signature = format!("{} {{ {} }}", header, self.signature(child));
+                    let child_skeleton = format!("{} {{ {} }}", skeleton,
+                                                 self.signature(&without_generics(child)));
+                    let mut child_generics = self.generics(get(get(child, "inner"), "generics"));
+                    child_generics.params.splice(0..0, generics.params.iter().cloned());
+                    child_generics.predicates.extend(generics.predicates.iter().cloned());
+                    self.add(child, key, Some(&type_path), signature,
+                             Some((child_skeleton, child_generics)));
+                }
+            }
+            return;
+        }
+
+        let key = format!("impl {}{} for {}",
+                          if boolean(get(inner, "negative")) { "!" } else { "" },
+                          self.ty(trait_),
+                          self.ty(for_));
+        for child in self.children(inner, "items") {
+            match string(get(child, "kind")) {
+                "type" | "associatedconstant" => {
+                    let child_key = format!("{}::{}", key, child_name(child));
+                    let signature = self.signature(child);
+                    self.add(child, child_key, Some(&key), signature, None);
+                }
+                _ => {}
+            }
+        }
+        self.add(item, key, None, header, Some((skeleton, generics)));
+    }
+
+    /// The path of the type an inherent impl is for, without its generic
+    /// arguments.
+    fn type_path(&self, ty: &Json) -> String {
+        let inner = get(ty, "inner");
+        match string(get(ty, "kind")) {
+            "resolved_path" => self.path(inner),
+            _ => self.ty(ty),
+        }
+    }
+
+    /// The full path of the item a resolved path refers to, if it's known.
+    fn path(&self, resolved: &Json) -> String {
+        let id = string(get(resolved, "id"));
+        let path = array(get(get(get(self.krate, "paths"), id), "path"));
+        if path.is_empty() {
+            string(get(resolved, "name")).to_owned()
+        } else {
+            path.iter().map(string).collect::<Vec<_>>().join("::")
+        }
+    }
+
+    /// The declaration of `item`, without its body or its visibility.
+    fn signature(&self, item: &Json) -> String {
+        let inner = get(item, "inner");
+        let name = child_name(item);
+        let generics = get(inner, "generics");
+        match string(get(item, "kind")) {
+            "fn" | "method" | "tymethod" => self.fn_signature(name, inner),
+            "struct" | "union" => {
+                let body = match string(get(inner, "struct_type")) {
+                    "unit" => ";",
+                    "tuple" => "(..);",
+                    _ => " { .. }",
+                };
+                format!("{} {}{}{}{}", string(get(item, "kind")), name,
+                        self.generic_params(generics), self.where_clause(generics), body)
+            }
+            "enum" => {
+                format!("enum {}{}{}", name, self.generic_params(generics),
+                        self.where_clause(generics))
+            }
+            "variant" => match string(get(inner, "variant_kind")) {
+                "tuple" => {
+                    let fields = array(get(inner, "fields")).iter()
+                                                            .map(|ty| self.ty(ty))
+                                                            .collect::<Vec<_>>();
+                    format!("{}({})", name, fields.join(", "))
+                }
+                "struct" => format!("{} {{ .. }}", name),
+                _ => name.to_owned(),
+            },
+            "structfield" => format!("{}: {}", name, self.ty(get(inner, "type"))),
+            "trait" => {
+                let bounds = array(get(inner, "bounds"));
+                format!("{}{}trait {}{}{}{}",
+                        if boolean(get(inner, "is_unsafe")) { "unsafe " } else { "" },
+                        if boolean(get(inner, "is_auto")) { "auto " } else { "" },
+                        name,
+                        self.generic_params(generics),
+                        if bounds.is_empty() {
+                            String::new()
+                        } else {
+                            format!(": {}", self.bounds(bounds))
+                        },
+                        self.where_clause(generics))
+            }
+            "type" => {
+                format!("type {}{}{} = {};", name, self.generic_params(generics),
+                        self.where_clause(generics), self.ty(get(inner, "type")))
+            }
+            "existential" => {
+                format!("existential type {}{}: {};", name, self.generic_params(generics),
+                        self.bounds(array(get(inner, "bounds"))))
+            }
+            "static" => {
+                format!("static {}{}: {};",
+                        if boolean(get(inner, "mutable")) { "mut " } else { "" },
+                        name,
+                        self.ty(get(inner, "type")))
+            }
+            "constant" => format!("const {}: {};", name, self.ty(get(inner, "type"))),
+            "associatedconstant" => {
+                format!("const {}: {}{};", name, self.ty(get(inner, "type")),
+                        if get(inner, "default").is_null() { "" } else { " = .." })
+            }
+            "associatedtype" => {
+                let bounds = array(get(inner, "bounds"));
+                let default = get(inner, "default");
+                format!("type {}{}{};",
+                        name,
+                        if bounds.is_empty() {
+                            String::new()
+                        } else {
+                            format!(": {}", self.bounds(bounds))
+                        },
+                        if default.is_null() {
+                            String::new()
+                        } else {
+                            format!(" = {}", self.ty(default))
+                        })
+            }
+            "impl" => self.impl_header(inner),
+            "import" => {
+                let source = string(get(inner, "source"));
+                if boolean(get(inner, "glob")) {
+                    format!("use {}::*;", source)
+                } else if source.rsplit("::").next() == Some(name) {
+                    format!("use {};", source)
+                } else {
+                    format!("use {} as {};", source, name)
+                }
+            }
+            "externcrate" => match get(inner, "source").as_string() {
+                Some(source) => format!("extern crate {} as {};", source, name),
+                None => format!("extern crate {};", name),
+            },
+            "macro" => string(get(inner, "source")).to_owned(),
+            "foreigntype" => format!("type {};", name),
+            kind => format!("{} {}", kind, name),
+        }
+    }
+
+    fn fn_signature(&self, name: &str, inner: &Json) -> String {
+        let header = get(inner, "header");
+        let decl = get(inner, "decl");
+        let generics = get(inner, "generics");
+        let mut s = String::new();
+        if boolean(get(header, "const")) {
+            s.push_str("const ");
+        }
+        if boolean(get(header, "unsafe")) {
+            s.push_str("unsafe ");
+        }
+        if boolean(get(header, "async")) {
+            s.push_str("async ");
+        }
+        s.push_str(&abi(string(get(header, "abi"))));
+        s.push_str("fn ");
+        s.push_str(name);
+        s.push_str(&self.generic_params(generics));
+        s.push_str(&self.fn_decl(decl, true));
+        s.push_str(&self.where_clause(generics));
+        s
+    }
+
+    /// The arguments and the return type of a function, with the names of
+    /// the arguments if `with_names` is set.
+    fn fn_decl(&self, decl: &Json, with_names: bool) -> String {
+        let mut inputs = array(get(decl, "inputs")).iter().map(|input| {
+            let pair = array(input);
+            let ty = pair.get(1).map(|ty| self.ty(ty)).unwrap_or_default();
+            let name = pair.get(0).map(string).unwrap_or("");
+            if with_names && !name.is_empty() {
+                format!("{}: {}", name, ty)
+            } else {
+                ty
+            }
+        }).collect::<Vec<_>>();
+        if boolean(get(decl, "variadic")) {
+            inputs.push("...".to_owned());
+        }
+        let output = get(decl, "output");
+        if output.is_null() {
+            format!("({})", inputs.join(", "))
+        } else {
+            format!("({}) -> {}", inputs.join(", "), self.ty(output))
+        }
+    }
+
+    fn impl_header(&self, inner: &Json) -> String {
+        let generics = get(inner, "generics");
+        let trait_ = get(inner, "trait");
+        let mut s = String::new();
+        if boolean(get(inner, "is_unsafe")) {
+            s.push_str("unsafe ");
+        }
+        s.push_str("impl");
+        s.push_str(&self.generic_params(generics));
+        s.push(' ');
+        if !trait_.is_null() {
+            if boolean(get(inner, "negative")) {
+                s.push('!');
+            }
+            s.push_str(&self.ty(trait_));
+            s.push_str(" for ");
+        }
+        s.push_str(&self.ty(get(inner, "for")));
+        s.push_str(&self.where_clause(generics));
+        s
+    }
+
+    /// `<'a, T: Bound = Default>`, leaving out the parameters of `impl Trait`
+    /// arguments.
+    fn generic_params(&self, generics: &Json) -> String {
+        let params = self.params(array(get(generics, "params")));
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", params.join(", "))
+        }
+    }
+
+    fn params(&self, params: &[Json]) -> Vec<String> {
+        params.iter().filter(|param| !boolean(get(param, "synthetic"))).map(|param| {
+            let mut s = string(get(param, "name")).to_owned();
+            let bounds = array(get(param, "bounds"));
+            if !bounds.is_empty() {
+                s.push_str(": ");
+                s.push_str(&self.bounds(bounds));
+            }
+            let default = get(param, "default");
+            if !default.is_null() {
+                s.push_str(" = ");
+                s.push_str(&self.ty(default));
+            }
+            s
+        }).collect()
+    }
+
+    /// The generics of an item, with each bound on its own so that bounds
+    /// can be compared one by one.
+    fn generics(&self, generics: &Json) -> Generics {
+        let params = array(get(generics, "params")).iter().filter(|param| {
+            !boolean(get(param, "synthetic"))
+        }).map(|param| {
+            let default = get(param, "default");
+            GenericParam {
+                name: string(get(param, "name")).to_owned(),
+                bounds: array(get(param, "bounds")).chunks(1).map(|b| self.bounds(b)).collect(),
+                default: if default.is_null() { None } else { Some(self.ty(default)) },
+            }
+        }).collect();
+        Generics {
+            params,
+            predicates: self.predicates(generics),
+        }
+    }
+
+    fn where_clause(&self, generics: &Json) -> String {
+        let predicates = self.predicates(generics);
+        if predicates.is_empty() {
+            String::new()
+        } else {
+            format!(" where {}", predicates.join(", "))
+        }
+    }
+
+    fn predicates(&self, generics: &Json) -> Vec<String> {
+        array(get(generics, "where_predicates")).iter().map(|pred| {
+            let inner = get(pred, "inner");
+            match string(get(pred, "kind")) {
+                "bound_predicate" => {
+                    format!("{}: {}", self.ty(get(inner, "type")),
+                            self.bounds(array(get(inner, "bounds"))))
+                }
+                "region_predicate" => {
+                    format!("{}: {}", string(get(inner, "lifetime")),
+                            self.bounds(array(get(inner, "bounds"))))
+                }
+                _ => format!("{} == {}", self.ty(get(inner, "lhs")), self.ty(get(inner, "rhs"))),
+            }
+        }).collect()
+    }
+
+    fn bounds(&self, bounds: &[Json]) -> String {
+        bounds.iter().map(|bound| {
+            let inner = get(bound, "inner");
+            match string(get(bound, "kind")) {
+                "trait_bound" => {
+                    let params = self.params(array(get(inner, "generic_params")));
+                    format!("{}{}{}",
+                            if params.is_empty() {
+                                String::new()
+                            } else {
+                                format!("for<{}> ", params.join(", "))
+                            },
+                            if boolean(get(inner, "maybe")) { "?" } else { "" },
+                            self.ty(get(inner, "trait")))
+                }
+                _ => string(inner).to_owned(),
+            }
+        }).collect::<Vec<_>>().join(" + ")
+    }
+
+    fn generic_args(&self, args: &Json) -> String {
+        let inner = get(args, "inner");
+        match string(get(args, "kind")) {
+            "angle_bracketed" => {
+                let mut list = array(get(inner, "lifetimes")).iter()
+                                                              .map(|l| string(l).to_owned())
+                                                              .collect::<Vec<_>>();
+                list.extend(array(get(inner, "types")).iter().map(|ty| self.ty(ty)));
+                list.extend(array(get(inner, "bindings")).iter().map(|binding| {
+                    format!("{} = {}", string(get(binding, "name")),
+                            self.ty(get(binding, "type")))
+                }));
+                format!("<{}>", list.join(", "))
+            }
+            "parenthesized" => {
+                let inputs = array(get(inner, "inputs")).iter()
+                                                         .map(|ty| self.ty(ty))
+                                                         .collect::<Vec<_>>();
+                let output = get(inner, "output");
+                if output.is_null() {
+                    format!("({})", inputs.join(", "))
+                } else {
+                    format!("({}) -> {}", inputs.join(", "), self.ty(output))
+                }
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn ty(&self, ty: &Json) -> String {
+        let inner = get(ty, "inner");
+        match string(get(ty, "kind")) {
+            "resolved_path" => {
+                let mut s = if boolean(get(inner, "is_generic")) {
+                    string(get(inner, "name")).to_owned()
+                } else {
+                    self.path(inner)
+                };
+                s.push_str(&self.generic_args(get(inner, "args")));
+                let bounds = array(get(inner, "bounds"));
+                if !bounds.is_empty() {
+                    s.push_str(" + ");
+                    s.push_str(&self.bounds(bounds));
+                }
+                s
+            }
+            "generic" | "primitive" => string(inner).to_owned(),
+            "function_pointer" => {
+                let params = self.params(array(get(inner, "generic_params")));
+                format!("{}{}{}fn{}",
+                        if params.is_empty() {
+                            String::new()
+                        } else {
+                            format!("for<{}> ", params.join(", "))
+                        },
+                        if boolean(get(inner, "unsafe")) { "unsafe " } else { "" },
+                        abi(string(get(inner, "abi"))),
+                        self.fn_decl(get(inner, "decl"), false))
+            }
+            "tuple" => {
+                let types = array(inner).iter().map(|ty| self.ty(ty)).collect::<Vec<_>>();
+                if types.len() == 1 {
+                    format!("({},)", types[0])
+                } else {
+                    format!("({})", types.join(", "))
+                }
+            }
+            "slice" => format!("[{}]", self.ty(inner)),
+            "array" => format!("[{}; {}]", self.ty(get(inner, "type")), string(get(inner, "len"))),
+            "never" => "!".to_owned(),
+            "unique" => format!("Box<{}>", self.ty(inner)),
+            "raw_pointer" => {
+                format!("*{} {}", if boolean(get(inner, "mutable")) { "mut" } else { "const" },
+                        self.ty(get(inner, "type")))
+            }
+            "borrowed_ref" => {
+                let mut s = "&".to_owned();
+                if let Some(lifetime) = get(inner, "lifetime").as_string() {
+                    s.push_str(lifetime);
+                    s.push(' ');
+                }
+                if boolean(get(inner, "mutable")) {
+                    s.push_str("mut ");
+                }
+                s.push_str(&self.ty(get(inner, "type")));
+                s
+            }
+            "qualified_path" => {
+                format!("<{} as {}>::{}", self.ty(get(inner, "self_type")),
+                        self.ty(get(inner, "trait")), string(get(inner, "name")))
+            }
+            "impl_trait" => format!("impl {}", self.bounds(array(inner))),
+            _ => "_".to_owned(),
+        }
+    }
+}
+
+fn is_public(item: &Json) -> bool {
+    string(get(item, "visibility")) == "public"
+}
+
+/// The name an item is known by in its parent. Re-exports are named after
+/// what they import.
+fn child_name(item: &Json) -> &str {
+    if string(get(item, "kind")) == "import" {
+        let inner = get(item, "inner");
+        if boolean(get(inner, "glob")) {
+            return "*";
+        }
+        if let Some(name) = get(inner, "name").as_string() {
+            return name;
+        }
+    }
+    string(get(item, "name"))
+}
+
+/// `item` without its generic parameters and where clauses.
+fn without_generics(item: &Json) -> Json {
+    let mut item = item.clone();
+    if let Json::Object(ref mut item) = item {
+        if let Some(&mut Json::Object(ref mut inner)) = item.get_mut("inner") {
+            inner.remove("generics");
+        }
+    }
+    item
+}
+
+fn abi(abi: &str) -> String {
+    if abi.is_empty() || abi == "Rust" {
+        String::new()
+    } else {
+        format!("extern {:?} ", abi)
+    }
+}
+
+fn compare(old: &Api, new: &Api) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let change = |severity, kind, item: &ApiItem, key: &str, note: Option<&'static str>| Change {
+        severity,
+        kind,
+        item_kind: item.kind.to_owned(),
+        key: key.to_owned(),
+        note,
+    };
+
+    for (key, old_item) in &old.items {
+        let new_item = match new.items.get(key) {
+            Some(item) => item,
+            None => {
+                // Removing an item removes everything in it.
+                let parent_removed = old_item.parent.as_ref().map_or(false, |parent| {
+                    old.items.contains_key(parent) && !new.items.contains_key(parent)
+                });
+                if !parent_removed {
+                    changes.push(change(Severity::Major, ChangeKind::Removed, old_item, key,
+                                        None));
+                }
+                continue;
+            }
+        };
+
+        if old_item.kind != new_item.kind || old_item.signature != new_item.signature {
+            let (severity, note) = classify_change(old_item, new_item);
+            let kind = ChangeKind::Changed {
+                old: old_item.signature.clone(),
+                new: new_item.signature.clone(),
+            };
+            changes.push(change(severity, kind, new_item, key, note));
+        } else if !old_item.fields_stripped() && new_item.fields_stripped() {
+            changes.push(change(Severity::Major, ChangeKind::Changed {
+                old: old_item.signature.clone(),
+                new: new_item.signature.clone(),
+            }, new_item, key, Some("it has private fields now, so it can't be built \
+                                    outside of the crate anymore")));
+        } else if !old_item.is_non_exhaustive() && new_item.is_non_exhaustive() {
+            changes.push(change(Severity::Major, ChangeKind::Changed {
+                old: old_item.signature.clone(),
+                new: new_item.signature.clone(),
+            }, new_item, key, Some("it is `#[non_exhaustive]` now")));
+        }
+    }
+
+    for (key, new_item) in &new.items {
+        if old.items.contains_key(key) {
+            continue;
+        }
+        let parent = match new_item.parent {
+            Some(ref parent) => match old.items.get(parent) {
+                Some(parent) => Some(parent),
+                // Adding an item adds everything in it.
+                None if new.items.contains_key(parent) => continue,
+                None => None,
+            },
+            None => None,
+        };
+        let (severity, note) = match parent {
+            Some(parent) => classify_addition(parent, new_item),
+            None => (Severity::Minor, None),
+        };
+        changes.push(change(severity, ChangeKind::Added, new_item, key, note));
+    }
+
+    changes.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.key.cmp(&b.key)));
+    changes
+}
+
+/// The severity of adding `item` to `parent`, which was already there.
+fn classify_addition(parent: &ApiItem, item: &ApiItem) -> (Severity, Option<&'static str>) {
+    match (parent.kind, item.kind) {
+        ("trait", _) if item.is_required() => {
+            (Severity::Major, Some("the existing impls of the trait don't define it"))
+        }
+        ("struct", "structfield") |
+        ("union", "structfield") |
+        ("variant", "structfield") if !parent.fields_stripped() &&
+                                      !parent.is_non_exhaustive() => {
+            (Severity::Major, Some("existing struct expressions and patterns don't mention it"))
+        }
+        ("enum", "variant") if !parent.is_non_exhaustive() => {
+            (Severity::Major, Some("existing exhaustive matches don't cover it"))
+        }
+        _ => (Severity::Minor, None),
+    }
+}
+
+/// The severity of a change to the kind or signature of an item.
+fn classify_change(old: &ApiItem, new: &ApiItem) -> (Severity, Option<&'static str>) {
+    match (old.kind, new.kind) {
+        ("tymethod", "method") if old.signature == new.signature => {
+            (Severity::Minor, Some("it has a default implementation now"))
+        }
+        ("method", "tymethod") if old.signature == new.signature => {
+            (Severity::Major, Some("it doesn't have a default implementation anymore"))
+        }
+        ("macro", "macro") => {
+            // Only the matchers are part of the source of a macro, one rule
+            // per line, so keeping all the old lines keeps all the old rules.
+            let new_lines = new.signature.lines().collect::<Vec<_>>();
+            if old.signature.lines().all(|line| new_lines.contains(&line)) {
+                (Severity::Minor, Some("it only has new rules"))
+            } else {
+                (Severity::Major, None)
+            }
+        }
+        _ if old.kind == new.kind => match (&old.generics, &new.generics) {
+            (&Some((ref old_skeleton, ref old_generics)),
+             &Some((ref new_skeleton, ref new_generics)))
+                if old_skeleton == new_skeleton && new_generics.loosens(old_generics) => {
+                (Severity::Minor,
+                 Some("it only has looser bounds or new type parameters with defaults"))
+            }
+            _ => (Severity::Major, None),
+        },
+        _ => (Severity::Major, None),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item<'a>(kind: &'a str, signature: &str, json: &'a Json) -> ApiItem<'a> {
+        ApiItem {
+            kind,
+            parent: None,
+            signature: signature.to_owned(),
+            generics: None,
+            json,
+        }
+    }
+
+    fn param(name: &str, bounds: &[&str], default: Option<&str>) -> GenericParam {
+        GenericParam {
+            name: name.to_owned(),
+            bounds: bounds.iter().map(|b| b.to_string()).collect(),
+            default: default.map(|d| d.to_owned()),
+        }
+    }
+
+    fn generic<'a>(kind: &'a str, skeleton: &str, params: Vec<GenericParam>,
+                   predicates: &[&str], json: &'a Json) -> ApiItem<'a> {
+        let mut item = item(kind, "", json);
+        item.generics = Some((skeleton.to_owned(), Generics {
+            params,
+            predicates: predicates.iter().map(|p| p.to_string()).collect(),
+        }));
+        item
+    }
+
+    #[test]
+    fn additions() {
+        let null = Json::Null;
+        let stripped: Json = r#"{"inner": {"fields_stripped": true}}"#.parse().unwrap();
+        let non_exhaustive: Json = r#"{"attrs": ["#[non_exhaustive]"]}"#.parse().unwrap();
+        let defaulted: Json = r#"{"inner": {"default": "u8"}}"#.parse().unwrap();
+
+        let tr = item("trait", "trait Tr", &null);
+        assert_eq!(classify_addition(&tr, &item("tymethod", "fn f(&self)", &null)).0,
+                   Severity::Major);
+        assert_eq!(classify_addition(&tr, &item("method", "fn f(&self)", &null)).0,
+                   Severity::Minor);
+        assert_eq!(classify_addition(&tr, &item("associatedtype", "type T;", &null)).0,
+                   Severity::Major);
+        assert_eq!(classify_addition(&tr, &item("associatedtype", "type T = u8;", &defaulted)).0,
+                   Severity::Minor);
+
+        let field = item("structfield", "b: u8", &null);
+        assert_eq!(classify_addition(&item("struct", "struct S { .. }", &null), &field).0,
+                   Severity::Major);
+        assert_eq!(classify_addition(&item("struct", "struct S { .. }", &stripped), &field).0,
+                   Severity::Minor);
+        assert_eq!(classify_addition(&item("struct", "struct S { .. }", &non_exhaustive),
+                                     &field).0,
+                   Severity::Minor);
+
+        let variant = item("variant", "B", &null);
+        assert_eq!(classify_addition(&item("enum", "enum E", &null), &variant).0,
+                   Severity::Major);
+        assert_eq!(classify_addition(&item("enum", "enum E", &non_exhaustive), &variant).0,
+                   Severity::Minor);
+
+        let method = item("method", "impl S { fn new() -> S }", &null);
+        assert_eq!(classify_addition(&item("struct", "struct S;", &null), &method).0,
+                   Severity::Minor);
+    }
+
+    #[test]
+    fn kind_and_macro_changes() {
+        let null = Json::Null;
+        let required = item("tymethod", "fn f(&self)", &null);
+        let provided = item("method", "fn f(&self)", &null);
+        assert_eq!(classify_change(&required, &provided).0, Severity::Minor);
+        assert_eq!(classify_change(&provided, &required).0, Severity::Major);
+
+        let old = item("macro", "() => { ... };", &null);
+        let more = item("macro", "() => { ... };\n($e:expr) => { ... };", &null);
+        assert_eq!(classify_change(&old, &more).0, Severity::Minor);
+        assert_eq!(classify_change(&more, &old).0, Severity::Major);
+
+        let old = item("fn", "fn f(x: u32)", &null);
+        let new = item("fn", "fn f(x: u64)", &null);
+        assert_eq!(classify_change(&old, &new).0, Severity::Major);
+        let new = item("constant", "const f: u32;", &null);
+        assert_eq!(classify_change(&old, &new).0, Severity::Major);
+    }
+
+    #[test]
+    fn generics_changes() {
+        let null = Json::Null;
+        let change = |old: ApiItem, new: ApiItem| classify_change(&old, &new).0;
+        let skeleton = "fn f(x: T)";
+
+        // Loosened bounds.
+        assert_eq!(change(generic("fn", skeleton, vec![param("T", &["Clone"], None)], &[], &null),
+                          generic("fn", skeleton, vec![param("T", &[], None)], &[], &null)),
+                   Severity::Minor);
+        assert_eq!(change(generic("fn", skeleton, vec![param("T", &[], None)],
+                                  &["T: Clone"], &null),
+                          generic("fn", skeleton, vec![param("T", &[], None)], &[], &null)),
+                   Severity::Minor);
+        // Tightened bounds.
+        assert_eq!(change(generic("fn", skeleton, vec![param("T", &[], None)], &[], &null),
+                          generic("fn", skeleton, vec![param("T", &["Clone"], None)], &[], &null)),
+                   Severity::Major);
+        assert_eq!(change(generic("fn", skeleton, vec![param("T", &[], None)], &[], &null),
+                          generic("fn", skeleton, vec![param("T", &[], None)],
+                                  &["T: Clone"], &null)),
+                   Severity::Major);
+        assert_eq!(change(generic("fn", skeleton, vec![param("T", &["?Sized"], None)], &[], &null),
+                          generic("fn", skeleton, vec![param("T", &[], None)], &[], &null)),
+                   Severity::Major);
+        // A bound loosened along with another change.
+        assert_eq!(change(generic("fn", skeleton, vec![param("T", &["Clone"], None)], &[], &null),
+                          generic("fn", "fn f(x: &T)", vec![param("T", &[], None)], &[], &null)),
+                   Severity::Major);
+
+        // New type parameters.
+        let skeleton = "struct S { .. }";
+        let old = || generic("struct", skeleton, vec![param("T", &[], None)], &[], &null);
+        assert_eq!(change(old(), generic("struct", skeleton,
+                                         vec![param("T", &[], None), param("A", &[], Some("()"))],
+                                         &[], &null)),
+                   Severity::Minor);
+        assert_eq!(change(old(), generic("struct", skeleton,
+                                         vec![param("T", &[], None), param("A", &[], None)],
+                                         &[], &null)),
+                   Severity::Major);
+        assert_eq!(change(old(), generic("struct", skeleton,
+                                         vec![param("A", &[], Some("()")), param("T", &[], None)],
+                                         &[], &null)),
+                   Severity::Major);
+        assert_eq!(change(old(), generic("struct", skeleton, vec![param("T", &[], Some("u8"))],
+                                         &[], &null)),
+                   Severity::Major);
+
+        // Items whose bounds can't be loosened, like the items of traits.
+        let old = item("tymethod", "fn f<T: Clone>(x: T)", &null);
+        let new = item("tymethod", "fn f<T>(x: T)", &null);
+        assert_eq!(classify_change(&old, &new).0, Severity::Major);
+    }
+}
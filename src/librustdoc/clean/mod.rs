@@ -68,6 +68,7 @@ use visit_ast;
 use html::render::{cache, ExternalLocation};
 use html::item_type::ItemType;
 use html::markdown::markdown_links;
+use linkcheck::{self, BrokenLink};
 
 pub mod inline;
 pub mod cfg;
@@ -1059,7 +1060,7 @@ fn type_ns_kind(def: Def, path_str: &str) -> (&'static str, &'static str, String
     (kind, article, format!("{}@{}", kind, path_str))
 }
 
-fn span_of_attrs(attrs: &Attributes) -> syntax_pos::Span {
+crate fn span_of_attrs(attrs: &Attributes) -> syntax_pos::Span {
     if attrs.doc_strings.is_empty() {
         return DUMMY_SP;
     }
@@ -1068,10 +1069,37 @@ fn span_of_attrs(attrs: &Attributes) -> syntax_pos::Span {
     start.to(end)
 }
 
+/// The file and line of the link at `link_range` in `dox`, the documentation
+/// in `attrs`, as reported by `--check-links`.
+fn link_location(cx: &DocContext, attrs: &Attributes, dox: &str,
+                 link_range: Option<Range<usize>>) -> String {
+    let loc = cx.sess().codemap().lookup_char_pos(span_of_attrs(attrs).lo());
+    // Each line of the documentation comes from a line of the source.
+    let line = loc.line + link_range.map_or(0, |range| dox[..range.start].matches('\n').count());
+    format!("{}:{}", loc.file.name, line)
+}
+
+/// Records a link which won't work, for `--check-links`.
+fn broken_link(cx: &DocContext, attrs: &Attributes, dox: &str,
+               link_range: Option<Range<usize>>, link: &str, reason: String) {
+    let location = link_location(cx, attrs, dox, link_range);
+    cx.renderinfo.borrow_mut().broken_links.push(BrokenLink {
+        location,
+        link: link.to_owned(),
+        reason,
+    });
+}
+
 fn ambiguity_error(cx: &DocContext, attrs: &Attributes,
-                   path_str: &str,
+                   path_str: &str, dox: &str, link_range: Option<Range<usize>>,
                    article1: &str, kind1: &str, disambig1: &str,
                    article2: &str, kind2: &str, disambig2: &str) {
+    if cx.check_links {
+        let reason = format!("is both {} {} and {} {}; use `{}` or `{}`",
+                             article1, kind1, article2, kind2, disambig1, disambig2);
+        broken_link(cx, attrs, dox, link_range, path_str, reason);
+        return;
+    }
     let sp = span_of_attrs(attrs);
     cx.sess()
       .struct_span_warn(sp,
@@ -1302,6 +1330,10 @@ fn resolution_failure(
     dox: &str,
     link_range: Option<Range<usize>>,
 ) {
+    if cx.check_links {
+        broken_link(cx, attrs, dox, link_range, path_str, "cannot be resolved".to_owned());
+        return;
+    }
     let sp = span_of_attrs(attrs);
     let msg = format!("`[{}]` cannot be resolved, ignoring it...", path_str);
 
@@ -1383,6 +1415,12 @@ impl Clean<Attributes> for [ast::Attribute] {
                     continue;
                 }
                 let link = ori_link.replace("`", "");
+                // `[Vec#examples]` links to an anchor on the page of `Vec`.
+                let (link, anchor) = match link.find('#') {
+                    Some(0) => continue,
+                    Some(i) => (link[..i].to_owned(), Some(link[i + 1..].to_owned())),
+                    None => (link, None),
+                };
                 let (def, fragment) = {
                     let mut kind = PathKind::Unknown;
                     let path_str = if let Some(prefix) =
@@ -1444,6 +1482,7 @@ impl Clean<Attributes> for [ast::Attribute] {
                                     let (type_kind, article, type_disambig)
                                         = type_ns_kind(type_def.0, path_str);
                                     ambiguity_error(cx, &attrs, path_str,
+                                                    &dox, link_range.clone(),
                                                     article, type_kind, &type_disambig,
                                                     "a", "macro", &format!("macro@{}", path_str));
                                     continue;
@@ -1453,6 +1492,7 @@ impl Clean<Attributes> for [ast::Attribute] {
                                             .expect("struct and mod cases should have been \
                                                      caught in previous branch");
                                     ambiguity_error(cx, &attrs, path_str,
+                                                    &dox, link_range.clone(),
                                                     "a", value_kind, &value_disambig,
                                                     "a", "macro", &format!("macro@{}", path_str));
                                 }
@@ -1468,6 +1508,7 @@ impl Clean<Attributes> for [ast::Attribute] {
                                         let (type_kind, article, type_disambig)
                                             = type_ns_kind(type_def.0, path_str);
                                         ambiguity_error(cx, &attrs, path_str,
+                                                        &dox, link_range.clone(),
                                                         article, type_kind, &type_disambig,
                                                         "a", value_kind, &value_disambig);
                                         continue;
@@ -1493,11 +1534,40 @@ impl Clean<Attributes> for [ast::Attribute] {
                     }
                 };
 
+                if anchor.is_some() && fragment.is_some() {
+                    // Primitives, methods, fields and variants are already
+                    // linked to with an anchor.
+                    if cx.check_links {
+                        broken_link(cx, &attrs, &dox, link_range, &ori_link,
+                                    "has an anchor, but points to an item which is \
+                                     already an anchor of its page".to_owned());
+                    } else {
+                        resolution_failure(cx, &attrs, &ori_link.replace("`", ""), &dox,
+                                           link_range);
+                    }
+                    continue;
+                }
+
                 if let Def::PrimTy(_) = def {
                     attrs.links.push((ori_link, None, fragment));
                 } else {
                     let id = register_def(cx, def);
-                    attrs.links.push((ori_link, Some(id), fragment));
+                    if let Some(ref anchor) = anchor {
+                        if cx.check_links && !linkcheck::anchors(cx, def, id).contains(anchor) {
+                            let reason = format!("points to `#{}`, which isn't on the page \
+                                                  of the item", anchor);
+                            broken_link(cx, &attrs, &dox, link_range, &ori_link, reason);
+                            continue;
+                        }
+                    }
+                    if cx.check_links {
+                        // Whether the item has a page is only known once the
+                        // passes have run, see `linkcheck::run`.
+                        let location = link_location(cx, &attrs, &dox, link_range);
+                        cx.renderinfo.borrow_mut().link_locations
+                            .insert((span_of_attrs(&attrs), ori_link.clone()), location);
+                    }
+                    attrs.links.push((ori_link, Some(id), fragment.or(anchor)));
                 }
             }
 
@@ -30,6 +30,7 @@ use syntax::codemap;
 use syntax::edition::Edition;
 use syntax::feature_gate::UnstableFeatures;
 use syntax::json::JsonEmitter;
+use syntax::sarif::SarifEmitter;
 use errors;
 use errors::emitter::{Emitter, EmitterWriter};
 
@@ -85,6 +86,10 @@ pub struct DocContext<'a, 'tcx: 'a, 'rcx: 'a> {
     pub generated_synthetics: RefCell<FxHashSet<(DefId, DefId)>>,
     pub current_item_name: RefCell<Option<Name>>,
     pub all_traits: Vec<DefId>,
+    /// Whether intra-doc links are checked with `--check-links`, in which case
+    /// the broken ones are recorded in `renderinfo` instead of being warned
+    /// about.
+    pub check_links: bool,
 }
 
 impl<'a, 'tcx, 'rcx> DocContext<'a, 'tcx, 'rcx> {
@@ -156,6 +161,11 @@ pub fn new_handler(error_format: ErrorOutputType, codemap: Option<Lrc<codemap::C
                 true,
                 false)
         ),
+        ErrorOutputType::Sarif => {
+            let codemap = codemap.unwrap_or_else(
+                || Lrc::new(codemap::CodeMap::new(sessopts.file_path_mapping())));
+            Box::new(SarifEmitter::stderr(None, codemap))
+        }
     };
 
     errors::Handler::with_emitter_and_flags(
@@ -184,7 +194,8 @@ pub fn run_core(search_paths: SearchPaths,
                 error_format: ErrorOutputType,
                 cmd_lints: Vec<(String, lint::Level)>,
                 lint_cap: Option<lint::Level>,
-                describe_lints: bool) -> (clean::Crate, RenderInfo)
+                describe_lints: bool,
+                check_links: bool) -> (clean::Crate, RenderInfo)
 {
     // Parse, resolve, and typecheck the given crate.
 
@@ -386,6 +397,7 @@ pub fn run_core(search_paths: SearchPaths,
                 generated_synthetics: RefCell::new(FxHashSet()),
                 current_item_name: RefCell::new(None),
                 all_traits: tcx.all_traits(LOCAL_CRATE).to_vec(),
+                check_links,
             };
             debug!("crate: {:?}", tcx.hir.krate());
 
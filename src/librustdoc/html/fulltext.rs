@@ -0,0 +1,229 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The full-text search index, built with `--full-text-search`.
+//!
+//! The words of the documentation of every item in the search index are
+//! stemmed, and each resulting term is mapped to the items whose
+//! documentation contains it. The terms are split into shards by their first
+//! two characters, and each shard is written to `search-fulltext/<shard>.js`,
+//! which has a line for every crate documented in the output directory:
+//!
+//! ```text
+//! var fullTextShard = {};
+//! fullTextShard["mycrate"] = {"pars":[3,1,12],"path":[7]};
+//! addFullTextShard("pa", fullTextShard);
+//! ```
+//!
+//! Items are given by their position in the `items` of the crate in
+//! `search-index.js`, as the first position and then the difference between
+//! each position and the previous one. The search only loads the shards of
+//! the words it looks for, so the index works without a server, and
+//! documenting more crates only makes the shards longer.
+//!
+//! `static/main.js` splits, stems and shards the words of the search the same
+//! way, and the two have to be changed together.
+
+use std::collections::BTreeMap;
+
+use serialize::json::{Json, ToJson, as_json};
+
+/// Words longer than this, which are rarely searched for, are left out.
+const MAX_WORD_LEN: usize = 40;
+
+/// Words too common to be worth searching for. Kept sorted.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "have", "in", "is",
+    "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were", "will", "with",
+];
+
+/// The terms of the documentation of the items of a crate.
+#[derive(Default)]
+crate struct Index {
+    /// The items containing each term, by shard.
+    shards: BTreeMap<String, BTreeMap<String, Vec<usize>>>,
+}
+
+impl Index {
+    /// Adds the terms of `doc` to the index, for the item at position `item`.
+    /// Items must be added in increasing position.
+    crate fn add(&mut self, item: usize, doc: &str) {
+        for word in words(doc) {
+            let term = stem(&word);
+            let items = self.shards.entry(shard(&term))
+                                   .or_insert_with(BTreeMap::new)
+                                   .entry(term)
+                                   .or_insert_with(Vec::new);
+            if items.last() != Some(&item) {
+                items.push(item);
+            }
+        }
+    }
+
+    /// The line of `krate` in each shard file, by shard.
+    crate fn shard_lines(&self, krate: &str) -> BTreeMap<String, String> {
+        self.shards.iter().map(|(shard, terms)| {
+            let terms = terms.iter().map(|(term, items)| {
+                let mut last = 0;
+                let deltas = items.iter().map(|&item| {
+                    let delta = item - last;
+                    last = item;
+                    delta
+                }).collect::<Vec<_>>();
+                (term.clone(), deltas.to_json())
+            }).collect();
+            let line = format!("fullTextShard[{}] = {};", as_json(&krate), Json::Object(terms));
+            (shard.clone(), line)
+        }).collect()
+    }
+}
+
+/// Splits `text` into lowercase words, leaving out stop words and words too
+/// short or too long to be searched for.
+///
+/// Words are made of ASCII letters and digits, and of any non-ASCII
+/// character.
+crate fn words<'a>(text: &'a str) -> impl Iterator<Item = String> + 'a {
+    text.split(|c: char| c.is_ascii() && !c.is_ascii_alphanumeric())
+        .map(|word| word.to_lowercase())
+        .filter(|word| {
+            // Measured like JavaScript strings are.
+            let len = word.encode_utf16().count();
+            len >= 2 && len <= MAX_WORD_LEN && STOP_WORDS.binary_search(&&**word).is_err()
+        })
+}
+
+/// Reduces `word` to its stem, for English words written in ASCII, so that
+/// e.g. "parses", "parsed" and "parsing" are all found when searching for
+/// "parse".
+crate fn stem(word: &str) -> String {
+    let mut w = word.to_owned();
+    if w.len() < 3 || !w.is_ascii() {
+        return w;
+    }
+
+    // Plurals.
+    if w.ends_with("sses") {
+        let len = w.len();
+        w.truncate(len - 2);
+    } else if w.ends_with("ies") && w.len() > 4 {
+        let len = w.len();
+        w.truncate(len - 3);
+        w.push('y');
+    } else if w.ends_with('s') && !(w.ends_with("ss") || w.ends_with("us") ||
+                                    w.ends_with("is")) {
+        w.pop();
+    }
+
+    // Verb forms.
+    if w.ends_with("ied") && w.len() > 4 {
+        let len = w.len();
+        w.truncate(len - 3);
+        w.push('y');
+    } else if strip_suffix(&mut w, "ing", 3) || strip_suffix(&mut w, "ed", 2) {
+        // "running" is "run".
+        let undouble = {
+            let bytes = w.as_bytes();
+            let len = bytes.len();
+            len >= 2 && bytes[len - 1] == bytes[len - 2] &&
+                !b"aeioulsz".contains(&bytes[len - 1])
+        };
+        if undouble {
+            w.pop();
+        }
+    }
+
+    // Adverbs and nouns.
+    for suffix in &["ly", "ment", "ness"] {
+        if strip_suffix(&mut w, suffix, 3) {
+            break;
+        }
+    }
+
+    if w.len() > 2 && w.ends_with('e') {
+        w.pop();
+    }
+    w
+}
+
+/// Removes `suffix` from `w` if at least `min_len` characters are left.
+fn strip_suffix(w: &mut String, suffix: &str, min_len: usize) -> bool {
+    if w.ends_with(suffix) && w.len() - suffix.len() >= min_len {
+        let len = w.len() - suffix.len();
+        w.truncate(len);
+        true
+    } else {
+        false
+    }
+}
+
+/// The shard of `term`, which is named after its first two characters. Terms
+/// with non-ASCII characters there are put together: `x_` for the terms
+/// starting with `x` followed by one, and `_` for the ones starting with one.
+crate fn shard(term: &str) -> String {
+    let mut chars = term.chars();
+    match (chars.next(), chars.next()) {
+        (Some(a), Some(b)) if a.is_ascii() && b.is_ascii() => format!("{}{}", a, b),
+        (Some(a), _) if a.is_ascii() => format!("{}_", a),
+        _ => "_".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Index, shard, stem, words};
+
+    #[test]
+    fn test_words() {
+        let text = "Parses the `&str` into a Vec<u8>; see [`FromStr`](trait.FromStr.html)";
+        assert_eq!(words(text).collect::<Vec<_>>(),
+                   ["parses", "str", "into", "vec", "u8", "see", "fromstr", "trait", "fromstr",
+                    "html"]);
+        assert_eq!(words("Größe à la carte").collect::<Vec<_>>(), ["größe", "la", "carte"]);
+    }
+
+    #[test]
+    fn test_stem() {
+        for word in &["parse", "parses", "parsed", "parsing"] {
+            assert_eq!(stem(word), "pars");
+        }
+        assert_eq!(stem("copies"), "copy");
+        assert_eq!(stem("copied"), "copy");
+        assert_eq!(stem("running"), "run");
+        assert_eq!(stem("filled"), "fill");
+        assert_eq!(stem("classes"), "class");
+        assert_eq!(stem("status"), "status");
+        assert_eq!(stem("quickly"), "quick");
+        assert_eq!(stem("only"), "only");
+        assert_eq!(stem("größe"), "größe");
+        assert_eq!(stem("u8"), "u8");
+    }
+
+    #[test]
+    fn test_shard() {
+        assert_eq!(shard("pars"), "pa");
+        assert_eq!(shard("u8"), "u8");
+        assert_eq!(shard("aß"), "a_");
+        assert_eq!(shard("größe"), "gr");
+        assert_eq!(shard("ßa"), "_");
+    }
+
+    #[test]
+    fn test_index() {
+        let mut index = Index::default();
+        index.add(2, "Parses a path.");
+        index.add(5, "The parsed path, and its parent path.");
+        index.add(9, "Parse it");
+        let lines = index.shard_lines("foo");
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines["pa"],
+                   r#"fullTextShard["foo"] = {"parent":[5],"pars":[2,3,4],"path":[2,3]};"#);
+    }
+}
@@ -565,6 +565,17 @@ pub fn find_testable_code(
     Ok(())
 }
 
+/// Whether `doc` has a code block that would be tested as Rust code, such as
+/// an example.
+pub fn has_rust_code_block(doc: &str) -> bool {
+    Parser::new(doc).any(|event| match event {
+        Event::Start(Tag::CodeBlock(ref lang)) => {
+            lang.is_empty() || LangString::parse(lang, ErrorCodes::No).rust
+        }
+        _ => false,
+    })
+}
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct LangString {
     original: String,
@@ -863,6 +874,34 @@ pub fn markdown_links(md: &str) -> Vec<(String, Option<Range<usize>>)> {
     links
 }
 
+/// The ids which the headings of `md` get when it is rendered on a page whose
+/// ids are `ids`, in the same way as `HeadingLinks` does.
+pub fn heading_ids(md: &str, ids: &mut IdMap) -> Vec<String> {
+    let mut opts = Options::empty();
+    opts.insert(OPTION_ENABLE_TABLES);
+    opts.insert(OPTION_ENABLE_FOOTNOTES);
+
+    let mut headings = vec![];
+    let mut heading = None;
+    for event in Parser::new_ext(md, opts) {
+        match event {
+            Event::Start(Tag::Header(_)) => heading = Some(String::new()),
+            Event::End(Tag::Header(_)) => {
+                if let Some(id) = heading.take() {
+                    headings.push(ids.derive(id));
+                }
+            }
+            Event::Text(ref text) => {
+                if let Some(ref mut id) = heading {
+                    id.extend(text.chars().filter_map(slugify));
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
 #[derive(Default)]
 pub struct IdMap {
     map: HashMap<String, usize>,
@@ -922,7 +961,7 @@ fn test_unique_id() {
 #[cfg(test)]
 mod tests {
     use super::{ErrorCodes, LangString, Markdown, MarkdownHtml, IdMap};
-    use super::plain_summary_line;
+    use super::{has_rust_code_block, heading_ids, plain_summary_line};
     use std::cell::RefCell;
 
     #[test]
@@ -1010,6 +1049,15 @@ mod tests {
             <a href=\"#panics-1\">Panics</a></h1>");
     }
 
+    #[test]
+    fn test_heading_ids() {
+        let mut map = IdMap::new();
+        map.populate(vec!["main".to_owned()]);
+        assert_eq!(heading_ids("# Examples\n\ntext\n\n## The `main` function\n\n# Main\n\n\
+                                # Examples", &mut map),
+                   ["examples", "the-main-function", "main-1", "examples-1"]);
+    }
+
     #[test]
     fn test_plain_summary_line() {
         fn t(input: &str, expect: &str) {
@@ -1037,4 +1085,13 @@ mod tests {
         t("Struct<'a, T>", "<p>Struct&lt;'a, T&gt;</p>\n");
         t("Struct<br>", "<p>Struct&lt;br&gt;</p>\n");
     }
+
+    #[test]
+    fn test_has_rust_code_block() {
+        assert!(has_rust_code_block("Example:\n\n```\nlet x = 1;\n```"));
+        assert!(has_rust_code_block("```rust,no_run\nloop {}\n```"));
+        assert!(has_rust_code_block("    let indented = true;"));
+        assert!(!has_rust_code_block("```text\nnot code\n```"));
+        assert!(!has_rust_code_block("Just `inline` code."));
+    }
 }
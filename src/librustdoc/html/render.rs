@@ -38,7 +38,7 @@ pub use self::ExternalLocation::*;
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 use std::default::Default;
 use std::error;
 use std::fmt::{self, Display, Formatter, Write as FmtWrite};
@@ -58,6 +58,7 @@ use serialize::json::{ToJson, Json, as_json};
 use syntax::ast;
 use syntax::codemap::FileName;
 use syntax::feature_gate::UnstableFeatures;
+use syntax_pos::Span;
 use rustc::hir::def_id::{CrateNum, CRATE_DEF_INDEX, DefId};
 use rustc::middle::privacy::AccessLevels;
 use rustc::middle::stability;
@@ -73,9 +74,11 @@ use html::format::{AsyncSpace, ConstnessSpace};
 use html::format::{GenericBounds, WhereClause, href, AbiSpace};
 use html::format::{VisSpace, Method, UnsafetySpace, MutableSpace};
 use html::format::fmt_impl_for_trait_page;
+use html::fulltext;
 use html::item_type::ItemType;
 use html::markdown::{self, Markdown, MarkdownHtml, MarkdownSummaryLine, ErrorCodes, IdMap};
 use html::{highlight, layout};
+use linkcheck::BrokenLink;
 
 use minifier;
 
@@ -324,6 +327,9 @@ pub struct Cache {
     parent_stack: Vec<DefId>,
     parent_is_trait_impl: bool,
     search_index: Vec<IndexItem>,
+    /// Whether to keep the documentation of the items of the search index, to
+    /// build the full-text index.
+    full_text_search: bool,
     stripped_mod: bool,
     deref_trait_did: Option<DefId>,
     deref_mut_trait_did: Option<DefId>,
@@ -353,6 +359,11 @@ pub struct RenderInfo {
     pub deref_trait_did: Option<DefId>,
     pub deref_mut_trait_did: Option<DefId>,
     pub owned_box_did: Option<DefId>,
+    /// The links found to be broken while cleaning, with `--check-links`.
+    pub broken_links: Vec<BrokenLink>,
+    /// The file and line of the links which resolved, by the span of the
+    /// documentation they are in and their text, with `--check-links`.
+    pub link_locations: FxHashMap<(Span, String), String>,
 }
 
 /// Helper struct to render all source code to HTML pages
@@ -389,6 +400,8 @@ struct IndexItem {
     parent: Option<DefId>,
     parent_idx: Option<usize>,
     search_type: Option<IndexItemFunctionType>,
+    /// The documentation of the item, for the full-text index.
+    doc: Option<String>,
 }
 
 impl ToJson for IndexItem {
@@ -489,6 +502,7 @@ pub fn run(mut krate: clean::Crate,
            sort_modules_alphabetically: bool,
            themes: Vec<PathBuf>,
            enable_minification: bool,
+           full_text_search: bool,
            id_map: IdMap) -> Result<(), Error> {
     let src_root = match krate.src {
         FileName::Real(ref p) => match p.parent() {
@@ -571,6 +585,8 @@ pub fn run(mut krate: clean::Crate,
         deref_trait_did,
         deref_mut_trait_did,
         owned_box_did,
+        broken_links: _,
+        link_locations: _,
     } = renderinfo;
 
     let external_paths = external_paths.into_iter()
@@ -586,6 +602,7 @@ pub fn run(mut krate: clean::Crate,
         stack: Vec::new(),
         parent_stack: Vec::new(),
         search_index: Vec::new(),
+        full_text_search,
         parent_is_trait_impl: false,
         extern_locations: FxHashMap(),
         primitive_locations: FxHashMap(),
@@ -635,7 +652,7 @@ pub fn run(mut krate: clean::Crate,
     krate = cache.fold_crate(krate);
 
     // Build our search index
-    let index = build_index(&krate, &mut cache);
+    let (index, full_text_index) = build_index(&krate, &mut cache);
 
     // Freeze the cache now that the index has been built. Put an Arc into TLS
     // for future parallelization opportunities
@@ -643,21 +660,28 @@ pub fn run(mut krate: clean::Crate,
     CACHE_KEY.with(|v| *v.borrow_mut() = cache.clone());
     CURRENT_LOCATION_KEY.with(|s| s.borrow_mut().clear());
 
-    write_shared(&cx, &krate, &*cache, index, enable_minification)?;
+    write_shared(&cx, &krate, &*cache, index, full_text_index, enable_minification)?;
 
     // And finally render the whole crate's documentation
     cx.krate(krate)
 }
 
-/// Build the search index from the collected metadata
-fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
+/// Build the search index from the collected metadata, and the full-text
+/// index if it was asked for.
+fn build_index(krate: &clean::Crate, cache: &mut Cache) -> (String, Option<fulltext::Index>) {
     let mut nodeid_to_pathid = FxHashMap();
     let mut crate_items = Vec::with_capacity(cache.search_index.len());
     let mut crate_paths = Vec::<Json>::new();
+    let mut full_text_index = if cache.full_text_search {
+        Some(fulltext::Index::default())
+    } else {
+        None
+    };
 
     let Cache { ref mut search_index,
                 ref orphan_impl_items,
-                ref mut paths, .. } = *cache;
+                ref mut paths,
+                full_text_search, .. } = *cache;
 
     // Attach all orphan items to the type's definition if the type
     // has since been learned.
@@ -671,6 +695,7 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
                 parent: Some(did),
                 parent_idx: None,
                 search_type: get_index_search_type(&item),
+                doc: if full_text_search { item.collapsed_doc_value() } else { None },
             });
         }
     }
@@ -680,7 +705,11 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
     let mut lastpath = String::new();
     let mut lastpathid = 0usize;
 
-    for item in search_index {
+    for (pos, item) in search_index.iter_mut().enumerate() {
+        if let (Some(index), Some(doc)) = (full_text_index.as_mut(), item.doc.take()) {
+            index.add(pos, &doc);
+        }
+
         item.parent_idx = item.parent.map(|nodeid| {
             if nodeid_to_pathid.contains_key(&nodeid) {
                 *nodeid_to_pathid.get(&nodeid).unwrap()
@@ -712,17 +741,22 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> String {
     crate_data.insert("doc".to_owned(), Json::String(crate_doc));
     crate_data.insert("items".to_owned(), Json::Array(crate_items));
     crate_data.insert("paths".to_owned(), Json::Array(crate_paths));
+    if full_text_index.is_some() {
+        crate_data.insert("fulltext".to_owned(), Json::Boolean(true));
+    }
 
     // Collect the index into a string
-    format!("searchIndex[{}] = {};",
-            as_json(&krate.name),
-            Json::Object(crate_data))
+    let index = format!("searchIndex[{}] = {};",
+                        as_json(&krate.name),
+                        Json::Object(crate_data));
+    (index, full_text_index)
 }
 
 fn write_shared(cx: &Context,
                 krate: &clean::Crate,
                 cache: &Cache,
                 search_index: String,
+                full_text_index: Option<fulltext::Index>,
                 enable_minification: bool) -> Result<(), Error> {
     // Write out the shared files. Note that these are shared among all rustdoc
     // docs placed in the output directory, so this needs to be a synchronized
@@ -946,6 +980,45 @@ themePicker.onblur = handleThemeButtonsBlur;
     }
     try_err!(writeln!(&mut w, "initSearch(searchIndex);"), &dst);
 
+    // Update the full-text index. Every shard is rewritten, including the
+    // ones without words of this crate, to remove what an earlier run of
+    // rustdoc wrote for it.
+    let dst = cx.dst.join("search-fulltext");
+    let mut lines = match full_text_index {
+        Some(ref index) => index.shard_lines(&krate.name),
+        None => BTreeMap::new(),
+    };
+    let mut shards = lines.keys().cloned().collect::<BTreeSet<_>>();
+    if dst.exists() {
+        for entry in try_err!(fs::read_dir(&dst), &dst) {
+            let path = try_err!(entry, &dst).path();
+            if path.extension() == Some(OsStr::new("js")) {
+                if let Some(shard) = path.file_stem().and_then(|s| s.to_str()) {
+                    shards.insert(shard.to_owned());
+                }
+            }
+        }
+    } else if !lines.is_empty() {
+        try_err!(fs::create_dir_all(&dst), &dst);
+    }
+    for shard in &shards {
+        let path = dst.join(&format!("{}.js", shard));
+        let mut all_lines = try_err!(collect(&path, &krate.name, "fullTextShard"), &path);
+        all_lines.extend(lines.remove(shard));
+        if all_lines.is_empty() {
+            try_err!(fs::remove_file(&path), &path);
+            continue;
+        }
+        all_lines.sort();
+        let mut w = try_err!(File::create(&path), &path);
+        try_err!(writeln!(&mut w, "var fullTextShard = {{}};"), &path);
+        for line in &all_lines {
+            try_err!(writeln!(&mut w, "{}", line), &path);
+        }
+        try_err!(writeln!(&mut w, "addFullTextShard({}, fullTextShard);", as_json(shard)),
+                 &path);
+    }
+
     // Update the list of all implementors for traits
     let dst = cx.dst.join("implementors");
     for (&did, imps) in &cache.implementors {
@@ -1096,7 +1169,7 @@ fn clean_srcpath<F>(src_root: &Path, p: &Path, keep_filename: bool, mut f: F) wh
 
 /// Attempts to find where an external crate is located, given that we're
 /// rendering in to the specified source destination.
-fn extern_location(e: &clean::ExternalCrate, dst: &Path) -> ExternalLocation {
+pub fn extern_location(e: &clean::ExternalCrate, dst: &Path) -> ExternalLocation {
     // See if there's documentation generated into the local directory
     let local_location = dst.join(&e.name);
     if local_location.is_dir() {
@@ -1309,6 +1382,11 @@ impl DocFolder for Cache {
                             parent,
                             parent_idx: None,
                             search_type: get_index_search_type(&item),
+                            doc: if self.full_text_search {
+                                item.collapsed_doc_value()
+                            } else {
+                                None
+                            },
                         });
                     }
                 }
@@ -1495,6 +1573,7 @@ impl<'a> Cache {
                                 parent: None,
                                 parent_idx: None,
                                 search_type: get_index_search_type(&item),
+                                doc: None,
                             });
             }
         }
@@ -4466,7 +4545,7 @@ fn sidebar_enum(fmt: &mut fmt::Formatter, it: &clean::Item,
     Ok(())
 }
 
-fn item_ty_to_strs(ty: &ItemType) -> (&'static str, &'static str) {
+pub fn item_ty_to_strs(ty: &ItemType) -> (&'static str, &'static str) {
     match *ty {
         ItemType::ExternCrate |
         ItemType::Import          => ("reexports", "Re-exports"),
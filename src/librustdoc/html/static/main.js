@@ -420,6 +420,11 @@
         var INPUTS_DATA = 0;
         var OUTPUT_DATA = 1;
         var params = getQueryStringParams();
+        // The position in `searchIndex` of the crates with a full-text index.
+        var fullTextCrates = {};
+        // The full-text shards by name: `false` while loading, `null` if
+        // missing, and otherwise their terms by crate.
+        var fullTextShards = {};
 
         // Populate search bar with query string search term when provided,
         // but only if the input bar is empty. This avoid the obnoxious issue
@@ -1109,6 +1114,194 @@
             };
         }
 
+        // The full-text index is built by `html/fulltext.rs`, and the words of
+        // the query must be split, stemmed and sharded exactly the same way.
+        var FULL_TEXT_MAX_WORD_LEN = 40;
+        var FULL_TEXT_STOP_WORDS = [
+            "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "have", "in",
+            "is", "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were",
+            "will", "with",
+        ];
+
+        function fullTextWords(text) {
+            var words = [];
+            var parts = text.split(/[\x00-\x2f\x3a-\x40\x5b-\x60\x7b-\x7f]+/);
+            for (var i = 0; i < parts.length; ++i) {
+                var word = parts[i].toLowerCase();
+                if (word.length >= 2 && word.length <= FULL_TEXT_MAX_WORD_LEN &&
+                        FULL_TEXT_STOP_WORDS.indexOf(word) === -1) {
+                    words.push(word);
+                }
+            }
+            return words;
+        }
+
+        function fullTextStem(word) {
+            if (word.length < 3 || /[^\x00-\x7f]/.test(word)) {
+                return word;
+            }
+            function stripSuffix(suffix, minLen) {
+                if (word.endsWith(suffix) && word.length - suffix.length >= minLen) {
+                    word = word.substring(0, word.length - suffix.length);
+                    return true;
+                }
+                return false;
+            }
+
+            // Plurals.
+            if (word.endsWith("sses")) {
+                word = word.substring(0, word.length - 2);
+            } else if (word.endsWith("ies") && word.length > 4) {
+                word = word.substring(0, word.length - 3) + "y";
+            } else if (word.endsWith("s") && !(word.endsWith("ss") || word.endsWith("us") ||
+                                               word.endsWith("is"))) {
+                word = word.substring(0, word.length - 1);
+            }
+
+            // Verb forms.
+            if (word.endsWith("ied") && word.length > 4) {
+                word = word.substring(0, word.length - 3) + "y";
+            } else if (stripSuffix("ing", 3) || stripSuffix("ed", 2)) {
+                var last = word.charAt(word.length - 1);
+                if (word.length >= 2 && last === word.charAt(word.length - 2) &&
+                        "aeioulsz".indexOf(last) === -1) {
+                    word = word.substring(0, word.length - 1);
+                }
+            }
+
+            // Adverbs and nouns.
+            var suffixes = ["ly", "ment", "ness"];
+            for (var i = 0; i < suffixes.length; ++i) {
+                if (stripSuffix(suffixes[i], 3)) {
+                    break;
+                }
+            }
+
+            if (word.length > 2 && word.endsWith("e")) {
+                word = word.substring(0, word.length - 1);
+            }
+            return word;
+        }
+
+        function fullTextShardName(term) {
+            if (term.charCodeAt(0) >= 128) {
+                return "_";
+            }
+            if (term.length < 2 || term.charCodeAt(1) >= 128) {
+                return term.charAt(0) + "_";
+            }
+            return term.substring(0, 2);
+        }
+
+        function loadFullTextShard(shard) {
+            fullTextShards[shard] = false;
+            var script = document.createElement('script');
+            script.src = rootPath + 'search-fulltext/' + shard + '.js';
+            // Rerun the search once the shard is there, or known to be missing.
+            script.onerror = function() {
+                fullTextShards[shard] = null;
+                currentResults = null;
+                search();
+            };
+            document.getElementsByTagName('head')[0].appendChild(script);
+        }
+
+        window.addFullTextShard = function(shard, data) {
+            fullTextShards[shard] = data;
+            currentResults = null;
+            search();
+        };
+
+        /**
+         * Adds the items whose documentation has all the words of the query to
+         * the results found in their names, loading the parts of the
+         * full-text index it needs first.
+         */
+        function addFullTextResults(query, results) {
+            var hasFullText = false;
+            for (var name in fullTextCrates) {
+                if (fullTextCrates.hasOwnProperty(name)) {
+                    hasFullText = true;
+                    break;
+                }
+            }
+            // Type filters only apply to names.
+            if (!hasFullText || query.type) {
+                return;
+            }
+            var words = fullTextWords(query.query);
+            var terms = [];
+            var i;
+            for (i = 0; i < words.length; ++i) {
+                var term = fullTextStem(words[i]);
+                if (terms.indexOf(term) === -1) {
+                    terms.push(term);
+                }
+            }
+            if (terms.length === 0) {
+                return;
+            }
+
+            var loading = false;
+            for (i = 0; i < terms.length; ++i) {
+                var shard = fullTextShardName(terms[i]);
+                if (fullTextShards[shard] === undefined) {
+                    loadFullTextShard(shard);
+                }
+                if (fullTextShards[shard] === false) {
+                    loading = true;
+                }
+            }
+            if (loading) {
+                return;
+            }
+
+            var counts = {};
+            for (i = 0; i < terms.length; ++i) {
+                var data = fullTextShards[fullTextShardName(terms[i])];
+                if (!data) {
+                    return;
+                }
+                for (var crate in data) {
+                    if (!data.hasOwnProperty(crate) || !fullTextCrates.hasOwnProperty(crate) ||
+                            !data[crate].hasOwnProperty(terms[i])) {
+                        continue;
+                    }
+                    // Positions are given as the difference with the previous
+                    // one, and items follow their crate in `searchIndex`.
+                    var deltas = data[crate][terms[i]];
+                    var id = fullTextCrates[crate] + 1;
+                    for (var j = 0; j < deltas.length; ++j) {
+                        id += deltas[j];
+                        counts[id] = (counts[id] || 0) + 1;
+                    }
+                }
+            }
+
+            var others = results['others'];
+            var found = {};
+            for (i = 0; i < others.length; ++i) {
+                found[others[i].fullPath] = true;
+            }
+            for (var pos in counts) {
+                if (others.length >= MAX_RESULTS) {
+                    break;
+                }
+                if (!counts.hasOwnProperty(pos) || counts[pos] !== terms.length) {
+                    continue;
+                }
+                var obj = searchIndex[pos];
+                var res = buildHrefAndPath(obj);
+                obj.displayPath = pathSplitter(res[0]);
+                obj.fullPath = obj.displayPath + obj.name + '|' + obj.ty;
+                obj.href = res[1];
+                if (found[obj.fullPath] !== true) {
+                    found[obj.fullPath] = true;
+                    others.push(obj);
+                }
+            }
+        }
+
         function initSearchNav() {
             var hoverTimeout;
 
@@ -1466,7 +1659,9 @@
                 }
             }
 
-            showResults(execSearch(query, index));
+            var results = execSearch(query, index);
+            addFullTextResults(query, results);
+            showResults(results);
         }
 
         function buildIndex(rawSearchIndex) {
@@ -1475,6 +1670,9 @@
             for (var crate in rawSearchIndex) {
                 if (!rawSearchIndex.hasOwnProperty(crate)) { continue; }
 
+                if (rawSearchIndex[crate].fulltext === true) {
+                    fullTextCrates[crate] = searchIndex.length;
+                }
                 searchWords.push(crate);
                 searchIndex.push({
                     crate: crate,
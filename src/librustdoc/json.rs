@@ -0,0 +1,593 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The JSON backend, selected with `--output-format json`.
+//!
+//! The whole crate is written to a single `<crate>.json` file in the output
+//! directory. Its layout is:
+//!
+//! ```text
+//! {
+//!   "format_version": 1,
+//!   "crate_name": "foo",
+//!   "crate_version": "0.1.0" | null,
+//!   "root": "0:0",
+//!   "index": { "<id>": <item>, ... },
+//!   "paths": { "<id>": { "crate_id": 0, "path": ["foo", "Bar"], "kind": "struct" }, ... },
+//!   "external_crates": { "<crate id>": { "name": "std" }, ... }
+//! }
+//! ```
+//!
+//! Every item that wasn't stripped by a pass is in `index`, including trait
+//! impls and the synthetic impls of auto traits. Items refer to each other by
+//! id, and `paths` holds the full path of every item that can be named,
+//! including the external ones that types refer to.
+//!
+//! Any change to the layout that could break a consumer must bump
+//! `FORMAT_VERSION`.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use rustc::hir;
+use rustc::hir::def_id::DefId;
+use rustc::middle::stability;
+use serialize::json::{Json, ToJson};
+use syntax::print::pprust;
+
+use clean;
+use html::item_type::ItemType;
+use html::render::RenderInfo;
+
+/// The version of the layout described in the module documentation.
+crate const FORMAT_VERSION: u64 = 1;
+
+/// Writes the documentation of `krate` to `<dst>/<crate name>.json`.
+crate fn run(krate: clean::Crate, renderinfo: RenderInfo, dst: PathBuf) -> io::Result<()> {
+    let json = crate_json(&krate, &renderinfo);
+    fs::create_dir_all(&dst)?;
+    let mut w = BufWriter::new(File::create(dst.join(format!("{}.json", krate.name)))?);
+    write!(w, "{}", json)?;
+    w.flush()
+}
+
+/// The documentation of `krate`, in the layout described in the module
+/// documentation.
+crate fn crate_json(krate: &clean::Crate, renderinfo: &RenderInfo) -> Json {
+    let mut renderer = JsonRenderer {
+        index: BTreeMap::new(),
+        paths: BTreeMap::new(),
+    };
+
+    let root = match krate.module {
+        Some(ref module) => renderer.item(module, &[], Some(&krate.name[..])),
+        None => None,
+    };
+
+    for (did, &(ref path, kind)) in &renderinfo.external_paths {
+        renderer.paths.insert(id(*did), path_json(*did, path, ItemType::from(kind)));
+    }
+
+    let mut external_crates = BTreeMap::new();
+    for &(cnum, ref ext) in &krate.externs {
+        let mut data = BTreeMap::new();
+        data.insert("name".to_owned(), ext.name.to_json());
+        external_crates.insert(cnum.as_u32().to_string(), Json::Object(data));
+    }
+
+    let mut data = BTreeMap::new();
+    data.insert("format_version".to_owned(), FORMAT_VERSION.to_json());
+    data.insert("crate_name".to_owned(), krate.name.to_json());
+    data.insert("crate_version".to_owned(), krate.version.to_json());
+    data.insert("root".to_owned(), root.to_json());
+    data.insert("index".to_owned(), Json::Object(renderer.index));
+    data.insert("paths".to_owned(), Json::Object(renderer.paths));
+    data.insert("external_crates".to_owned(), Json::Object(external_crates));
+    Json::Object(data)
+}
+
+struct JsonRenderer {
+    index: BTreeMap<String, Json>,
+    paths: BTreeMap<String, Json>,
+}
+
+impl JsonRenderer {
+    /// Adds `item` and all the items it contains to the index, and returns its
+    /// id. Items that were stripped by a pass are left out.
+    ///
+    /// `parent_path` is the path of the item containing this one, and is empty
+    /// for the crate root and the items of impls, which have no path of their
+    /// own. `name` overrides the item's name.
+    fn item(
+        &mut self,
+        item: &clean::Item,
+        parent_path: &[String],
+        name: Option<&str>,
+    ) -> Option<String> {
+        if item.is_stripped() {
+            return None;
+        }
+        let item_id = id(item.def_id);
+        let name = name.map(|s| s.to_owned()).or_else(|| item.name.clone());
+
+        let path = match (&item.inner, &name) {
+            (&clean::ImplItem(_), _) | (_, &None) => Vec::new(),
+            (_, &Some(ref name)) => {
+                if parent_path.is_empty() && !item.is_crate() {
+                    Vec::new()
+                } else {
+                    let mut path = parent_path.to_vec();
+                    path.push(name.clone());
+                    path
+                }
+            }
+        };
+        if !path.is_empty() {
+            self.paths.insert(item_id.clone(), path_json(item.def_id, &path, item.type_()));
+        }
+
+        let inner = self.inner(&item.inner, &path);
+
+        let mut data = BTreeMap::new();
+        data.insert("id".to_owned(), item_id.to_json());
+        data.insert("crate_id".to_owned(), item.def_id.krate.as_u32().to_json());
+        data.insert("name".to_owned(), name.to_json());
+        data.insert("kind".to_owned(), item.type_().css_class().to_json());
+        data.insert("source".to_owned(), span(&item.source));
+        data.insert("visibility".to_owned(), visibility(&item.visibility));
+        data.insert("docs".to_owned(), item.collapsed_doc_value().to_json());
+        data.insert("links".to_owned(), links(&item.attrs));
+        data.insert("attrs".to_owned(), item.attrs.other_attrs.iter()
+            .map(|attr| pprust::attribute_to_string(attr))
+            .collect::<Vec<_>>()
+            .to_json());
+        data.insert("stability".to_owned(), item.stability.as_ref().map(stability_json).to_json());
+        data.insert("deprecation".to_owned(),
+                    item.deprecation.as_ref().map(deprecation_json).to_json());
+        data.insert("inner".to_owned(), inner);
+
+        self.index.insert(item_id.clone(), Json::Object(data));
+        Some(item_id)
+    }
+
+    /// Adds `items` to the index, and returns the ids of those that weren't
+    /// stripped.
+    fn items(&mut self, items: &[clean::Item], parent_path: &[String]) -> Json {
+        items.iter()
+             .filter_map(|item| self.item(item, parent_path, None))
+             .collect::<Vec<_>>()
+             .to_json()
+    }
+
+    fn inner(&mut self, inner: &clean::ItemEnum, path: &[String]) -> Json {
+        use clean::ItemEnum::*;
+
+        let mut data = BTreeMap::new();
+        {
+            let mut field = |name: &str, value: Json| {
+                data.insert(name.to_owned(), value);
+            };
+            match *inner {
+                ModuleItem(ref m) => {
+                    field("is_crate", m.is_crate.to_json());
+                    field("items", self.items(&m.items, path));
+                }
+                ExternCrateItem(ref name, ref src) => {
+                    field("name", name.to_json());
+                    field("source", src.to_json());
+                }
+                ImportItem(ref import) => {
+                    let (name, source, glob) = match *import {
+                        clean::Import::Simple(ref name, ref source) => {
+                            (Some(name.clone()), source, false)
+                        }
+                        clean::Import::Glob(ref source) => (None, source, true),
+                    };
+                    field("name", name.to_json());
+                    field("source", path_string(&source.path).to_json());
+                    field("id", source.did.map(id).to_json());
+                    field("glob", glob.to_json());
+                }
+                StructItem(clean::Struct { struct_type, ref generics, ref fields,
+                                           fields_stripped }) |
+                UnionItem(clean::Union { struct_type, ref generics, ref fields,
+                                         fields_stripped }) => {
+                    field("struct_type", struct_type_json(struct_type));
+                    field("generics", generics.to_json());
+                    field("fields", self.items(fields, path));
+                    field("fields_stripped", fields_stripped.to_json());
+                }
+                EnumItem(ref e) => {
+                    field("generics", e.generics.to_json());
+                    field("variants", self.items(&e.variants, path));
+                    field("variants_stripped", e.variants_stripped.to_json());
+                }
+                VariantItem(ref v) => match v.kind {
+                    clean::VariantKind::CLike => field("variant_kind", "plain".to_json()),
+                    clean::VariantKind::Tuple(ref types) => {
+                        field("variant_kind", "tuple".to_json());
+                        field("fields", types.to_json());
+                    }
+                    clean::VariantKind::Struct(ref s) => {
+                        field("variant_kind", "struct".to_json());
+                        field("fields", self.items(&s.fields, path));
+                        field("fields_stripped", s.fields_stripped.to_json());
+                    }
+                },
+                StructFieldItem(ref ty) => field("type", ty.to_json()),
+                FunctionItem(ref f) | ForeignFunctionItem(ref f) => {
+                    field("decl", f.decl.to_json());
+                    field("generics", f.generics.to_json());
+                    field("header", header(&f.header));
+                }
+                MethodItem(ref m) => {
+                    field("decl", m.decl.to_json());
+                    field("generics", m.generics.to_json());
+                    field("header", header(&m.header));
+                    field("has_body", true.to_json());
+                }
+                TyMethodItem(ref m) => {
+                    field("decl", m.decl.to_json());
+                    field("generics", m.generics.to_json());
+                    field("header", header(&m.header));
+                    field("has_body", false.to_json());
+                }
+                TypedefItem(ref t, _) => {
+                    field("type", t.type_.to_json());
+                    field("generics", t.generics.to_json());
+                }
+                ExistentialItem(ref e, _) => {
+                    field("bounds", e.bounds.to_json());
+                    field("generics", e.generics.to_json());
+                }
+                StaticItem(ref s) | ForeignStaticItem(ref s) => {
+                    field("type", s.type_.to_json());
+                    field("mutable", (s.mutability == clean::Mutable).to_json());
+                    field("expr", s.expr.to_json());
+                }
+                ConstantItem(ref c) => {
+                    field("type", c.type_.to_json());
+                    field("expr", c.expr.to_json());
+                }
+                TraitItem(ref t) => {
+                    field("is_auto", t.is_auto.to_json());
+                    field("is_unsafe", (t.unsafety == hir::Unsafety::Unsafe).to_json());
+                    field("generics", t.generics.to_json());
+                    field("bounds", t.bounds.to_json());
+                    field("items", self.items(&t.items, path));
+                }
+                ImplItem(ref i) => {
+                    let mut provided = i.provided_trait_methods.iter()
+                                                               .cloned()
+                                                               .collect::<Vec<_>>();
+                    provided.sort();
+                    field("is_unsafe", (i.unsafety == hir::Unsafety::Unsafe).to_json());
+                    field("generics", i.generics.to_json());
+                    field("provided_trait_methods", provided.to_json());
+                    field("trait", i.trait_.to_json());
+                    field("for", i.for_.to_json());
+                    field("items", self.items(&i.items, &[]));
+                    field("negative",
+                          (i.polarity == Some(clean::ImplPolarity::Negative)).to_json());
+                    field("synthetic", i.synthetic.to_json());
+                    field("blanket_impl", i.blanket_impl.to_json());
+                }
+                ForeignTypeItem => {}
+                MacroItem(ref m) => {
+                    field("source", m.source.to_json());
+                    field("imported_from", m.imported_from.to_json());
+                }
+                PrimitiveItem(prim) => field("name", prim.as_str().to_json()),
+                AssociatedConstItem(ref ty, ref default) => {
+                    field("type", ty.to_json());
+                    field("default", default.to_json());
+                }
+                AssociatedTypeItem(ref bounds, ref default) => {
+                    field("bounds", bounds.to_json());
+                    field("default", default.to_json());
+                }
+                KeywordItem(ref kw) => field("name", kw.to_json()),
+                StrippedItem(..) => unreachable!(),
+            }
+        }
+        Json::Object(data)
+    }
+}
+
+/// The id of an item, unique within the documented crate.
+fn id(did: DefId) -> String {
+    format!("{}:{}", did.krate.as_u32(), did.index.as_raw_u32())
+}
+
+fn path_json(did: DefId, path: &[String], kind: ItemType) -> Json {
+    let mut data = BTreeMap::new();
+    data.insert("crate_id".to_owned(), did.krate.as_u32().to_json());
+    data.insert("path".to_owned(), path.to_json());
+    data.insert("kind".to_owned(), kind.css_class().to_json());
+    Json::Object(data)
+}
+
+fn path_string(path: &clean::Path) -> String {
+    let segments = path.segments.iter().map(|s| &s.name[..]).collect::<Vec<_>>();
+    format!("{}{}", if path.global { "::" } else { "" }, segments.join("::"))
+}
+
+fn span(span: &clean::Span) -> Json {
+    if span.loline == 0 {
+        return Json::Null;
+    }
+    let mut data = BTreeMap::new();
+    data.insert("filename".to_owned(), span.filename.to_string().to_json());
+    data.insert("begin".to_owned(), vec![span.loline, span.locol].to_json());
+    data.insert("end".to_owned(), vec![span.hiline, span.hicol].to_json());
+    Json::Object(data)
+}
+
+fn visibility(vis: &Option<clean::Visibility>) -> Json {
+    match *vis {
+        Some(clean::Public) => "public".to_json(),
+        Some(clean::Inherited) | None => "default".to_json(),
+        Some(clean::Visibility::Crate) => "crate".to_json(),
+        Some(clean::Visibility::Restricted(did, ref path)) => {
+            let mut restricted = BTreeMap::new();
+            restricted.insert("parent".to_owned(), id(did).to_json());
+            restricted.insert("path".to_owned(), path_string(path).to_json());
+            let mut data = BTreeMap::new();
+            data.insert("restricted".to_owned(), Json::Object(restricted));
+            Json::Object(data)
+        }
+    }
+}
+
+/// The intra-doc links of an item: the text of each link, along with the id
+/// of the item it resolved to and the fragment within that item's page. Links
+/// to primitive types only have a fragment.
+fn links(attrs: &clean::Attributes) -> Json {
+    attrs.links.iter().map(|&(ref text, did, ref fragment)| {
+        let mut data = BTreeMap::new();
+        data.insert("text".to_owned(), text.to_json());
+        data.insert("id".to_owned(), did.map(id).to_json());
+        data.insert("fragment".to_owned(), fragment.to_json());
+        Json::Object(data)
+    }).collect::<Vec<_>>().to_json()
+}
+
+/// `Some` of the string, unless it's empty.
+fn non_empty(s: &str) -> Json {
+    if s.is_empty() { Json::Null } else { s.to_json() }
+}
+
+fn stability_json(stab: &clean::Stability) -> Json {
+    let mut data = BTreeMap::new();
+    data.insert("level".to_owned(), match stab.level {
+        stability::Stable => "stable",
+        stability::Unstable => "unstable",
+    }.to_json());
+    data.insert("feature".to_owned(), non_empty(&stab.feature));
+    data.insert("since".to_owned(), non_empty(&stab.since));
+    data.insert("deprecated_since".to_owned(), non_empty(&stab.deprecated_since));
+    data.insert("deprecated_reason".to_owned(), non_empty(&stab.deprecated_reason));
+    data.insert("unstable_reason".to_owned(), non_empty(&stab.unstable_reason));
+    data.insert("issue".to_owned(), stab.issue.to_json());
+    Json::Object(data)
+}
+
+fn deprecation_json(depr: &clean::Deprecation) -> Json {
+    let mut data = BTreeMap::new();
+    data.insert("since".to_owned(), non_empty(&depr.since));
+    data.insert("note".to_owned(), non_empty(&depr.note));
+    Json::Object(data)
+}
+
+fn struct_type_json(struct_type: ::doctree::StructType) -> Json {
+    match struct_type {
+        ::doctree::Plain => "plain",
+        ::doctree::Tuple => "tuple",
+        ::doctree::Unit => "unit",
+    }.to_json()
+}
+
+fn header(header: &hir::FnHeader) -> Json {
+    let mut data = BTreeMap::new();
+    data.insert("const".to_owned(), (header.constness == hir::Constness::Const).to_json());
+    data.insert("unsafe".to_owned(), (header.unsafety == hir::Unsafety::Unsafe).to_json());
+    data.insert("async".to_owned(), (header.asyncness == hir::IsAsync::Async).to_json());
+    data.insert("abi".to_owned(), header.abi.name().to_json());
+    Json::Object(data)
+}
+
+/// Types are objects with a `kind`, and the details of the type of that kind
+/// in `inner`.
+fn tagged(kind: &str, inner: Json) -> Json {
+    let mut data = BTreeMap::new();
+    data.insert("kind".to_owned(), kind.to_json());
+    data.insert("inner".to_owned(), inner);
+    Json::Object(data)
+}
+
+impl ToJson for clean::Type {
+    fn to_json(&self) -> Json {
+        use clean::Type::*;
+
+        match *self {
+            ResolvedPath { ref path, ref typarams, did, is_generic } => {
+                let mut data = BTreeMap::new();
+                data.insert("name".to_owned(), path_string(path).to_json());
+                data.insert("id".to_owned(), id(did).to_json());
+                data.insert("args".to_owned(),
+                            path.segments.last().map(|s| s.args.to_json()).to_json());
+                data.insert("bounds".to_owned(), typarams.to_json());
+                data.insert("is_generic".to_owned(), is_generic.to_json());
+                tagged("resolved_path", Json::Object(data))
+            }
+            Generic(ref name) => tagged("generic", name.to_json()),
+            Primitive(prim) => tagged("primitive", prim.as_str().to_json()),
+            BareFunction(ref f) => {
+                let mut data = BTreeMap::new();
+                data.insert("decl".to_owned(), f.decl.to_json());
+                data.insert("generic_params".to_owned(), f.generic_params.to_json());
+                data.insert("unsafe".to_owned(),
+                            (f.unsafety == hir::Unsafety::Unsafe).to_json());
+                data.insert("abi".to_owned(), f.abi.name().to_json());
+                tagged("function_pointer", Json::Object(data))
+            }
+            Tuple(ref types) => tagged("tuple", types.to_json()),
+            Slice(ref ty) => tagged("slice", ty.to_json()),
+            Array(ref ty, ref len) => {
+                let mut data = BTreeMap::new();
+                data.insert("type".to_owned(), ty.to_json());
+                data.insert("len".to_owned(), len.to_json());
+                tagged("array", Json::Object(data))
+            }
+            Never => tagged("never", Json::Null),
+            Unique(ref ty) => tagged("unique", ty.to_json()),
+            RawPointer(mutability, ref ty) => {
+                let mut data = BTreeMap::new();
+                data.insert("mutable".to_owned(), (mutability == clean::Mutable).to_json());
+                data.insert("type".to_owned(), ty.to_json());
+                tagged("raw_pointer", Json::Object(data))
+            }
+            BorrowedRef { ref lifetime, mutability, ref type_ } => {
+                let mut data = BTreeMap::new();
+                data.insert("lifetime".to_owned(),
+                            lifetime.as_ref().map(|l| l.get_ref().to_owned()).to_json());
+                data.insert("mutable".to_owned(), (mutability == clean::Mutable).to_json());
+                data.insert("type".to_owned(), type_.to_json());
+                tagged("borrowed_ref", Json::Object(data))
+            }
+            QPath { ref name, ref self_type, ref trait_ } => {
+                let mut data = BTreeMap::new();
+                data.insert("name".to_owned(), name.to_json());
+                data.insert("self_type".to_owned(), self_type.to_json());
+                data.insert("trait".to_owned(), trait_.to_json());
+                tagged("qualified_path", Json::Object(data))
+            }
+            Infer => tagged("infer", Json::Null),
+            ImplTrait(ref bounds) => tagged("impl_trait", bounds.to_json()),
+        }
+    }
+}
+
+impl ToJson for clean::GenericArgs {
+    fn to_json(&self) -> Json {
+        let mut data = BTreeMap::new();
+        match *self {
+            clean::GenericArgs::AngleBracketed { ref lifetimes, ref types, ref bindings } => {
+                if lifetimes.is_empty() && types.is_empty() && bindings.is_empty() {
+                    return Json::Null;
+                }
+                let lifetimes = lifetimes.iter()
+                                         .map(|l| l.get_ref().to_owned())
+                                         .collect::<Vec<_>>();
+                data.insert("lifetimes".to_owned(), lifetimes.to_json());
+                data.insert("types".to_owned(), types.to_json());
+                data.insert("bindings".to_owned(), bindings.iter().map(|b| {
+                    let mut binding = BTreeMap::new();
+                    binding.insert("name".to_owned(), b.name.to_json());
+                    binding.insert("type".to_owned(), b.ty.to_json());
+                    Json::Object(binding)
+                }).collect::<Vec<_>>().to_json());
+                tagged("angle_bracketed", Json::Object(data))
+            }
+            clean::GenericArgs::Parenthesized { ref inputs, ref output } => {
+                data.insert("inputs".to_owned(), inputs.to_json());
+                data.insert("output".to_owned(), output.to_json());
+                tagged("parenthesized", Json::Object(data))
+            }
+        }
+    }
+}
+
+impl ToJson for clean::GenericBound {
+    fn to_json(&self) -> Json {
+        match *self {
+            clean::GenericBound::TraitBound(ref poly, modifier) => {
+                let mut data = BTreeMap::new();
+                data.insert("trait".to_owned(), poly.trait_.to_json());
+                data.insert("generic_params".to_owned(), poly.generic_params.to_json());
+                data.insert("maybe".to_owned(),
+                            (modifier == hir::TraitBoundModifier::Maybe).to_json());
+                tagged("trait_bound", Json::Object(data))
+            }
+            clean::GenericBound::Outlives(ref lifetime) => {
+                tagged("outlives", lifetime.get_ref().to_json())
+            }
+        }
+    }
+}
+
+impl ToJson for clean::GenericParamDef {
+    fn to_json(&self) -> Json {
+        let mut data = BTreeMap::new();
+        data.insert("name".to_owned(), self.name.to_json());
+        match self.kind {
+            clean::GenericParamDefKind::Lifetime => {
+                data.insert("kind".to_owned(), "lifetime".to_json());
+            }
+            clean::GenericParamDefKind::Type { ref bounds, ref default, synthetic, .. } => {
+                data.insert("kind".to_owned(), "type".to_json());
+                data.insert("bounds".to_owned(), bounds.to_json());
+                data.insert("default".to_owned(), default.to_json());
+                data.insert("synthetic".to_owned(), synthetic.is_some().to_json());
+            }
+        }
+        Json::Object(data)
+    }
+}
+
+impl ToJson for clean::Generics {
+    fn to_json(&self) -> Json {
+        let mut data = BTreeMap::new();
+        data.insert("params".to_owned(), self.params.to_json());
+        data.insert("where_predicates".to_owned(), self.where_predicates.to_json());
+        Json::Object(data)
+    }
+}
+
+impl ToJson for clean::WherePredicate {
+    fn to_json(&self) -> Json {
+        let mut data = BTreeMap::new();
+        match *self {
+            clean::WherePredicate::BoundPredicate { ref ty, ref bounds } => {
+                data.insert("type".to_owned(), ty.to_json());
+                data.insert("bounds".to_owned(), bounds.to_json());
+                tagged("bound_predicate", Json::Object(data))
+            }
+            clean::WherePredicate::RegionPredicate { ref lifetime, ref bounds } => {
+                data.insert("lifetime".to_owned(), lifetime.get_ref().to_json());
+                data.insert("bounds".to_owned(), bounds.to_json());
+                tagged("region_predicate", Json::Object(data))
+            }
+            clean::WherePredicate::EqPredicate { ref lhs, ref rhs } => {
+                data.insert("lhs".to_owned(), lhs.to_json());
+                data.insert("rhs".to_owned(), rhs.to_json());
+                tagged("eq_predicate", Json::Object(data))
+            }
+        }
+    }
+}
+
+impl ToJson for clean::FnDecl {
+    fn to_json(&self) -> Json {
+        let inputs = self.inputs.values.iter().map(|arg| {
+            vec![arg.name.to_json(), arg.type_.to_json()].to_json()
+        }).collect::<Vec<_>>();
+        let mut data = BTreeMap::new();
+        data.insert("inputs".to_owned(), inputs.to_json());
+        data.insert("output".to_owned(), match self.output {
+            clean::Return(ref ty) => ty.to_json(),
+            clean::DefaultReturn => Json::Null,
+        });
+        data.insert("variadic".to_owned(), self.variadic.to_json());
+        Json::Object(data)
+    }
+}
@@ -63,6 +63,7 @@ use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::mpsc::channel;
 
+use serialize::json::ToJson;
 use syntax::edition::Edition;
 use externalfiles::ExternalHtml;
 use rustc::session::{early_warn, early_error};
@@ -75,15 +76,19 @@ use rustc::session::config::get_cmd_lint_options;
 #[macro_use]
 mod externalfiles;
 
+mod apidiff;
 mod clean;
 mod core;
 mod doctree;
 mod fold;
+mod json;
+mod linkcheck;
 pub mod html {
     crate mod highlight;
     crate mod escape;
     crate mod item_type;
     crate mod format;
+    crate mod fulltext;
     crate mod layout;
     pub mod markdown;
     crate mod render;
@@ -151,7 +156,7 @@ fn opts() -> Vec<RustcOptGroup> {
                      "[rust]")
         }),
         stable("w", |o| {
-            o.optopt("w", "output-format", "the output type to write", "[html]")
+            o.optopt("w", "output-format", "the output type to write", "[html|json]")
         }),
         stable("o", |o| o.optopt("o", "output", "where to place the output", "PATH")),
         stable("crate-name", |o| {
@@ -310,6 +315,51 @@ fn opts() -> Vec<RustcOptGroup> {
                        "disable-minification",
                        "Disable minification applied on JS files")
         }),
+        unstable("show-coverage", |o| {
+            o.optflag("",
+                      "show-coverage",
+                      "report how many items are documented, instead of writing \
+                       documentation; the report is JSON with `--output-format json`")
+        }),
+        unstable("coverage-threshold", |o| {
+            o.optopt("",
+                     "coverage-threshold",
+                     "with --show-coverage, fail if less than this percentage of the \
+                      items are documented",
+                     "PERCENT")
+        }),
+        unstable("api-diff", |o| {
+            o.optopt("",
+                     "api-diff",
+                     "compare the public API with an earlier version documented with \
+                      `--output-format json`, instead of writing documentation",
+                     "PATH")
+        }),
+        unstable("check-links", |o| {
+            o.optflag("",
+                      "check-links",
+                      "report the intra-doc links which won't work, instead of writing \
+                       documentation; the report is JSON with `--output-format json`")
+        }),
+        unstable("batch-doctests", |o| {
+            o.optflag("",
+                      "batch-doctests",
+                      "with --test, compile the code examples which allow it together, \
+                       rather than each on its own")
+        }),
+        unstable("doctest-cache", |o| {
+            o.optopt("",
+                     "doctest-cache",
+                     "with --test, record the code examples which pass in this directory, and \
+                      skip them while they, the crate and the options stay the same",
+                     "PATH")
+        }),
+        unstable("full-text-search", |o| {
+            o.optflag("",
+                      "full-text-search",
+                      "add the text of the documentation to the search index, in files \
+                       which are loaded as they are needed")
+        }),
         stable("warn", |o| {
             o.optmulti("W", "warn", "Set lint warnings", "OPT")
         }),
@@ -378,6 +428,14 @@ fn main_args(args: &[String]) -> isize {
         for &name in passes::DEFAULT_PRIVATE_PASSES {
             println!("{:>20}", name);
         }
+        println!("\nPasses run with `--show-coverage`:");
+        for &name in passes::DEFAULT_COVERAGE_PASSES {
+            println!("{:>20}", name);
+        }
+        println!("\nPasses run with `--show-coverage --document-private-items`:");
+        for &name in passes::DEFAULT_PRIVATE_COVERAGE_PASSES {
+            println!("{:>20}", name);
+        }
         return 0;
     }
 
@@ -519,6 +577,27 @@ fn main_args(args: &[String]) -> isize {
     let sort_modules_alphabetically = !matches.opt_present("sort-modules-by-appearance");
     let resource_suffix = matches.opt_str("resource-suffix");
     let enable_minification = !matches.opt_present("disable-minification");
+    let full_text_search = matches.opt_present("full-text-search");
+    let batch_doctests = matches.opt_present("batch-doctests");
+    let doctest_cache = matches.opt_str("doctest-cache").map(PathBuf::from);
+    let api_diff = matches.opt_str("api-diff").map(PathBuf::from);
+    let show_coverage = matches.opt_present("show-coverage");
+    let check_links = matches.opt_present("check-links");
+    let coverage_threshold = match matches.opt_str("coverage-threshold") {
+        Some(s) => match s.parse::<f64>() {
+            Ok(threshold) if threshold >= 0.0 && threshold <= 100.0 => Some(threshold),
+            _ => {
+                diag.struct_err("argument for --coverage-threshold must be a percentage \
+                                 between 0 and 100").emit();
+                return 1;
+            }
+        },
+        None => None,
+    };
+    if coverage_threshold.is_some() && !show_coverage {
+        diag.struct_err("--coverage-threshold can only be used with --show-coverage").emit();
+        return 1;
+    }
 
     let edition = matches.opt_str("edition").unwrap_or("2015".to_string());
     let edition = match edition.parse() {
@@ -534,11 +613,12 @@ fn main_args(args: &[String]) -> isize {
     match (should_test, markdown_input) {
         (true, true) => {
             return markdown::test(input, cfgs, libs, externs, test_args, maybe_sysroot,
-                                  display_warnings, linker, edition, cg, &diag)
+                                  display_warnings, linker, edition, cg, batch_doctests, &diag)
         }
         (true, false) => {
             return test::run(Path::new(input), cfgs, libs, externs, test_args, crate_name,
-                             maybe_sysroot, display_warnings, linker, edition, cg)
+                             maybe_sysroot, display_warnings, linker, edition, cg,
+                             batch_doctests, doctest_cache)
         }
         (false, true) => return markdown::render(Path::new(input),
                                                  output.unwrap_or(PathBuf::from("doc")),
@@ -548,11 +628,51 @@ fn main_args(args: &[String]) -> isize {
     }
 
     let output_format = matches.opt_str("w");
+    if output_format.as_ref().map(|s| &**s) == Some("json") &&
+        !nightly_options::is_unstable_enabled(&matches) {
+        diag.struct_err("the `json` output format is unstable")
+            .note("pass `-Z unstable-options` to enable it")
+            .emit();
+        return 1;
+    }
 
     let res = acquire_input(PathBuf::from(input), externs, edition, cg, &matches, error_format,
                             move |out| {
         let Output { krate, passes, renderinfo } = out;
         let diag = core::new_handler(error_format, None);
+        if let Some(old) = api_diff {
+            return apidiff::run(&old, &krate, &renderinfo, &diag);
+        }
+        if check_links {
+            return linkcheck::run(krate, &renderinfo, &output.unwrap_or(PathBuf::from("doc")),
+                                  output_format.as_ref().map(|s| &**s) == Some("json"), &diag);
+        }
+        if show_coverage {
+            let coverage = match passes::take_coverage() {
+                Some(coverage) => coverage,
+                None => {
+                    diag.struct_err("--show-coverage needs the `calculate-doc-coverage` pass")
+                        .help("add it with `--passes calculate-doc-coverage`")
+                        .emit();
+                    return 1;
+                }
+            };
+            if output_format.as_ref().map(|s| &**s) == Some("json") {
+                println!("{}", coverage.to_json().pretty());
+            } else {
+                print!("{}", coverage.table());
+            }
+            let percentage = coverage.total().percentage();
+            return match coverage_threshold {
+                Some(threshold) if percentage < threshold => {
+                    diag.struct_err(&format!("{:.1}% of the items are documented, less than \
+                                              the threshold of {}%", percentage, threshold))
+                        .emit();
+                    1
+                }
+                _ => 0,
+            };
+        }
         info!("going to format");
         match output_format.as_ref().map(|s| &**s) {
             Some("html") | None => {
@@ -564,10 +684,22 @@ fn main_args(args: &[String]) -> isize {
                                   renderinfo,
                                   sort_modules_alphabetically,
                                   themes,
-                                  enable_minification, id_map)
+                                  enable_minification,
+                                  full_text_search,
+                                  id_map)
                     .expect("failed to generate documentation");
                 0
             }
+            Some("json") => {
+                match json::run(krate, renderinfo, output.unwrap_or(PathBuf::from("doc"))) {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        diag.struct_err(&format!("failed to write JSON documentation: {}", e))
+                            .emit();
+                        1
+                    }
+                }
+            }
             Some(s) => {
                 diag.struct_err(&format!("unknown output format: {}", s)).emit();
                 1
@@ -630,10 +762,17 @@ fn rust_input<R, F>(cratefile: PathBuf,
 where R: 'static + Send,
       F: 'static + Send + FnOnce(Output) -> R
 {
+    let show_coverage = matches.opt_present("show-coverage");
     let mut default_passes = if matches.opt_present("no-defaults") {
         passes::DefaultPassOption::None
     } else if matches.opt_present("document-private-items") {
-        passes::DefaultPassOption::Private
+        if show_coverage {
+            passes::DefaultPassOption::PrivateCoverage
+        } else {
+            passes::DefaultPassOption::Private
+        }
+    } else if show_coverage {
+        passes::DefaultPassOption::Coverage
     } else {
         passes::DefaultPassOption::Default
     };
@@ -667,6 +806,7 @@ where R: 'static + Send,
     });
 
     let (lint_opts, describe_lints, lint_cap) = get_cmd_lint_options(matches, error_format);
+    let check_links = matches.opt_present("check-links");
 
     let (tx, rx) = channel();
 
@@ -677,7 +817,7 @@ where R: 'static + Send,
             core::run_core(paths, cfgs, externs, Input::File(cratefile), triple, maybe_sysroot,
                            display_warnings, crate_name.clone(),
                            force_unstable_if_unmarked, edition, cg, error_format,
-                           lint_opts, lint_cap, describe_lints);
+                           lint_opts, lint_cap, describe_lints, check_links);
 
         info!("finished with rustc");
 
@@ -731,6 +871,8 @@ where R: 'static + Send,
             if attr.is_word() && name == Some("document_private_items") {
                 if default_passes == passes::DefaultPassOption::Default {
                     default_passes = passes::DefaultPassOption::Private;
+                } else if default_passes == passes::DefaultPassOption::Coverage {
+                    default_passes = passes::DefaultPassOption::PrivateCoverage;
                 }
             }
         }
@@ -777,7 +919,6 @@ where R: 'static + Send,
 fn check_deprecated_options(matches: &getopts::Matches, diag: &errors::Handler) {
     let deprecated_flags = [
        "input-format",
-       "output-format",
        "no-defaults",
        "passes",
     ];
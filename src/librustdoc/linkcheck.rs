@@ -0,0 +1,332 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checking the intra-doc links of a crate, with `--check-links`.
+//!
+//! Links are resolved while the crate is cleaned. When they are checked, the
+//! links which can't be resolved, and the ones whose `#anchor` isn't on the
+//! page of the item they point to, are recorded in `RenderInfo::broken_links`
+//! rather than reported as warnings. Once the passes have run, the links to
+//! items which won't have a page are added: items of the crate which are
+//! private or hidden, and items of other crates which aren't documented, or
+//! whose documentation can't be found. Everything is reported at once.
+
+use std::collections::BTreeMap;
+use std::mem;
+use std::path::Path;
+
+use errors;
+use rustc::hir::def::{CtorKind, Def};
+use rustc::hir::def_id::{CrateNum, DefId};
+use rustc::ty;
+use rustc::util::nodemap::{FxHashMap, FxHashSet};
+use serialize::json::{Json, ToJson};
+use syntax_pos::Span;
+
+use clean::{self, Item};
+use core::{DocAccessLevels, DocContext};
+use fold::DocFolder;
+use html::item_type::ItemType;
+use html::markdown::{heading_ids, IdMap};
+use html::render::{self, ExternalLocation, RenderInfo};
+
+/// A link which won't work in the documentation.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct BrokenLink {
+    /// The file and line of the link, or of the item whose documentation has
+    /// it.
+    pub location: String,
+    pub link: String,
+    /// Why the link won't work.
+    pub reason: String,
+}
+
+impl ToJson for BrokenLink {
+    fn to_json(&self) -> Json {
+        let mut data = BTreeMap::new();
+        data.insert("location".to_owned(), self.location.to_json());
+        data.insert("link".to_owned(), self.link.to_json());
+        data.insert("reason".to_owned(), self.reason.to_json());
+        Json::Object(data)
+    }
+}
+
+/// The kinds of items listed on module pages, each in its own section.
+const MODULE_ITEMS: &[ItemType] = &[
+    ItemType::Import,
+    ItemType::Module,
+    ItemType::Struct,
+    ItemType::Union,
+    ItemType::Enum,
+    ItemType::Function,
+    ItemType::Typedef,
+    ItemType::Static,
+    ItemType::Constant,
+    ItemType::Trait,
+    ItemType::Macro,
+    ItemType::Primitive,
+    ItemType::ForeignType,
+    ItemType::Keyword,
+    ItemType::Existential,
+];
+
+/// The sections of trait pages which the implementors are loaded into, even
+/// when there are none.
+const IMPLEMENTORS: &[&str] = &[
+    "implementors",
+    "implementors-list",
+    "synthetic-implementors",
+    "synthetic-implementors-list",
+];
+
+/// The anchors on the page of `def`, whose id is `did`: the sections which
+/// are rendered for it, its members and the headings of its documentation.
+///
+/// They are found through the type context, so that links to the items of
+/// other crates are checked against their metadata.
+crate fn anchors(cx: &DocContext, def: Def, did: DefId) -> FxHashSet<String> {
+    let mut anchors = FxHashSet();
+    let mut members = vec![];
+    match def {
+        Def::Mod(_) => {
+            anchors.extend(MODULE_ITEMS.iter().map(|ty| {
+                render::item_ty_to_strs(ty).0.to_owned()
+            }));
+        }
+        Def::Struct(_) | Def::Union(_) | Def::Enum(_) => {
+            let adt = cx.tcx.adt_def(did);
+            if adt.is_enum() {
+                if !adt.variants.is_empty() {
+                    anchors.insert("variants".to_owned());
+                }
+                for variant in &adt.variants {
+                    members.push((ItemType::Variant, variant.name.to_string()));
+                    for field in &variant.fields {
+                        anchors.insert(format!("variant.{}.field.{}", variant.name, field.ident));
+                        anchors.insert(format!("{}.{}.{}.{}",
+                                               variant.name,
+                                               ItemType::Variant.name_space(),
+                                               field.ident,
+                                               ItemType::StructField.name_space()));
+                    }
+                }
+            } else {
+                // Only the public fields of structs with named fields, and of
+                // unions, get a section.
+                let variant = adt.non_enum_variant();
+                let fields = variant.fields.iter()
+                                           .filter(|field| field.vis == ty::Visibility::Public)
+                                           .collect::<Vec<_>>();
+                let named = adt.is_union() || variant.ctor_kind == CtorKind::Fictive;
+                if named && !fields.is_empty() {
+                    anchors.insert("fields".to_owned());
+                    members.extend(fields.iter().map(|field| {
+                        (ItemType::StructField, field.ident.to_string())
+                    }));
+                }
+            }
+
+            // The items of both inherent and trait impls are on the page.
+            let mut impls = cx.tcx.inherent_impls(did).to_vec();
+            if !impls.is_empty() {
+                anchors.insert("methods".to_owned());
+            }
+            let ty = cx.tcx.type_of(did);
+            let deref_trait = cx.tcx.lang_items().deref_trait();
+            for &trait_did in &cx.all_traits {
+                cx.tcx.for_each_relevant_impl(trait_did, ty, |imp| {
+                    let section = match cx.tcx.type_of(imp).sty {
+                        ty::TyParam(_) => "blanket-implementations",
+                        _ => "implementations",
+                    };
+                    anchors.insert(section.to_owned());
+                    anchors.insert(format!("{}-list", section));
+                    if Some(trait_did) == deref_trait {
+                        anchors.insert("deref-methods".to_owned());
+                    }
+                    impls.push(imp);
+                });
+            }
+            // The auto traits are implemented for every type.
+            anchors.insert("synthetic-implementations".to_owned());
+            anchors.insert("synthetic-implementations-list".to_owned());
+            for imp in impls {
+                members.extend(cx.tcx.associated_items(imp).map(|item| {
+                    (assoc_item_type(&item, false), item.ident.to_string())
+                }));
+            }
+        }
+        Def::Trait(_) => {
+            for item in cx.tcx.associated_items(did) {
+                let ty = assoc_item_type(&item, true);
+                let section = match ty {
+                    ItemType::AssociatedType => "associated-types",
+                    ItemType::AssociatedConst => "associated-const",
+                    ItemType::TyMethod => "required-methods",
+                    _ => "provided-methods",
+                };
+                anchors.insert(section.to_owned());
+                members.push((ty, item.ident.to_string()));
+            }
+            anchors.extend(IMPLEMENTORS.iter().map(|&id| id.to_owned()));
+            cx.tcx.for_each_impl(did, |imp| {
+                let foreign = cx.tcx.type_of(imp).ty_adt_def().map_or(true, |adt| {
+                    !adt.did.is_local()
+                });
+                if foreign {
+                    anchors.insert("foreign-impls".to_owned());
+                }
+            });
+        }
+        _ => {}
+    }
+    for (ty, name) in members {
+        anchors.insert(format!("{}.{}", ty, name));
+        anchors.insert(format!("{}.{}", name, ty.name_space()));
+    }
+
+    let attrs = clean::Attributes::from_ast(cx.sess().diagnostic(), &cx.tcx.get_attrs(did));
+    if let Some(doc) = attrs.collapsed_doc_value() {
+        let mut ids = IdMap::new();
+        ids.populate(render::initial_ids());
+        anchors.extend(heading_ids(&doc, &mut ids));
+    }
+    anchors
+}
+
+/// The kind of an associated item, as on the page of its trait or type.
+fn assoc_item_type(item: &ty::AssociatedItem, in_trait: bool) -> ItemType {
+    match item.kind {
+        ty::AssociatedKind::Const => ItemType::AssociatedConst,
+        ty::AssociatedKind::Type | ty::AssociatedKind::Existential => ItemType::AssociatedType,
+        ty::AssociatedKind::Method if in_trait && !item.defaultness.has_value() => {
+            ItemType::TyMethod
+        }
+        ty::AssociatedKind::Method => ItemType::Method,
+    }
+}
+
+/// Prints the broken intra-doc links of `krate`, as JSON if `json` is set.
+/// `dst` is the directory the documentation would be written to, where the
+/// documentation of other crates may be.
+///
+/// Returns the exit code: 1 if any link is broken, 0 otherwise.
+crate fn run(
+    krate: clean::Crate,
+    renderinfo: &RenderInfo,
+    dst: &Path,
+    json: bool,
+    diag: &errors::Handler,
+) -> isize {
+    let mut links = renderinfo.broken_links.clone();
+    links.extend(unreachable_links(krate, &renderinfo.link_locations, dst));
+    // The attributes of some items are cleaned more than once.
+    links.sort();
+    links.dedup();
+
+    if json {
+        println!("{}", links.to_json().pretty());
+    } else {
+        for link in &links {
+            println!("{}: `[{}]` {}", link.location, link.link, link.reason);
+        }
+    }
+    if links.is_empty() {
+        0
+    } else {
+        diag.struct_err(&format!("found {} broken intra-doc link{}",
+                                 links.len(),
+                                 if links.len() == 1 { "" } else { "s" }))
+            .emit();
+        1
+    }
+}
+
+/// The links of the documented items of `krate` to items which won't have a
+/// page of their own. `locations` has the file and line of each link.
+fn unreachable_links(
+    mut krate: clean::Crate,
+    locations: &FxHashMap<(Span, String), String>,
+    dst: &Path,
+) -> Vec<BrokenLink> {
+    let mut collector = LinkCollector {
+        locations,
+        links: vec![],
+        documented: FxHashSet(),
+        stripped: false,
+    };
+    // Not `fold_crate`, which would also walk the items of external traits.
+    krate.module = krate.module.take().and_then(|module| collector.fold_item(module));
+
+    let names = krate.externs.iter()
+                             .map(|&(cnum, ref e)| (cnum, e.name.clone()))
+                             .collect::<FxHashMap<CrateNum, String>>();
+    let unknown = krate.externs.iter().filter_map(|&(cnum, ref e)| {
+        match render::extern_location(e, dst) {
+            ExternalLocation::Unknown => Some(cnum),
+            _ => None,
+        }
+    }).collect::<FxHashSet<_>>();
+
+    let documented = collector.documented;
+    collector.links.into_iter().filter_map(|(location, link, did)| {
+        let reason = if did.is_local() {
+            if documented.contains(&did) {
+                return None;
+            }
+            "points to an item which isn't documented, because it is private or hidden"
+                .to_owned()
+        } else if !krate.access_levels.is_doc_reachable(did) {
+            format!("points to an item of `{}` which isn't documented", names[&did.krate])
+        } else if unknown.contains(&did.krate) {
+            format!("points into `{}`, whose documentation isn't in the output directory, \
+                     and which has no `#![doc(html_root_url)]`", names[&did.krate])
+        } else {
+            return None;
+        };
+        Some(BrokenLink { location, link, reason })
+    }).collect()
+}
+
+struct LinkCollector<'a> {
+    locations: &'a FxHashMap<(Span, String), String>,
+    /// The links of the documented items to other items, with where they are.
+    links: Vec<(String, String, DefId)>,
+    /// The ids of the documented items.
+    documented: FxHashSet<DefId>,
+    /// Whether the items being walked are in a stripped item, and aren't
+    /// part of the documentation.
+    stripped: bool,
+}
+
+impl<'a> DocFolder for LinkCollector<'a> {
+    fn fold_item(&mut self, i: Item) -> Option<Item> {
+        if let clean::StrippedItem(..) = i.inner {
+            let stripped = mem::replace(&mut self.stripped, true);
+            let ret = self.fold_item_recur(i);
+            self.stripped = stripped;
+            return ret;
+        }
+        if !self.stripped {
+            self.documented.insert(i.def_id);
+            let span = clean::span_of_attrs(&i.attrs);
+            for &(ref link, did, _) in &i.attrs.links {
+                if let Some(did) = did {
+                    // Like the links reported while cleaning, these are
+                    // reported on the line of the documentation they are on.
+                    let location = self.locations.get(&(span, link.clone())).cloned()
+                        .unwrap_or_else(|| format!("{}:{}", i.source.filename, i.source.loline));
+                    self.links.push((location, link.clone(), did));
+                }
+            }
+        }
+        self.fold_item_recur(i)
+    }
+}
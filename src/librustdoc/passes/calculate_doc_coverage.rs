@@ -0,0 +1,309 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::mem;
+use std::ops;
+
+use serialize::json::{Json, ToJson};
+
+use clean::{self, Item};
+use fold::{self, DocFolder};
+use html::item_type::ItemType;
+use html::markdown;
+
+thread_local!(static COVERAGE: RefCell<Option<Coverage>> = RefCell::new(None));
+
+/// Counts the documented items of the crate, for `--show-coverage`, which
+/// picks the counts up with `take_coverage` once all passes have run.
+pub fn calculate_doc_coverage(krate: clean::Crate) -> clean::Crate {
+    let (krate, coverage) = doc_coverage(krate);
+    COVERAGE.with(|c| *c.borrow_mut() = Some(coverage));
+    krate
+}
+
+/// The counts of the last run of `calculate-doc-coverage` on this thread, if
+/// it ran.
+pub fn take_coverage() -> Option<Coverage> {
+    COVERAGE.with(|c| c.borrow_mut().take())
+}
+
+/// Counts the documented items of `krate`, by module and by kind.
+///
+/// Only the items of the crate itself are counted, leaving out the items of
+/// trait impls, which use the documentation of the trait, and re-exports.
+fn doc_coverage(mut krate: clean::Crate) -> (clean::Crate, Coverage) {
+    let mut calc = CoverageCalculator {
+        coverage: Coverage::default(),
+        path: vec![krate.name.clone()],
+        module: krate.name.clone(),
+        public: true,
+        in_trait: false,
+        stripped: false,
+    };
+    // Not `fold_crate`, which would also count the items of external traits.
+    krate.module = krate.module.take().and_then(|module| calc.fold_item(module));
+    (krate, calc.coverage)
+}
+
+/// How many items of some set are documented.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct ItemCount {
+    pub total: u64,
+    pub with_docs: u64,
+    /// The documented items whose documentation has a code block of Rust code.
+    pub with_examples: u64,
+}
+
+impl ItemCount {
+    fn count(&mut self, has_docs: bool, has_example: bool) {
+        self.total += 1;
+        if has_docs {
+            self.with_docs += 1;
+        }
+        if has_example {
+            self.with_examples += 1;
+        }
+    }
+
+    /// The percentage of documented items. A set without any items is fully
+    /// documented.
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.with_docs as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+impl ops::AddAssign for ItemCount {
+    fn add_assign(&mut self, rhs: ItemCount) {
+        self.total += rhs.total;
+        self.with_docs += rhs.with_docs;
+        self.with_examples += rhs.with_examples;
+    }
+}
+
+impl ToJson for ItemCount {
+    fn to_json(&self) -> Json {
+        let mut data = BTreeMap::new();
+        data.insert("total".to_owned(), self.total.to_json());
+        data.insert("with_docs".to_owned(), self.with_docs.to_json());
+        data.insert("with_examples".to_owned(), self.with_examples.to_json());
+        data.insert("percentage".to_owned(), self.percentage().to_json());
+        Json::Object(data)
+    }
+}
+
+/// The documentation coverage of a crate.
+#[derive(Default)]
+pub struct Coverage {
+    /// The items of each module, not counting the items of its submodules.
+    pub modules: BTreeMap<String, ItemCount>,
+    pub kinds: BTreeMap<&'static str, ItemCount>,
+    /// The full path and kind of every public item without documentation.
+    pub missing: Vec<(String, ItemType)>,
+}
+
+impl Coverage {
+    pub fn total(&self) -> ItemCount {
+        let mut total = ItemCount::default();
+        for count in self.modules.values() {
+            total += *count;
+        }
+        total
+    }
+
+    /// The coverage as text tables, followed by the list of the public items
+    /// without documentation.
+    pub fn table(&self) -> String {
+        let mut s = String::new();
+        write_table(&mut s, "Module", &self.modules, self.total());
+        s.push('\n');
+        write_table(&mut s, "Item kind", &self.kinds, self.total());
+        if !self.missing.is_empty() {
+            s.push_str("\nPublic items without documentation:\n");
+            for &(ref path, kind) in &self.missing {
+                writeln!(s, "    {} {}", kind, path).unwrap();
+            }
+        }
+        s
+    }
+}
+
+impl ToJson for Coverage {
+    fn to_json(&self) -> Json {
+        let mut data = BTreeMap::new();
+        data.insert("total".to_owned(), self.total().to_json());
+        data.insert("modules".to_owned(), self.modules.to_json());
+        data.insert("kinds".to_owned(), self.kinds.iter()
+            .map(|(&kind, count)| (kind.to_owned(), count.to_json()))
+            .collect::<BTreeMap<_, _>>()
+            .to_json());
+        data.insert("missing".to_owned(), self.missing.iter().map(|&(ref path, kind)| {
+            let mut item = BTreeMap::new();
+            item.insert("path".to_owned(), path.to_json());
+            item.insert("kind".to_owned(), kind.css_class().to_json());
+            Json::Object(item)
+        }).collect::<Vec<_>>().to_json());
+        Json::Object(data)
+    }
+}
+
+fn write_table<K: AsRef<str>>(
+    s: &mut String,
+    title: &str,
+    rows: &BTreeMap<K, ItemCount>,
+    total: ItemCount,
+) {
+    let width = rows.keys()
+                    .map(|name| name.as_ref().len())
+                    .chain(Some(title.len()))
+                    .max()
+                    .unwrap_or(0);
+    let rule = format!("+-{}-+------------+------------+----------+\n", "-".repeat(width));
+    s.push_str(&rule);
+    writeln!(s, "| {:<w$} | Documented | Percentage | Examples |", title, w = width).unwrap();
+    s.push_str(&rule);
+    {
+        let mut row = |name: &str, count: &ItemCount| {
+            writeln!(s, "| {:<w$} | {:>10} | {:>9.1}% | {:>8} |",
+                     name,
+                     format!("{}/{}", count.with_docs, count.total),
+                     count.percentage(),
+                     count.with_examples,
+                     w = width).unwrap();
+        };
+        for (name, count) in rows {
+            row(name.as_ref(), count);
+        }
+        row("Total", &total);
+    }
+    s.push_str(&rule);
+}
+
+struct CoverageCalculator {
+    coverage: Coverage,
+    /// The path of the item being walked.
+    path: Vec<String>,
+    /// The path of the module being walked.
+    module: String,
+    /// Whether the items containing the one being walked can be reached from
+    /// other crates.
+    public: bool,
+    in_trait: bool,
+    /// Whether the items being walked are in a stripped item, and aren't
+    /// part of the documentation.
+    stripped: bool,
+}
+
+impl CoverageCalculator {
+    /// Whether `item` is visible wherever its parent is.
+    fn is_public(&self, item: &Item) -> bool {
+        match item.inner {
+            clean::VariantItem(..) |
+            clean::TyMethodItem(..) |
+            clean::AssociatedTypeItem(..) |
+            clean::AssociatedConstItem(..) => true,
+            clean::MethodItem(..) if self.in_trait => true,
+            _ => item.visibility == Some(clean::Public),
+        }
+    }
+
+    /// Counts the items of an inherent impl, which are named after its type.
+    /// Trait impls use the documentation of the trait, and are left out.
+    fn fold_impl(&mut self, i: Item) -> Option<Item> {
+        let for_ = match i.inner {
+            clean::ImplItem(ref imp) if imp.trait_.is_none() => {
+                Some(format!("{:#}", imp.for_))
+            }
+            _ => None,
+        };
+        let for_ = match for_ {
+            Some(for_) => for_,
+            None => return Some(i),
+        };
+        self.path.push(for_);
+        // Impls of private types are stripped, so the items of the ones that
+        // are left are public if they are marked so.
+        let public = mem::replace(&mut self.public, true);
+        let stripped = mem::replace(&mut self.stripped, false);
+        let ret = self.fold_item_recur(i);
+        self.stripped = stripped;
+        self.public = public;
+        self.path.pop();
+        ret
+    }
+}
+
+impl fold::DocFolder for CoverageCalculator {
+    fn fold_item(&mut self, i: Item) -> Option<Item> {
+        match i.inner {
+            // Re-exports are documented where they come from.
+            clean::ImportItem(..) | clean::ExternCrateItem(..) => return Some(i),
+            // Stripped items aren't part of the documentation, but impls of
+            // visible types can still be in them.
+            clean::StrippedItem(..) => {
+                let stripped = mem::replace(&mut self.stripped, true);
+                let ret = self.fold_item_recur(i);
+                self.stripped = stripped;
+                return ret;
+            }
+            clean::ImplItem(..) => return self.fold_impl(i),
+            _ if self.stripped => return self.fold_item_recur(i),
+            _ => {}
+        }
+
+        let docs = i.collapsed_doc_value().unwrap_or_default();
+        let has_docs = !docs.trim().is_empty();
+        let has_example = has_docs && markdown::has_rust_code_block(&docs);
+        let public = self.public && self.is_public(&i);
+        let kind = i.type_();
+
+        let named = !i.is_crate() && i.name.is_some();
+        if named {
+            self.path.push(i.name.clone().unwrap());
+        }
+        let path = self.path.join("::");
+
+        self.coverage.modules.entry(self.module.clone())
+                             .or_insert_with(ItemCount::default)
+                             .count(has_docs, has_example);
+        self.coverage.kinds.entry(kind.css_class())
+                           .or_insert_with(ItemCount::default)
+                           .count(has_docs, has_example);
+        if public && !has_docs {
+            self.coverage.missing.push((path.clone(), kind));
+        }
+
+        let module = if i.is_mod() {
+            Some(mem::replace(&mut self.module, path))
+        } else {
+            None
+        };
+        let outer_public = mem::replace(&mut self.public, public);
+        let in_trait = mem::replace(&mut self.in_trait, kind == ItemType::Trait);
+
+        let ret = self.fold_item_recur(i);
+
+        self.in_trait = in_trait;
+        self.public = outer_public;
+        if let Some(module) = module {
+            self.module = module;
+        }
+        if named {
+            self.path.pop();
+        }
+        ret
+    }
+}
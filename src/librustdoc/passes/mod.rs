@@ -35,6 +35,9 @@ pub use self::unindent_comments::unindent_comments;
 mod propagate_doc_cfg;
 pub use self::propagate_doc_cfg::propagate_doc_cfg;
 
+mod calculate_doc_coverage;
+pub use self::calculate_doc_coverage::{calculate_doc_coverage, take_coverage};
+
 type Pass = (
     &'static str,                     // name
     fn(clean::Crate) -> clean::Crate, // fn
@@ -73,6 +76,11 @@ pub const PASSES: &'static [Pass] = &[
         propagate_doc_cfg,
         "propagates `#[doc(cfg(...))]` to child items",
     ),
+    (
+        "calculate-doc-coverage",
+        calculate_doc_coverage,
+        "counts the documented items of the crate, for `--show-coverage`",
+    ),
 ];
 
 pub const DEFAULT_PASSES: &'static [&'static str] = &[
@@ -90,10 +98,27 @@ pub const DEFAULT_PRIVATE_PASSES: &'static [&'static str] = &[
     "propagate-doc-cfg",
 ];
 
+pub const DEFAULT_COVERAGE_PASSES: &'static [&'static str] = &[
+    "strip-hidden",
+    "strip-private",
+    "collapse-docs",
+    "unindent-comments",
+    "calculate-doc-coverage",
+];
+
+pub const DEFAULT_PRIVATE_COVERAGE_PASSES: &'static [&'static str] = &[
+    "strip-priv-imports",
+    "collapse-docs",
+    "unindent-comments",
+    "calculate-doc-coverage",
+];
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum DefaultPassOption {
     Default,
     Private,
+    Coverage,
+    PrivateCoverage,
     None,
 }
 
@@ -101,6 +126,8 @@ pub fn defaults(default_set: DefaultPassOption) -> &'static [&'static str] {
     match default_set {
         DefaultPassOption::Default => DEFAULT_PASSES,
         DefaultPassOption::Private => DEFAULT_PRIVATE_PASSES,
+        DefaultPassOption::Coverage => DEFAULT_COVERAGE_PASSES,
+        DefaultPassOption::PrivateCoverage => DEFAULT_PRIVATE_COVERAGE_PASSES,
         DefaultPassOption::None => &[],
     }
 }
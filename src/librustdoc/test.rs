@@ -8,14 +8,18 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::collections::HashSet;
 use std::env;
 use std::ffi::OsString;
+use std::fs::{self, File};
+use std::hash::Hash;
 use std::io::prelude::*;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::panic::{self, AssertUnwindSafe};
-use std::process::Command;
+use std::process::{Command, Output};
 use std::str;
+use rustc_data_structures::stable_hasher::StableHasher;
 use rustc_data_structures::sync::Lrc;
 use std::sync::{Arc, Mutex};
 
@@ -23,11 +27,13 @@ use testing;
 use rustc_lint;
 use rustc::hir;
 use rustc::hir::intravisit;
+use rustc::hir::svh::Svh;
 use rustc::session::{self, CompileIncomplete, config};
 use rustc::session::config::{OutputType, OutputTypes, Externs, CodegenOptions};
 use rustc::session::search_paths::{SearchPaths, PathKind};
 use rustc_metadata::dynamic_lib::DynamicLibrary;
 use tempfile::Builder as TempFileBuilder;
+use tempfile::TempDir;
 use rustc_driver::{self, driver, target_features, Compilation};
 use rustc_driver::driver::phase_2_configure_and_expand;
 use rustc_metadata::cstore::CStore;
@@ -65,7 +71,9 @@ pub fn run(input_path: &Path,
            display_warnings: bool,
            linker: Option<PathBuf>,
            edition: Edition,
-           cg: CodegenOptions)
+           cg: CodegenOptions,
+           batch_doctests: bool,
+           cache_dir: Option<PathBuf>)
            -> isize {
     let input = config::Input::File(input_path.to_owned());
 
@@ -95,6 +103,13 @@ pub fn run(input_path: &Path,
         let mut sess = session::build_session_(
             sessopts, Some(input_path.to_owned()), handler, codemap.clone(),
         );
+        if let Some(ref dir) = cache_dir {
+            if let Err(e) = fs::create_dir_all(dir) {
+                sess.err(&format!("failed to create the doctest cache `{}`: {}",
+                                  dir.display(), e));
+                return 1;
+            }
+        }
         let codegen_backend = rustc_driver::get_codegen_backend(&sess);
         let cstore = CStore::new(codegen_backend.metadata_loader());
         rustc_lint::register_builtins(&mut sess.lint_store.borrow_mut(), Some(&sess));
@@ -138,9 +153,16 @@ pub fn run(input_path: &Path,
             linker,
             edition
         );
+        if batch_doctests {
+            collector.enable_batching(testing::get_concurrency());
+        }
 
         {
             let map = hir::map::map_crate(&sess, &cstore, &mut hir_forest, &defs);
+            if let Some(dir) = cache_dir {
+                let key = collector.crate_key(map.crate_hash, &sess);
+                collector.enable_cache(TestCache { dir, crate_key: key });
+            }
             let krate = map.krate();
             let mut hir_collector = HirCollector {
                 sess: &sess,
@@ -152,6 +174,15 @@ pub fn run(input_path: &Path,
                 intravisit::walk_crate(this, krate);
             });
         }
+        collector.prune_cache();
+        if collector.cached > 0 {
+            // On stderr, so as not to get in the way of `--format json`.
+            eprintln!("note: {} example{} passed before and {} ignored, as recorded in the \
+                       doctest cache",
+                      collector.cached,
+                      if collector.cached == 1 { "" } else { "s" },
+                      if collector.cached == 1 { "is" } else { "are" });
+        }
 
         test_args.insert(0, "rustdoctest".to_string());
 
@@ -210,6 +241,103 @@ fn run_test(test: &str, cratename: &str, filename: &FileName, line: usize,
         name: filename.to_owned(),
         input: test.to_owned(),
     };
+
+    // Shuffle around a few input and output handles here. We're going to pass
+    // an explicit handle into rustc to collect output messages, but we also
+    // want to catch the error message that rustc prints when it fails.
+    //
+    // We take our thread-local stderr (likely set by the test runner) and replace
+    // it with a sink that is also passed to rustc itself. When this function
+    // returns the output of the sink is copied onto the output of our own thread.
+    //
+    // The basic idea is to not use a default Handler for rustc, and then also
+    // not print things by default to the actual stderr.
+    struct Bomb(Arc<Mutex<Vec<u8>>>, Box<dyn Write+Send>);
+    impl Drop for Bomb {
+        fn drop(&mut self) {
+            let _ = self.1.write_all(&self.0.lock().unwrap());
+        }
+    }
+    let data = Arc::new(Mutex::new(Vec::new()));
+
+    let old = io::set_panic(Some(box Sink(data.clone())));
+    let _bomb = Bomb(data.clone(), old.unwrap_or(box io::stdout()));
+
+    let (libdir, outdir, compile_result) = compile(input,
+                                                   Some((filename,
+                                                         line as isize - line_offset as isize)),
+                                                   data.clone(),
+                                                   cfgs,
+                                                   libs,
+                                                   cg,
+                                                   externs,
+                                                   as_test_harness,
+                                                   no_run,
+                                                   maybe_sysroot,
+                                                   linker,
+                                                   edition);
+
+    match (compile_result, compile_fail) {
+        (Ok(()), true) => {
+            panic!("test compiled while it wasn't supposed to")
+        }
+        (Ok(()), false) => {}
+        (Err(()), true) => {
+            if error_codes.len() > 0 {
+                let out = String::from_utf8(data.lock().unwrap().to_vec()).unwrap();
+                error_codes.retain(|err| !out.contains(err));
+            }
+        }
+        (Err(()), false) => {
+            panic!("couldn't compile the test")
+        }
+    }
+
+    if error_codes.len() > 0 {
+        panic!("Some expected error codes were not found: {:?}", error_codes);
+    }
+
+    if no_run { return }
+
+    // Run the code!
+    match run_executable(outdir.path(), &libdir, None) {
+        Err(e) => panic!("couldn't run the test: {}{}", e,
+                        if e.kind() == io::ErrorKind::PermissionDenied {
+                            " - maybe your tempdir is mounted with noexec?"
+                        } else { "" }),
+        Ok(out) => {
+            if should_panic && out.status.success() {
+                panic!("test executable succeeded when it should have failed");
+            } else if !should_panic && !out.status.success() {
+                panic!("test executable failed:\n{}\n{}\n",
+                       str::from_utf8(&out.stdout).unwrap_or(""),
+                       str::from_utf8(&out.stderr).unwrap_or(""));
+            }
+        }
+    }
+}
+
+/// A writer which collects what is written into a shared buffer.
+struct Sink(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Sink {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        Write::write(&mut *self.0.lock().unwrap(), data)
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// Compiles `input` into an executable named `rust_out`, in a new temporary
+/// directory, writing the messages of the compiler to `out`. `position` is
+/// the file and line the code comes from, if it comes from a single doctest.
+///
+/// Returns the directory of the libraries of the target, the directory of the
+/// executable, and whether compilation succeeded.
+fn compile(input: config::Input, position: Option<(&FileName, isize)>,
+           out: Arc<Mutex<Vec<u8>>>, cfgs: Vec<String>, libs: SearchPaths,
+           cg: CodegenOptions, externs: Externs, as_test_harness: bool, no_run: bool,
+           maybe_sysroot: Option<PathBuf>, linker: Option<PathBuf>, edition: Edition)
+           -> (PathBuf, TempDir, Result<(), ()>) {
     let outputs = OutputTypes::new(&[(OutputType::Exe, None)]);
 
     let sessopts = config::Options {
@@ -233,39 +361,14 @@ fn run_test(test: &str, cratename: &str, filename: &FileName, line: usize,
         ..config::basic_options().clone()
     };
 
-    // Shuffle around a few input and output handles here. We're going to pass
-    // an explicit handle into rustc to collect output messages, but we also
-    // want to catch the error message that rustc prints when it fails.
-    //
-    // We take our thread-local stderr (likely set by the test runner) and replace
-    // it with a sink that is also passed to rustc itself. When this function
-    // returns the output of the sink is copied onto the output of our own thread.
-    //
-    // The basic idea is to not use a default Handler for rustc, and then also
-    // not print things by default to the actual stderr.
-    struct Sink(Arc<Mutex<Vec<u8>>>);
-    impl Write for Sink {
-        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
-            Write::write(&mut *self.0.lock().unwrap(), data)
-        }
-        fn flush(&mut self) -> io::Result<()> { Ok(()) }
-    }
-    struct Bomb(Arc<Mutex<Vec<u8>>>, Box<dyn Write+Send>);
-    impl Drop for Bomb {
-        fn drop(&mut self) {
-            let _ = self.1.write_all(&self.0.lock().unwrap());
-        }
-    }
-    let data = Arc::new(Mutex::new(Vec::new()));
-
-    let old = io::set_panic(Some(box Sink(data.clone())));
-    let _bomb = Bomb(data.clone(), old.unwrap_or(box io::stdout()));
-
-    let (libdir, outdir, compile_result) = driver::spawn_thread_pool(sessopts, |sessopts| {
-        let codemap = Lrc::new(CodeMap::new_doctest(
-            sessopts.file_path_mapping(), filename.clone(), line as isize - line_offset as isize
-        ));
-        let emitter = errors::emitter::EmitterWriter::new(box Sink(data.clone()),
+    driver::spawn_thread_pool(sessopts, |sessopts| {
+        let codemap = Lrc::new(match position {
+            Some((filename, line_offset)) => CodeMap::new_doctest(
+                sessopts.file_path_mapping(), filename.clone(), line_offset
+            ),
+            None => CodeMap::new(sessopts.file_path_mapping()),
+        });
+        let emitter = errors::emitter::EmitterWriter::new(box Sink(out),
                                                         Some(codemap.clone()),
                                                         false,
                                                         false);
@@ -315,62 +418,29 @@ fn run_test(test: &str, cratename: &str, filename: &FileName, line: usize,
             Err(_) | Ok(Err(CompileIncomplete::Errored(_))) => Err(())
         };
 
-        (libdir, outdir, compile_result)
-    });
-
-    match (compile_result, compile_fail) {
-        (Ok(()), true) => {
-            panic!("test compiled while it wasn't supposed to")
-        }
-        (Ok(()), false) => {}
-        (Err(()), true) => {
-            if error_codes.len() > 0 {
-                let out = String::from_utf8(data.lock().unwrap().to_vec()).unwrap();
-                error_codes.retain(|err| !out.contains(err));
-            }
-        }
-        (Err(()), false) => {
-            panic!("couldn't compile the test")
-        }
-    }
-
-    if error_codes.len() > 0 {
-        panic!("Some expected error codes were not found: {:?}", error_codes);
-    }
-
-    if no_run { return }
+        (libdir, outdir.into_inner().unwrap(), compile_result)
+    })
+}
 
-    // Run the code!
-    //
+/// Runs the `rust_out` executable of `outdir`, giving it `arg` if any.
+fn run_executable(outdir: &Path, libdir: &Path, arg: Option<usize>) -> io::Result<Output> {
     // We're careful to prepend the *target* dylib search path to the child's
     // environment to ensure that the target loads the right libraries at
     // runtime. It would be a sad day if the *host* libraries were loaded as a
     // mistake.
-    let mut cmd = Command::new(&outdir.lock().unwrap().path().join("rust_out"));
+    let mut cmd = Command::new(&outdir.join("rust_out"));
+    if let Some(arg) = arg {
+        cmd.arg(arg.to_string());
+    }
     let var = DynamicLibrary::envvar();
     let newpath = {
         let path = env::var_os(var).unwrap_or(OsString::new());
         let mut path = env::split_paths(&path).collect::<Vec<_>>();
-        path.insert(0, libdir.clone());
+        path.insert(0, libdir.to_path_buf());
         env::join_paths(path).unwrap()
     };
     cmd.env(var, &newpath);
-
-    match cmd.output() {
-        Err(e) => panic!("couldn't run the test: {}{}", e,
-                        if e.kind() == io::ErrorKind::PermissionDenied {
-                            " - maybe your tempdir is mounted with noexec?"
-                        } else { "" }),
-        Ok(out) => {
-            if should_panic && out.status.success() {
-                panic!("test executable succeeded when it should have failed");
-            } else if !should_panic && !out.status.success() {
-                panic!("test executable failed:\n{}\n{}\n",
-                       str::from_utf8(&out.stdout).unwrap_or(""),
-                       str::from_utf8(&out.stderr).unwrap_or(""));
-            }
-        }
-    }
+    cmd.output()
 }
 
 /// Makes the test file. Also returns the number of lines before the code begins
@@ -403,20 +473,53 @@ pub fn make_test(s: &str,
     // are intended to be crate attributes.
     prog.push_str(&crate_attrs);
 
+    if needs_extern_crate(s, cratename, opts) {
+        prog.push_str(&format!("extern crate {};\n", cratename.unwrap()));
+        line_offset += 1;
+    }
+
+    if dont_insert_main || has_main(s) {
+        prog.push_str(everything_else);
+    } else {
+        prog.push_str("fn main() {\n");
+        line_offset += 1;
+        prog.push_str(everything_else);
+        prog.push_str("\n}");
+    }
+
+    info!("final test program: {}", prog);
+
+    (prog, line_offset)
+}
+
+/// Whether `extern crate cratename;` must be added to the test: it uses the
+/// crate, and doesn't link to crates on its own.
+fn needs_extern_crate(s: &str, cratename: Option<&str>, opts: &TestOptions) -> bool {
     // Don't inject `extern crate std` because it's already injected by the
     // compiler.
-    if !s.contains("extern crate") && !opts.no_crate_inject && cratename != Some("std") {
-        if let Some(cratename) = cratename {
-            if s.contains(cratename) {
-                prog.push_str(&format!("extern crate {};\n", cratename));
-                line_offset += 1;
-            }
-        }
+    if s.contains("extern crate") || opts.no_crate_inject || cratename == Some("std") {
+        return false;
     }
+    let cratename = match cratename {
+        Some(cratename) => cratename,
+        None => return false,
+    };
 
+    // Only the name on its own counts, not as part of another identifier.
+    fn is_ident_char(c: char) -> bool {
+        c == '_' || c.is_alphanumeric()
+    }
+    s.match_indices(cratename).any(|(start, _)| {
+        !s[..start].chars().next_back().map_or(false, is_ident_char) &&
+            !s[start + cratename.len()..].chars().next().map_or(false, is_ident_char)
+    })
+}
+
+/// Whether the test has its own `fn main`.
+fn has_main(s: &str) -> bool {
     // FIXME (#21299): prefer libsyntax or some other actual parser over this
     // best-effort ad hoc approach
-    let already_has_main = s.lines()
+    s.lines()
         .map(|line| {
             let comment = line.find("//");
             if let Some(comment_begins) = comment {
@@ -425,20 +528,197 @@ pub fn make_test(s: &str,
                 line
             }
         })
-        .any(|code| code.contains("fn main"));
+        .any(|code| code.contains("fn main"))
+}
 
-    if dont_insert_main || already_has_main {
-        prog.push_str(everything_else);
-    } else {
-        prog.push_str("fn main() {\n");
-        line_offset += 1;
-        prog.push_str(everything_else);
-        prog.push_str("\n}");
+/// Makes the module of the test in a batch, where it is the test at `index`.
+/// Also returns whether the batch must link to the crate named `cratename`.
+///
+/// Returns `None` for the tests which can't be put in a module: the ones with
+/// crate attributes, `extern crate` or their own `fn main`.
+fn make_batched_test(s: &str, cratename: Option<&str>, opts: &TestOptions, index: usize)
+                     -> Option<(String, bool)> {
+    let (crate_attrs, everything_else) = partition_source(s);
+    if !crate_attrs.trim().is_empty() || s.contains("extern crate") || has_main(s) {
+        return None;
     }
 
-    info!("final test program: {}", prog);
+    // Paths starting with `std` are only relative to the crate root in 2015.
+    let mut module = format!("mod __doctest_{} {{\nuse std;\n", index);
+    let uses_crate = needs_extern_crate(s, cratename, opts);
+    if uses_crate {
+        module.push_str(&format!("extern crate {};\n", cratename.unwrap()));
+    }
+    module.push_str("pub fn main() {\n");
+    module.push_str(everything_else.trim());
+    module.push_str("\n}\n}\n");
+    Some((module, uses_crate))
+}
 
-    (prog, line_offset)
+/// Records the doctests which passed, so that they are skipped as long as
+/// nothing they depend on changes.
+///
+/// The records of the tests which changed or were removed since they passed
+/// are removed once the tests are collected, see `prune`.
+pub struct TestCache {
+    /// The directory with an empty file for each test which passed, named
+    /// after its key.
+    pub dir: PathBuf,
+    /// The part of the keys shared by all the tests of the crate.
+    pub crate_key: u128,
+}
+
+impl TestCache {
+    fn key(&self, test: &str, config: &LangString) -> String {
+        let mut hasher = StableHasher::<u128>::new();
+        self.crate_key.hash(&mut hasher);
+        test.hash(&mut hasher);
+        config.should_panic.hash(&mut hasher);
+        config.no_run.hash(&mut hasher);
+        config.test_harness.hash(&mut hasher);
+        config.compile_fail.hash(&mut hasher);
+        config.error_codes.hash(&mut hasher);
+        format!("{:032x}", hasher.finish())
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.dir.join(key).exists()
+    }
+
+    fn insert(&self, key: &str) {
+        // Failing to record the test only means it will run again next time.
+        let _ = File::create(self.dir.join(key));
+    }
+
+    /// Removes the records of the tests whose keys aren't in `keys`.
+    fn prune(&self, keys: &HashSet<String>) {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let stale = match entry.file_name().to_str() {
+                Some(name) => !keys.contains(name),
+                None => true,
+            };
+            if stale {
+                // A stale record left behind only takes some space.
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+/// Doctests compiled together into a single executable, which runs the one
+/// whose index it is given.
+///
+/// The batch is compiled by the first of its tests to run, and the others
+/// wait for it. A test which can't be run from its batch, because the batch
+/// doesn't compile or the test fails, is compiled and run on its own. The
+/// tests can only tell they are batched from their module path, the lines of
+/// their code, and the arguments of the executable.
+struct Batch {
+    cfgs: Vec<String>,
+    libs: SearchPaths,
+    cg: CodegenOptions,
+    externs: Externs,
+    maybe_sysroot: Option<PathBuf>,
+    linker: Option<PathBuf>,
+    edition: Edition,
+    state: Mutex<BatchState>,
+}
+
+#[derive(Default)]
+struct BatchState {
+    /// The module of each test.
+    modules: Vec<String>,
+    /// The crate to link to, if some tests use it.
+    extern_crate: Option<String>,
+    /// Once the batch was compiled, the directory of the libraries of the
+    /// target and the directory of the executable, or `None` if the batch
+    /// doesn't compile.
+    compiled: Option<Option<(PathBuf, TempDir)>>,
+}
+
+impl Batch {
+    /// Adds `test` to the batch, if it can be put in one, and returns its
+    /// index.
+    fn add(&self, test: &str, cratename: &str, opts: &TestOptions) -> Option<usize> {
+        let mut state = self.state.lock().unwrap();
+        let index = state.modules.len();
+        let (module, uses_crate) = make_batched_test(test, Some(cratename), opts, index)?;
+        state.modules.push(module);
+        if uses_crate {
+            state.extern_crate = Some(cratename.to_owned());
+        }
+        Some(index)
+    }
+
+    /// Runs the test at `index`, compiling the batch first if no test did
+    /// yet, and returns whether it passed.
+    fn run(&self, index: usize, should_panic: bool, no_run: bool) -> bool {
+        let (outdir, libdir) = {
+            let mut state = self.state.lock().unwrap();
+            if state.compiled.is_none() {
+                let compiled = self.compile(&state);
+                state.compiled = Some(compiled);
+            }
+            match state.compiled {
+                Some(Some((ref libdir, ref outdir))) => {
+                    (outdir.path().to_path_buf(), libdir.clone())
+                }
+                _ => return false,
+            }
+        };
+        if no_run {
+            return true;
+        }
+        match run_executable(&outdir, &libdir, Some(index)) {
+            Ok(out) => out.status.success() != should_panic,
+            Err(_) => false,
+        }
+    }
+
+    fn compile(&self, state: &BatchState) -> Option<(PathBuf, TempDir)> {
+        // The crate is linked to at the root too, for paths relative to it
+        // like the ones of `use`.
+        let mut prog = String::from("#![allow(unused)]\n");
+        if let Some(ref cratename) = state.extern_crate {
+            prog.push_str(&format!("extern crate {};\n", cratename));
+        }
+        for module in &state.modules {
+            prog.push_str(module);
+        }
+        prog.push_str("fn main() {\n");
+        prog.push_str("    match ::std::env::args().nth(1).and_then(|arg| arg.parse().ok()) {\n");
+        for index in 0..state.modules.len() {
+            prog.push_str(&format!("        Some({0}usize) => __doctest_{0}::main(),\n", index));
+        }
+        prog.push_str("        _ => ::std::process::exit(2),\n    }\n}\n");
+        info!("batched test program: {}", prog);
+
+        let input = config::Input::Str {
+            name: FileName::Custom("batched doctests".to_owned()),
+            input: prog,
+        };
+        // The errors are reported when the tests are compiled on their own.
+        let (libdir, outdir, result) = compile(input,
+                                               None,
+                                               Arc::new(Mutex::new(Vec::new())),
+                                               self.cfgs.clone(),
+                                               self.libs.clone(),
+                                               self.cg.clone(),
+                                               self.externs.clone(),
+                                               false,
+                                               false,
+                                               self.maybe_sysroot.clone(),
+                                               self.linker.clone(),
+                                               self.edition);
+        match result {
+            Ok(()) => Some((libdir, outdir)),
+            Err(()) => None,
+        }
+    }
 }
 
 // FIXME(aburka): use a real parser to deal with multiline attributes
@@ -504,6 +784,18 @@ pub struct Collector {
     filename: Option<PathBuf>,
     linker: Option<PathBuf>,
     edition: Edition,
+
+    /// Where the tests which passed are recorded, if they are skipped while
+    /// they stay the same.
+    cache: Option<Arc<TestCache>>,
+    /// The keys in `cache` of the tests collected so far.
+    cache_keys: HashSet<String>,
+    /// The number of tests skipped because they passed before.
+    cached: usize,
+    /// The batches the tests are compiled in, if they are batched.
+    batches: Vec<Arc<Batch>>,
+    /// The number of tests added to a batch so far.
+    batched: usize,
 }
 
 impl Collector {
@@ -527,9 +819,85 @@ impl Collector {
             filename,
             linker,
             edition,
+            cache: None,
+            cache_keys: HashSet::new(),
+            cached: 0,
+            batches: Vec::new(),
+            batched: 0,
+        }
+    }
+
+    /// Compiles the tests which allow it in `count` batches, rather than each
+    /// on its own.
+    pub fn enable_batching(&mut self, count: usize) {
+        let batches = (0..count).map(|_| Arc::new(Batch {
+            cfgs: self.cfgs.clone(),
+            libs: self.libs.clone(),
+            cg: self.cg.clone(),
+            externs: self.externs.clone(),
+            maybe_sysroot: self.maybe_sysroot.clone(),
+            linker: self.linker.clone(),
+            edition: self.edition,
+            state: Mutex::new(BatchState::default()),
+        })).collect();
+        self.batches = batches;
+    }
+
+    /// Skips the tests recorded in `cache` as having passed, and records the
+    /// ones which pass.
+    pub fn enable_cache(&mut self, cache: TestCache) {
+        self.cache = Some(Arc::new(cache));
+    }
+
+    /// Removes the records of the tests which weren't collected from the
+    /// cache, as they changed or were removed.
+    pub fn prune_cache(&self) {
+        if let Some(ref cache) = self.cache {
+            cache.prune(&self.cache_keys);
         }
     }
 
+    /// The hash of everything the tests depend on besides their own code: the
+    /// crate, whose hash is `crate_hash`, and the options and libraries the
+    /// tests are compiled with.
+    fn crate_key(&self, crate_hash: Svh, sess: &session::Session) -> u128 {
+        let mut hasher = StableHasher::<u128>::new();
+        option_env!("CFG_VERSION").hash(&mut hasher);
+        crate_hash.as_u64().hash(&mut hasher);
+        self.cratename.hash(&mut hasher);
+        sess.opts.dep_tracking_hash().hash(&mut hasher);
+        self.cfgs.hash(&mut hasher);
+        self.linker.hash(&mut hasher);
+        self.maybe_sysroot.hash(&mut hasher);
+        self.opts.no_crate_inject.hash(&mut hasher);
+        self.opts.display_warnings.hash(&mut hasher);
+        self.opts.attrs.hash(&mut hasher);
+        // Rebuilding a library changes the file given for it with `--extern`.
+        for (_, paths) in self.externs.iter() {
+            for path in paths {
+                path.hash(&mut hasher);
+                if let Ok(metadata) = fs::metadata(path) {
+                    metadata.len().hash(&mut hasher);
+                    metadata.modified().ok().hash(&mut hasher);
+                }
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Adds `test` to the next batch if it can be compiled in one, and returns
+    /// that batch with the index of the test in it.
+    fn add_to_batch(&mut self, test: &str, config: &LangString) -> Option<(Arc<Batch>, usize)> {
+        if self.batches.is_empty() || config.ignore || config.compile_fail ||
+           config.test_harness || !self.opts.attrs.is_empty() || self.opts.display_warnings {
+            return None;
+        }
+        let batch = self.batches[self.batched % self.batches.len()].clone();
+        let index = batch.add(test, &self.cratename, &self.opts)?;
+        self.batched += 1;
+        Some((batch, index))
+    }
+
     fn generate_name(&self, line: usize, filename: &FileName) -> String {
         format!("{} - {} (line {})", filename, self.names.join("::"), line)
     }
@@ -546,15 +914,34 @@ impl Collector {
         let maybe_sysroot = self.maybe_sysroot.clone();
         let linker = self.linker.clone();
         let edition = self.edition;
+        let mut desc = testing::TestDesc {
+            name: testing::DynTestName(name.clone()),
+            ignore: config.ignore,
+            // compiler failures are test failures
+            should_panic: testing::ShouldPanic::No,
+            allow_fail: config.allow_fail,
+            timeout: None,
+        };
+        let cached = self.cache.as_ref().map(|cache| (cache.clone(), cache.key(&test, &config)));
+        if let Some((ref cache, ref key)) = cached {
+            self.cache_keys.insert(key.clone());
+            if cache.contains(key) {
+                debug!("Skipping test {}, which passed before", name);
+                // It didn't run, so it isn't reported as passing; the name is
+                // kept, for filters and for the tools reading the results.
+                desc.ignore = true;
+                self.cached += 1;
+                self.tests.push(testing::TestDescAndFn {
+                    desc,
+                    testfn: testing::DynTestFn(box || {}),
+                });
+                return;
+            }
+        }
+        let batch = self.add_to_batch(&test, &config);
         debug!("Creating test {}: {}", name, test);
         self.tests.push(testing::TestDescAndFn {
-            desc: testing::TestDesc {
-                name: testing::DynTestName(name.clone()),
-                ignore: config.ignore,
-                // compiler failures are test failures
-                should_panic: testing::ShouldPanic::No,
-                allow_fail: config.allow_fail,
-            },
+            desc,
             testfn: testing::DynTestFn(box move || {
                 let panic = io::set_panic(None);
                 let print = io::set_print(None);
@@ -563,23 +950,36 @@ impl Collector {
                         io::set_panic(panic);
                         io::set_print(print);
                         hygiene::set_default_edition(edition);
-                        run_test(&test,
-                                 &cratename,
-                                 &filename,
-                                 line,
-                                 cfgs,
-                                 libs,
-                                 cg,
-                                 externs,
-                                 config.should_panic,
-                                 config.no_run,
-                                 config.test_harness,
-                                 config.compile_fail,
-                                 config.error_codes,
-                                 &opts,
-                                 maybe_sysroot,
-                                 linker,
-                                 edition)
+                        // A test which fails in its batch is run again on its
+                        // own, to report the failure as usual.
+                        let passed = match batch {
+                            Some((ref batch, index)) => {
+                                batch.run(index, config.should_panic, config.no_run)
+                            }
+                            None => false,
+                        };
+                        if !passed {
+                            run_test(&test,
+                                     &cratename,
+                                     &filename,
+                                     line,
+                                     cfgs,
+                                     libs,
+                                     cg,
+                                     externs,
+                                     config.should_panic,
+                                     config.no_run,
+                                     config.test_harness,
+                                     config.compile_fail,
+                                     config.error_codes,
+                                     &opts,
+                                     maybe_sysroot,
+                                     linker,
+                                     edition);
+                        }
+                        if let Some((cache, key)) = cached {
+                            cache.insert(&key);
+                        }
                     }))
                 } {
                     Ok(()) => (),
@@ -762,7 +1162,7 @@ impl<'a, 'hir> intravisit::Visitor<'hir> for HirCollector<'a, 'hir> {
 
 #[cfg(test)]
 mod tests {
-    use super::{TestOptions, make_test};
+    use super::{TestOptions, make_batched_test, make_test};
 
     #[test]
     fn make_test_basic() {
@@ -1006,4 +1406,73 @@ assert_eq!(2+2, 4);
         let output = make_test(input, None, false, &opts);
         assert_eq!(output, (expected.clone(), 1));
     }
+
+    #[test]
+    fn make_batched_test_basic() {
+        //wraps the test in its own module, where `fn main` can be reached
+        let input =
+"assert_eq!(2+2, 4);";
+        let expected =
+"mod __doctest_3 {
+use std;
+pub fn main() {
+assert_eq!(2+2, 4);
+}
+}
+".to_string();
+        let output = make_batched_test(input, Some("asdf"), &TestOptions::default(), 3);
+        assert_eq!(output, Some((expected, false)));
+    }
+
+    #[test]
+    fn make_batched_test_crate_name() {
+        //links to the crate in the module, and tells the batch to link to it too
+        let input =
+"use asdf::qwop;
+assert_eq!(2+2, 4);";
+        let expected =
+"mod __doctest_0 {
+use std;
+extern crate asdf;
+pub fn main() {
+use asdf::qwop;
+assert_eq!(2+2, 4);
+}
+}
+".to_string();
+        let output = make_batched_test(input, Some("asdf"), &TestOptions::default(), 0);
+        assert_eq!(output, Some((expected, true)));
+    }
+
+    #[test]
+    fn make_batched_test_unbatchable() {
+        //tests which need to be at the crate root can't be batched
+        let inputs = [
+            "#![feature(asdf)]\nassert_eq!(2+2, 4);",
+            "extern crate asdf;\nassert_eq!(2+2, 4);",
+            "fn main() {\n    assert_eq!(2+2, 4);\n}",
+        ];
+        for input in &inputs {
+            assert_eq!(make_batched_test(input, Some("asdf"), &TestOptions::default(), 0), None);
+        }
+    }
+
+    #[test]
+    fn make_batched_test_crate_name_in_identifier() {
+        //names which only contain the name of the crate don't link to it
+        let input =
+"let asdf_qwop = 2;
+assert_eq!(asdf_qwop + 2, 4);";
+        let expected =
+"mod __doctest_0 {
+use std;
+pub fn main() {
+let asdf_qwop = 2;
+assert_eq!(asdf_qwop + 2, 4);
+}
+}
+".to_string();
+        let output = make_batched_test(input, Some("asdf"), &TestOptions::default(), 0);
+        assert_eq!(output, Some((expected, false)));
+    }
 }
@@ -88,7 +88,9 @@ pub use alloc_crate::alloc::*;
 pub use alloc_system::System;
 
 #[cfg(target_os = "switch")]
-use ALLOC as System;
+#[stable(feature = "alloc_system_type", since = "1.28.0")]
+#[doc(inline)]
+pub use sys::alloc::System;
 
 static HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
 
@@ -339,10 +339,6 @@
 #[global_allocator]
 static ALLOC: alloc_system::System = alloc_system::System;
 
-#[cfg(target_os = "switch")]
-#[global_allocator]
-static ALLOC: megaton_allocator::Allocator = megaton_allocator::Allocator::new();
-
 // Explicitly import the prelude. The compiler uses this same unstable attribute
 // to import the prelude implicitly when building crates that depend on std.
 #[prelude_import]
@@ -368,8 +364,6 @@ extern crate alloc_system;
 #[cfg(target_os = "switch")]
 extern crate megaton_hammer;
 #[cfg(target_os = "switch")]
-extern crate megaton_allocator;
-#[cfg(target_os = "switch")]
 #[macro_use]
 extern crate lazy_static;
 
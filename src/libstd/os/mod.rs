@@ -49,10 +49,22 @@ cfg_if! {
         #[cfg(target_os = "fuchsia")]    pub mod fuchsia;
         #[cfg(target_os = "hermit")]     pub mod hermit;
 
-        #[cfg(any(target_os = "switch", target_os = "redox", unix))]
+        #[cfg(any(target_os = "redox", unix))]
         #[stable(feature = "rust1", since = "1.0.0")]
         pub use sys::ext as unix;
 
+        // The Switch-only modules, like `alloc`, are only in `switch`.
+        #[cfg(target_os = "switch")]
+        #[stable(feature = "rust1", since = "1.0.0")]
+        pub mod unix {
+            #[stable(feature = "rust1", since = "1.0.0")]
+            pub use sys::ext::{ffi, fs, prelude};
+        }
+
+        #[cfg(target_os = "switch")]
+        #[unstable(feature = "switch_ext", issue = "0")]
+        pub use sys::ext as switch;
+
         #[cfg(windows)]
         #[stable(feature = "rust1", since = "1.0.0")]
         pub use sys::ext as windows;
@@ -56,6 +56,13 @@ cfg_if! {
     }
 }
 
+// The Switch sys layer talks to Horizon only through the traits in
+// `switch/services`, so most of it can also be built on a unix host against
+// mock services, and tested along with the rest of libstd.
+#[cfg(all(test, unix))]
+#[path = "switch/host.rs"]
+mod switch_host;
+
 // Import essential modules from both platforms when documenting. These are
 // then later used in the `std::os` module when documenting, for example,
 // Windows when we're compiling for Linux.
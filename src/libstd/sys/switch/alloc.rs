@@ -0,0 +1,367 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The system allocator for the Switch.
+//!
+//! Horizon doesn't give us `malloc`. What it gives us is a single contiguous
+//! heap region whose size is controlled with the `SetHeapSize` syscall. The
+//! base address stays the same when the heap grows, so we manage the region
+//! with a simple address-ordered free list, and grow it whenever no hole is
+//! big enough to satisfy a request.
+//!
+//! Since `GlobalAlloc::dealloc` hands us the layout back, blocks don't need a
+//! header: every allocation is rounded up to `BLOCK` bytes, and freeing just
+//! puts the rounded range back in the free list, merging it with its
+//! neighbours.
+
+use alloc::{GlobalAlloc, Layout};
+use cmp;
+use mem;
+use ptr;
+use sync::atomic::{AtomicUsize, Ordering};
+use sys::mutex::Mutex;
+
+#[cfg(target_os = "switch")]
+use megaton_hammer::kernel::svc::set_heap_size;
+#[cfg(not(target_os = "switch"))]
+use super::set_heap_size;
+
+/// The default memory allocator provided by the operating system.
+///
+/// On the Switch this manages the process heap set up through the
+/// `SetHeapSize` syscall.
+///
+/// This type can be used in a `static` item with the `#[global_allocator]`
+/// attribute, or be wrapped by a custom global allocator, exactly like on
+/// other platforms. Heap usage statistics for this allocator are available
+/// through [`std::os::switch::alloc::heap_stats`].
+///
+/// [`std::os::switch::alloc::heap_stats`]: ../os/switch/alloc/fn.heap_stats.html
+#[stable(feature = "alloc_system_type", since = "1.28.0")]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct System;
+
+/// Granularity of every block handed out, and the minimum alignment we
+/// guarantee. A free hole needs to store a `Hole` in place, so this must be at
+/// least `mem::size_of::<Hole>()`.
+const BLOCK: usize = 16;
+
+/// `SetHeapSize` only accepts multiples of 2MiB.
+const HEAP_GRANULARITY: usize = 0x200000;
+
+struct Hole {
+    size: usize,
+    next: *mut Hole,
+}
+
+struct Heap {
+    base: usize,
+    size: usize,
+    // Address-ordered list of free holes.
+    free: *mut Hole,
+}
+
+static LOCK: Mutex = Mutex::new();
+static mut HEAP: Heap = Heap { base: 0, size: 0, free: 0 as *mut Hole };
+
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+static USED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Usage counters of the system heap, in bytes.
+///
+/// These only track memory handed out by [`System`]. If a custom
+/// `#[global_allocator]` doesn't forward to it, the counters stay at zero.
+///
+/// [`System`]: ../../../../std/alloc/struct.System.html
+#[unstable(feature = "switch_ext", issue = "0")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Size of the heap region currently mapped with `SetHeapSize`.
+    pub total: usize,
+    /// Bytes currently allocated, including rounding to the block size.
+    pub used: usize,
+    /// Highest value `used` has reached since the process started.
+    pub peak: usize,
+}
+
+/// Returns a snapshot of the system heap usage.
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(switch_ext)]
+/// use std::os::switch::alloc::heap_stats;
+///
+/// let v = vec![0u8; 4096];
+/// let stats = heap_stats();
+/// println!("{} of {} bytes used (peak {})", stats.used, stats.total, stats.peak);
+/// # drop(v);
+/// ```
+#[unstable(feature = "switch_ext", issue = "0")]
+pub fn heap_stats() -> HeapStats {
+    HeapStats {
+        total: TOTAL.load(Ordering::Relaxed),
+        used: USED.load(Ordering::Relaxed),
+        peak: PEAK.load(Ordering::Relaxed),
+    }
+}
+
+fn round_up(n: usize, to: usize) -> usize {
+    (n + to - 1) & !(to - 1)
+}
+
+/// Like `round_up`, but `None` if the result doesn't fit in a `usize`.
+fn checked_round_up(n: usize, to: usize) -> Option<usize> {
+    n.checked_add(to - 1).map(|n| n & !(to - 1))
+}
+
+/// The size of the block holding an allocation of `layout`. Only `alloc`
+/// needs to check for overflow: the layouts given back to `dealloc` and
+/// `realloc` were allocated successfully.
+fn block_size(layout: &Layout) -> usize {
+    round_up(cmp::max(layout.size(), 1), BLOCK)
+}
+
+impl Heap {
+    /// Carves `size` bytes aligned to `align` out of the first hole that can
+    /// hold them.
+    unsafe fn take(&mut self, size: usize, align: usize) -> *mut u8 {
+        let mut prev: *mut *mut Hole = &mut self.free;
+        while !(*prev).is_null() {
+            let hole = *prev;
+            let start = hole as usize;
+            let end = start + (*hole).size;
+            let addr = round_up(start, align);
+            if addr + size <= end {
+                let next = (*hole).next;
+                // Both leftovers are multiples of BLOCK, so any non-empty one
+                // is large enough to hold a `Hole`.
+                let after = addr + size;
+                let mut link = next;
+                if after < end {
+                    let tail = after as *mut Hole;
+                    ptr::write(tail, Hole { size: end - after, next: link });
+                    link = tail;
+                }
+                if addr > start {
+                    (*hole).size = addr - start;
+                    (*hole).next = link;
+                } else {
+                    *prev = link;
+                }
+                return addr as *mut u8;
+            }
+            prev = &mut (*hole).next;
+        }
+        ptr::null_mut()
+    }
+
+    /// Returns `[addr, addr + size)` to the free list, merging it with
+    /// adjacent holes.
+    unsafe fn give(&mut self, addr: usize, size: usize) {
+        let mut prev: *mut Hole = ptr::null_mut();
+        let mut next = self.free;
+        while !next.is_null() && (next as usize) < addr {
+            prev = next;
+            next = (*next).next;
+        }
+
+        let new = addr as *mut Hole;
+        ptr::write(new, Hole { size, next });
+        if !next.is_null() && addr + size == next as usize {
+            (*new).size += (*next).size;
+            (*new).next = (*next).next;
+        }
+
+        if prev.is_null() {
+            self.free = new;
+        } else if prev as usize + (*prev).size == addr {
+            (*prev).size += (*new).size;
+            (*prev).next = (*new).next;
+        } else {
+            (*prev).next = new;
+        }
+    }
+
+    /// Grows the heap so that at least `size` more bytes aligned to `align`
+    /// become available at its end.
+    unsafe fn grow(&mut self, size: usize, align: usize) -> bool {
+        let new_size = size.checked_add(align)
+            .and_then(|n| self.size.checked_add(n))
+            .and_then(|n| checked_round_up(n, HEAP_GRANULARITY));
+        let new_size = match new_size {
+            Some(n) => n,
+            None => return false,
+        };
+        let mut addr: *mut u8 = ptr::null_mut();
+        if set_heap_size(&mut addr, new_size as u64) != 0 {
+            return false;
+        }
+        if self.size == 0 {
+            self.base = addr as usize;
+        }
+        debug_assert_eq!(self.base, addr as usize, "heap base moved while growing");
+
+        let old_end = self.base + self.size;
+        self.size = new_size;
+        TOTAL.store(new_size, Ordering::Relaxed);
+        self.give(old_end, new_size - (old_end - self.base));
+        true
+    }
+
+    unsafe fn alloc(&mut self, layout: &Layout) -> *mut u8 {
+        let size = match checked_round_up(cmp::max(layout.size(), 1), BLOCK) {
+            Some(size) => size,
+            None => return ptr::null_mut(),
+        };
+        let align = cmp::max(layout.align(), BLOCK);
+        let mut ptr = self.take(size, align);
+        if ptr.is_null() && self.grow(size, align) {
+            ptr = self.take(size, align);
+        }
+        if !ptr.is_null() {
+            let used = USED.fetch_add(size, Ordering::Relaxed) + size;
+            if used > PEAK.load(Ordering::Relaxed) {
+                PEAK.store(used, Ordering::Relaxed);
+            }
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: &Layout) {
+        let size = block_size(layout);
+        USED.fetch_sub(size, Ordering::Relaxed);
+        self.give(ptr as usize, size);
+    }
+}
+
+#[stable(feature = "alloc_system_type", since = "1.28.0")]
+unsafe impl GlobalAlloc for System {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        debug_assert!(mem::size_of::<Hole>() <= BLOCK);
+        LOCK.lock();
+        let ptr = HEAP.alloc(&layout);
+        LOCK.unlock();
+        ptr
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LOCK.lock();
+        HEAP.dealloc(ptr, &layout);
+        LOCK.unlock();
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // Shrinking, or growing within the slack of the current block, can
+        // be done in place.
+        if block_size(&layout) >= new_size {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let (old, new) = (block_size(&layout), block_size(&new_layout));
+            if new < old {
+                LOCK.lock();
+                HEAP.dealloc(ptr.offset(new as isize),
+                             &Layout::from_size_align_unchecked(old - new, BLOCK));
+                LOCK.unlock();
+            }
+            return ptr;
+        }
+
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size());
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(align(256))]
+    struct Arena([u8; 1024]);
+
+    /// A heap made of a single hole covering `arena`.
+    fn heap(arena: &mut Arena) -> Heap {
+        let base = arena.0.as_mut_ptr() as usize;
+        let mut heap = Heap { base, size: arena.0.len(), free: ptr::null_mut() };
+        unsafe { heap.give(base, heap.size) };
+        heap
+    }
+
+    /// The offsets and sizes of the holes of `heap`.
+    fn holes(heap: &Heap) -> Vec<(usize, usize)> {
+        let mut holes = vec![];
+        let mut hole = heap.free;
+        while !hole.is_null() {
+            unsafe {
+                holes.push((hole as usize - heap.base, (*hole).size));
+                hole = (*hole).next;
+            }
+        }
+        holes
+    }
+
+    #[test]
+    fn give_merges_neighbours() {
+        let mut arena = Arena([0; 1024]);
+        let mut heap = heap(&mut arena);
+        unsafe {
+            let a = heap.take(32, BLOCK) as usize;
+            let b = heap.take(32, BLOCK) as usize;
+            let c = heap.take(32, BLOCK) as usize;
+            assert_eq!((a - heap.base, b - heap.base, c - heap.base), (0, 32, 64));
+            assert_eq!(holes(&heap), [(96, 928)]);
+
+            heap.give(b, 32);
+            assert_eq!(holes(&heap), [(32, 32), (96, 928)]);
+            heap.give(a, 32);
+            assert_eq!(holes(&heap), [(0, 64), (96, 928)]);
+            heap.give(c, 32);
+            assert_eq!(holes(&heap), [(0, 1024)]);
+        }
+    }
+
+    #[test]
+    fn take_aligns() {
+        let mut arena = Arena([0; 1024]);
+        let mut heap = heap(&mut arena);
+        unsafe {
+            let a = heap.take(16, BLOCK) as usize;
+            let b = heap.take(64, 256) as usize;
+            assert_eq!((a - heap.base, b - heap.base), (0, 256));
+            // The padding before `b` stays free.
+            assert_eq!(holes(&heap), [(16, 240), (320, 704)]);
+
+            heap.give(b, 64);
+            heap.give(a, 16);
+            assert_eq!(holes(&heap), [(0, 1024)]);
+            assert!(heap.take(2048, BLOCK).is_null());
+        }
+    }
+
+    #[test]
+    fn huge_layouts_fail() {
+        let mut heap = Heap { base: 0, size: 0, free: ptr::null_mut() };
+        unsafe {
+            let layout = Layout::from_size_align(usize::max_value(), 1).unwrap();
+            assert!(heap.alloc(&layout).is_null());
+            assert!(!heap.grow(usize::max_value() - BLOCK, 64));
+            assert!(!heap.grow(usize::max_value() - HEAP_GRANULARITY, BLOCK));
+        }
+        assert_eq!(heap.size, 0);
+    }
+}
@@ -0,0 +1,6 @@
+//! Switch-specific extensions to the memory allocation APIs.
+
+#![unstable(feature = "switch_ext", issue = "0")]
+
+#[doc(inline)]
+pub use sys::alloc::{heap_stats, HeapStats};
@@ -4,6 +4,7 @@
 
 use io;
 use fs::File;
+use sys::path;
 use sys_common::{FromInner, AsInner};
 
 /// Switch-specitic extension to [`File`]
@@ -22,3 +23,47 @@ impl FileExt for File {
         Ok(File::from_inner(self.as_inner().reopen()?))
     }
 }
+
+/// Returns whether `err` was returned because an operation, such as
+/// [`fs::rename`], would have had to move a file from one device to another.
+///
+/// Such errors are of kind [`ErrorKind::Other`]; a program can check for them
+/// to fall back to copying the file.
+///
+/// [`fs::rename`]: ../../../../std/fs/fn.rename.html
+/// [`ErrorKind::Other`]: ../../../../std/io/enum.ErrorKind.html#variant.Other
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(switch_ext)]
+/// use std::fs;
+/// use std::os::switch::fs::is_cross_device;
+///
+/// if let Err(e) = fs::rename("sdmc:/save.dat", "save:/save.dat") {
+///     if is_cross_device(&e) {
+///         fs::copy("sdmc:/save.dat", "save:/save.dat").unwrap();
+///         fs::remove_file("sdmc:/save.dat").unwrap();
+///     }
+/// }
+/// ```
+#[unstable(feature = "switch_ext", issue = "0")]
+pub fn is_cross_device(err: &io::Error) -> bool {
+    path::is_cross_device_err(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use fs;
+    use io::{self, ErrorKind};
+    use super::is_cross_device;
+
+    #[test]
+    fn rename_across_devices() {
+        let err = fs::rename("sdmc:/a", "romfs:/a").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert!(is_cross_device(&err));
+
+        assert!(!is_cross_device(&io::Error::new(ErrorKind::Other, "other")));
+    }
+}
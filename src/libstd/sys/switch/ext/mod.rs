@@ -3,6 +3,7 @@
 #![stable(feature = "rust1", since = "1.0.0")]
 #![doc(cfg(target_os = "switch"))]
 
+pub mod alloc;
 pub mod ffi;
 pub mod fs;
 
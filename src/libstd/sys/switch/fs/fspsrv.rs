@@ -1,96 +1,79 @@
+//! `FilesystemOps` on top of an fsp-srv filesystem.
+
 use io::{self, ErrorKind};
 use super::{FilesystemOps, FileOps, OpenOptions, FileAttr, SeekFrom, FilePermissions, ReadDir, FileType,DirEntry, ReadDirOps};
-use path::{Path, PathBuf, Component};
-use ffi::OsStr;
-use megaton_hammer::ipcdefs::nn::fssrv::sf::{IFile, IDirectory, IFileSystem, IDirectoryEntry, DirectoryEntryType};
-use megaton_hammer::kernel::Object;
+use super::{path, services, unsupported};
+use path::{Path, PathBuf};
 use sync::atomic::{AtomicU64, Ordering};
 use sync::Arc;
-use sys::switch::unsupported;
-use sys::ext::ffi::OsStrExt;
-use sys_common::AsInner;
-use core::slice;
-use core::fmt::Debug;
 
-pub struct FspSrvFs<T>(IFileSystem<T>);
+pub struct FspSrvFs(Arc<services::FileSystem>);
 
 #[derive(Debug)]
-pub struct FspSrvFile<T: Debug> {
-    internal: Arc<IFile<T>>,
+pub struct FspSrvFile {
+    internal: Arc<services::File>,
     offset: AtomicU64
 }
 
-impl<T> FspSrvFs<T> {
-    pub fn new(fs: IFileSystem<T>) -> FspSrvFs<T> {
+impl FspSrvFs {
+    pub fn new(fs: Arc<services::FileSystem>) -> FspSrvFs {
         FspSrvFs(fs)
     }
 }
 
 #[derive(Debug)]
-pub struct FspReadDir<T> {
-    internal: IDirectory<T>,
+pub struct FspReadDir {
+    internal: Box<services::Directory>,
     parent: PathBuf
 }
 
-impl<T: Object + Debug> ReadDirOps for FspReadDir<T> {}
-impl<T: Object> Iterator for FspReadDir<T> {
+impl ReadDirOps for FspReadDir {}
+impl Iterator for FspReadDir {
     type Item = io::Result<DirEntry>;
 
     fn next(&mut self) -> Option<io::Result<DirEntry>> {
-        let mut entry: [IDirectoryEntry; 1] = [IDirectoryEntry {
-            path: [0; 0x300],
-            unk1: 0,
-            directory_entry_type: DirectoryEntryType::File,
-            filesize: 0,
-        }];
-        match self.internal.read(&mut entry) {
-            Ok(0) => return None,
-            Err(err) => return Some(Err(err.into())),
-            Ok(n) => ()
-        }
-        let size = entry[0].path.iter().position(|c| *c == b'\0').unwrap_or(0x300);
-        let file_name = OsStr::from_bytes(&entry[0].path[..size]);
+        let entry = match self.internal.next_entry() {
+            Ok(Some(entry)) => entry,
+            Ok(None) => return None,
+            Err(err) => return Some(Err(err)),
+        };
         Some(Ok(DirEntry {
-            path: self.parent.join(file_name),
-            file_name: file_name.into(),
+            path: self.parent.join(&entry.name),
             metadata: FileAttr {
-                size: entry[0].filesize,
+                size: entry.size,
                 perm: FilePermissions,
-                file_type: entry[0].directory_entry_type.into()
+                file_type: entry.entry_type.into()
             },
+            file_name: entry.name,
         }))
     }
 }
 
-impl From<DirectoryEntryType> for FileType {
-    fn from(ty: DirectoryEntryType) -> FileType {
+impl From<services::EntryType> for FileType {
+    fn from(ty: services::EntryType) -> FileType {
         match ty {
-            DirectoryEntryType::File => FileType::File,
-            DirectoryEntryType::Directory => FileType::Directory
+            services::EntryType::File => FileType::File,
+            services::EntryType::Directory => FileType::Directory
         }
     }
 }
 
-impl<T: Object + Debug + 'static> FilesystemOps for FspSrvFs<T> {
+impl FilesystemOps for FspSrvFs {
     fn open(&self, path: &Path, opts: &OpenOptions) -> io::Result<Box<FileOps>> {
-        let mut arr = [0u8; 0x301];
-        let path_as_bytes = path.as_os_str().as_bytes();
-        (&mut arr[..path_as_bytes.len()]).copy_from_slice(path_as_bytes);
         let mut mode = 0;
         if opts.read {
-            mode |= 1;
+            mode |= services::OPEN_READ;
         }
-        if opts.write {
-            mode |= 1 << 1 | 1 << 2;
+        if opts.write || opts.append {
+            mode |= services::OPEN_WRITE | services::OPEN_APPEND;
         }
         if opts.create || opts.create_new {
-            let err = self.0.create_file(0, 0, &arr);
-            match (opts.create_new, err) {
-                (false, Err(err)) if io::Error::from(err).kind() == ErrorKind::AlreadyExists => (),
+            match (opts.create_new, self.0.create_file(path, 0)) {
+                (false, Err(ref err)) if err.kind() == ErrorKind::AlreadyExists => (),
                 (_, err) => err?
             }
         }
-        let file = self.0.open_file(mode, &arr)?;
+        let file = self.0.open_file(path, mode)?;
         if opts.truncate {
             file.set_size(0)?;
         }
@@ -100,69 +83,42 @@ impl<T: Object + Debug + 'static> FilesystemOps for FspSrvFs<T> {
             0
         };
         Ok(Box::new(FspSrvFile {
-            internal: Arc::new(file),
+            internal: Arc::from(file),
             offset: AtomicU64::new(offset)
         }))
     }
     fn readdir(&self, p: &Path) -> io::Result<ReadDir> {
-        let mut arr = [0u8; 0x301];
-        let path_as_bytes = p.as_os_str().as_bytes();
-        (&mut arr[..path_as_bytes.len()]).copy_from_slice(path_as_bytes);
         Ok(ReadDir(Box::new(FspReadDir {
-            internal: self.0.open_directory(3, &arr)?,
+            internal: self.0.open_directory(p)?,
             parent: p.into()
         })))
     }
     fn unlink(&self, p: &Path) -> io::Result<()> {
-        let mut arr = [0u8; 0x301];
-        let path_as_bytes = p.as_os_str().as_bytes();
-        (&mut arr[..path_as_bytes.len()]).copy_from_slice(path_as_bytes);
-        self.0.delete_file(&arr)?;
-        Ok(())
+        self.0.delete_file(p)
     }
     fn rename(&self, old: &Path, new: &Path) -> io::Result<()> {
-        let mut oldarr = [0u8; 0x301];
-        let path_as_bytes = old.as_os_str().as_bytes();
-        (&mut oldarr[..path_as_bytes.len()]).copy_from_slice(path_as_bytes);
-
-        let mut newarr = [0u8; 0x301];
-        let path_as_bytes = new.as_os_str().as_bytes();
-        (&mut newarr[..path_as_bytes.len()]).copy_from_slice(path_as_bytes);
-        self.0.rename_file(&oldarr, &newarr)?;
-        Ok(())
+        self.0.rename_file(old, new)
     }
-    fn set_perm(&self, p: &Path, perm: FilePermissions) -> io::Result<()> {
+    fn set_perm(&self, _p: &Path, _perm: FilePermissions) -> io::Result<()> {
         unsupported()
     }
     fn rmdir(&self, p: &Path) -> io::Result<()> {
-        let mut arr = [0u8; 0x301];
-        let path_as_bytes = p.as_os_str().as_bytes();
-        (&mut arr[..path_as_bytes.len()]).copy_from_slice(path_as_bytes);
-        self.0.delete_directory(&arr)?;
-        Ok(())
+        self.0.delete_directory(p)
     }
     fn remove_dir_all(&self, p: &Path) -> io::Result<()> {
-        let mut arr = [0u8; 0x301];
-        let path_as_bytes = p.as_os_str().as_bytes();
-        (&mut arr[..path_as_bytes.len()]).copy_from_slice(path_as_bytes);
-        self.0.delete_directory_recursively(&arr)?;
-        Ok(())
+        self.0.delete_directory_recursively(p)
     }
-    fn readlink(&self, p: &Path) -> io::Result<PathBuf> {
+    fn readlink(&self, _p: &Path) -> io::Result<PathBuf> {
         unsupported()
     }
     fn stat(&self, p: &Path) -> io::Result<FileAttr> {
-        let mut arr = [0u8; 0x301];
-        let path_as_bytes = p.as_os_str().as_bytes();
-        (&mut arr[..path_as_bytes.len()]).copy_from_slice(path_as_bytes);
-
-        let entry_type = self.0.get_entry_type(&arr)?;
+        let entry_type = self.0.get_entry_type(p)?;
 
         let size = match entry_type {
-            DirectoryEntryType::File => {
-                self.0.open_file(0, &arr)?.get_size()?
+            services::EntryType::File => {
+                self.0.open_file(p, 0)?.get_size()?
             },
-            DirectoryEntryType::Directory => 0
+            services::EntryType::Directory => 0
         };
         Ok(FileAttr {
             size,
@@ -170,25 +126,15 @@ impl<T: Object + Debug + 'static> FilesystemOps for FspSrvFs<T> {
             file_type: entry_type.into()
         })
     }
-    fn lstat(&self, p: &Path) -> io::Result<FileAttr> {
+    fn lstat(&self, _p: &Path) -> io::Result<FileAttr> {
         unsupported()
     }
     fn canonicalize(&self, p: &Path) -> io::Result<PathBuf> {
-        let mut fullpath = PathBuf::from("/");
-        for component in p.components() {
-            match component {
-                Component::Prefix(p) => panic!("Shouldn't obtain a prefix in inner fs impl"),
-                Component::RootDir => fullpath.push("/"),
-                Component::CurDir => (),
-                Component::ParentDir => { fullpath.pop(); },
-                Component::Normal(p) => fullpath.push(p)
-            }
-        }
-        Ok(p.into())
+        Ok(path::normalize(p))
     }
 }
 
-impl<T: 'static + Object + Debug> FileOps for FspSrvFile<T> {
+impl FileOps for FspSrvFile {
     fn file_attr(&self) -> io::Result<FileAttr> {
         Ok(FileAttr {
             size: self.internal.get_size()?,
@@ -208,15 +154,15 @@ impl<T: 'static + Object + Debug> FileOps for FspSrvFile<T> {
     }
     fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
         // TODO: Maybe I should lock the file on read/write?
-        let read_size = self.internal.read(0, self.offset.load(Ordering::SeqCst), buf.len() as u64, buf)?;
-        self.offset.fetch_add(read_size, Ordering::SeqCst);
-        Ok(read_size as usize)
+        let read_size = self.internal.read(self.offset.load(Ordering::SeqCst), buf)?;
+        self.offset.fetch_add(read_size as u64, Ordering::SeqCst);
+        Ok(read_size)
     }
     fn write(&self, buf: &[u8]) -> io::Result<usize> {
         // TODO: Maybe I should lock the file on read/write?
         // TODO: In append mode, should I ignore offset and just write from
         // the end?
-        self.internal.write(0, self.offset.load(Ordering::SeqCst), buf.len() as u64, buf)?;
+        self.internal.write(self.offset.load(Ordering::SeqCst), buf)?;
         self.offset.fetch_add(buf.len() as u64, Ordering::SeqCst);
         Ok(buf.len())
     }
@@ -268,3 +214,112 @@ impl<T: 'static + Object + Debug> FileOps for FspSrvFile<T> {
         unsupported()
     }
 }
+
+#[cfg(all(test, not(target_os = "switch")))]
+mod tests {
+    use super::*;
+    use io::{ErrorKind, SeekFrom};
+    use path::Path;
+    use sync::Arc;
+    use super::super::services::FileSystem;
+    use super::super::services::host::HostFileSystem;
+
+    fn fs() -> (Arc<HostFileSystem>, FspSrvFs) {
+        let host = Arc::new(HostFileSystem::new());
+        (host.clone(), FspSrvFs::new(host))
+    }
+
+    fn opts(f: &Fn(&mut OpenOptions)) -> OpenOptions {
+        let mut opts = OpenOptions::new();
+        f(&mut opts);
+        opts
+    }
+
+    #[test]
+    fn create_write_read() {
+        let (_host, fs) = fs();
+        let path = Path::new("/hello.txt");
+        let file = fs.open(path, &opts(&|o| { o.write(true); o.create(true); })).unwrap();
+        assert_eq!(file.write(b"hello world").unwrap(), 11);
+
+        let file = fs.open(path, &opts(&|o| o.read(true))).unwrap();
+        let mut buf = [0; 5];
+        assert_eq!(file.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b"hello");
+        assert_eq!(file.seek(SeekFrom::Current(1)).unwrap(), 6);
+        assert_eq!(file.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b"world");
+        assert_eq!(file.read(&mut buf).unwrap(), 0);
+        assert_eq!(file.file_attr().unwrap().size(), 11);
+    }
+
+    #[test]
+    fn create_new_existing() {
+        let (_host, fs) = fs();
+        let path = Path::new("/a");
+        fs.open(path, &opts(&|o| { o.write(true); o.create_new(true); })).unwrap();
+        let err = fs.open(path, &opts(&|o| { o.write(true); o.create_new(true); })).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        fs.open(path, &opts(&|o| { o.write(true); o.create(true); })).unwrap();
+    }
+
+    #[test]
+    fn append_and_truncate() {
+        let (_host, fs) = fs();
+        let path = Path::new("/log");
+        let file = fs.open(path, &opts(&|o| { o.write(true); o.create(true); })).unwrap();
+        file.write(b"abc").unwrap();
+
+        let file = fs.open(path, &opts(&|o| o.append(true))).unwrap();
+        file.write(b"def").unwrap();
+        assert_eq!(fs.stat(path).unwrap().size(), 6);
+
+        fs.open(path, &opts(&|o| { o.write(true); o.truncate(true); })).unwrap();
+        assert_eq!(fs.stat(path).unwrap().size(), 0);
+    }
+
+    #[test]
+    fn directories() {
+        let (host, fs) = fs();
+        host.create_directory(Path::new("/dir")).unwrap();
+        host.create_file(Path::new("/dir/a"), 3).unwrap();
+        host.create_directory(Path::new("/dir/sub")).unwrap();
+
+        let mut entries = fs.readdir(Path::new("/dir")).unwrap()
+            .map(|e| e.unwrap())
+            .map(|e| (e.path(), e.file_type().unwrap().is_dir(), e.metadata().unwrap().size()))
+            .collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(entries, vec![
+            (PathBuf::from("/dir/a"), false, 3),
+            (PathBuf::from("/dir/sub"), true, 0),
+        ]);
+
+        assert!(fs.stat(Path::new("/dir")).unwrap().file_type().is_dir());
+        assert_eq!(fs.rmdir(Path::new("/dir")).unwrap_err().kind(), ErrorKind::Other);
+        fs.remove_dir_all(Path::new("/dir")).unwrap();
+        assert_eq!(fs.stat(Path::new("/dir")).unwrap_err().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn rename_and_unlink() {
+        let (host, fs) = fs();
+        host.create_file(Path::new("/old"), 0).unwrap();
+        fs.rename(Path::new("/old"), Path::new("/new")).unwrap();
+        assert_eq!(fs.stat(Path::new("/old")).unwrap_err().kind(), ErrorKind::NotFound);
+        fs.unlink(Path::new("/new")).unwrap();
+        assert_eq!(fs.stat(Path::new("/new")).unwrap_err().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn reopen_has_its_own_cursor() {
+        let (host, fs) = fs();
+        host.create_file(Path::new("/f"), 0).unwrap();
+        let file = fs.open(Path::new("/f"), &opts(&|o| { o.read(true); o.append(true); })).unwrap();
+        file.write(b"0123").unwrap();
+        let other = file.reopen().unwrap();
+        let mut buf = [0; 4];
+        assert_eq!(other.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"0123");
+    }
+}
@@ -12,14 +12,12 @@ use ffi::OsString;
 use fmt::{self, Debug};
 use hash::{Hash, Hasher};
 use io::{self, SeekFrom};
-use path::{Path, PathBuf, Component};
-use sys::time::SystemTime;
-use sys::{unsupported, Void};
-use sys::os::getcwd;
-//use sync::Mutex;
-use megaton_hammer::ipcdefs::nn::fssrv::sf::IFileSystemProxy;
-use megaton_hammer::kernel::Session;
-use megaton_hammer::error::Result as MTHResult;
+use path::{Path, PathBuf};
+use super::time::SystemTime;
+use super::{unsupported, Void};
+use super::os::getcwd;
+use super::path;
+use super::services;
 
 pub struct File(Box<FileOps>);
 
@@ -202,27 +200,15 @@ trait FileOps: Debug {
 }
 
 mod fspsrv;
+#[cfg(target_os = "switch")]
 mod romfs;
 
 use self::fspsrv::FspSrvFs;
 
-lazy_static! {
-    static ref SDMC: MTHResult<FspSrvFs<Session>> = {
-        let ifs = IFileSystemProxy::new(|init| init(0))?;
-        let sdcard = ifs.open_sd_card_file_system()?;
-        Ok(FspSrvFs::new(sdcard))
-    };
-}
-
-fn get_filesystem(path: &Path) -> io::Result<(&'static FilesystemOps, &Path)> {
-    assert!(path.is_absolute(), "CWD is not absolute ?");
-    let mut iter = path.components();
-    let prefix = match iter.next() {
-        Some(Component::Prefix(prefix)) => prefix.as_os_str(),
-        _ => panic!("If path is absolute, it should start with prefix")
-    };
-    if prefix == "sdmc:" {
-        Ok(((&*SDMC).as_ref().map_err(|v| *v)?, &iter.as_path()))
+fn get_filesystem(path: &Path) -> io::Result<(Box<FilesystemOps>, PathBuf)> {
+    let (device, path) = path::split_device(path)?;
+    if device == "sdmc" {
+        Ok((Box::new(FspSrvFs::new(services::sdmc()?)), path))
     } else {
         unsupported()
     }
@@ -232,7 +218,7 @@ impl File {
     pub fn open(path: &Path, opts: &OpenOptions) -> io::Result<File> {
         let path = getcwd()?.join(path);
         let (fs, path) = get_filesystem(&path)?;
-        Ok(File(fs.open(path, opts)?))
+        Ok(File(fs.open(&path, opts)?))
     }
 
     pub fn reopen(&self) -> io::Result<File> {
@@ -299,61 +285,49 @@ impl fmt::Debug for File {
 pub fn readdir(p: &Path) -> io::Result<ReadDir> {
     let path = getcwd()?.join(p);
     let (fs, path) = get_filesystem(&path)?;
-    fs.readdir(path)
+    fs.readdir(&path)
 }
 
 pub fn unlink(p: &Path) -> io::Result<()> {
     let path = getcwd()?.join(p);
     let (fs, path) = get_filesystem(&path)?;
-    fs.unlink(path)
+    fs.unlink(&path)
 }
 
 pub fn rename(old: &Path, new: &Path) -> io::Result<()> {
-    // Guarantee old and new have the same prefix
     let cwd = getcwd()?;
     let old = cwd.join(old);
     let new = cwd.join(new);
-    let prefix_old = match old.components().next() {
-        Some(Component::Prefix(prefix)) => prefix.as_os_str(),
-        _ => panic!("If path is absolute, it should start with prefix")
-    };
-    let mut newpath_iter = new.components();
-    let prefix_new = match newpath_iter.next() {
-        Some(Component::Prefix(prefix)) => prefix.as_os_str(),
-        _ => panic!("If path is absolute, it should start with prefix")
-    };
-
-    if prefix_old != prefix_new {
-        // TODO: MTH error
-        return Err(io::Error::from(io::ErrorKind::Other));
+    if path::device(&old) != path::device(&new) {
+        return Err(path::cross_device_err());
     }
     let (fs, oldpath) = get_filesystem(&old)?;
-    let newpath = newpath_iter.as_path();
-    fs.rename(&old, newpath)
+    let (_, newpath) = get_filesystem(&new)?;
+    fs.rename(&oldpath, &newpath)
 }
 
 pub fn set_perm(p: &Path, perm: FilePermissions) -> io::Result<()> {
     let path = getcwd()?.join(p);
     let (fs, path) = get_filesystem(&path)?;
-    fs.set_perm(path, perm)
+    fs.set_perm(&path, perm)
 }
 
 pub fn rmdir(p: &Path) -> io::Result<()> {
     let path = getcwd()?.join(p);
     let (fs, path) = get_filesystem(&path)?;
-    fs.rmdir(path)
+    fs.rmdir(&path)
 }
 
 pub fn remove_dir_all(p: &Path) -> io::Result<()> {
     let path = getcwd()?.join(p);
     let (fs, path) = get_filesystem(&path)?;
-    fs.remove_dir_all(path)
+    fs.remove_dir_all(&path)
 }
 
 pub fn readlink(p: &Path) -> io::Result<PathBuf> {
     let path = getcwd()?.join(p);
     let (fs, path) = get_filesystem(&path)?;
-    fs.readlink(path)
+    fs.readlink(&path)
 }
 
 pub fn symlink(src: &Path, dst: &Path) -> io::Result<()> {
@@ -367,26 +341,21 @@ pub fn link(_src: &Path, _dst: &Path) -> io::Result<()> {
 pub fn stat(p: &Path) -> io::Result<FileAttr> {
     let path = getcwd()?.join(p);
     let (fs, path) = get_filesystem(&path)?;
-    fs.stat(path)
+    fs.stat(&path)
 }
 
 pub fn lstat(p: &Path) -> io::Result<FileAttr> {
     let path = getcwd()?.join(p);
     let (fs, path) = get_filesystem(&path)?;
-    fs.lstat(path)
+    fs.lstat(&path)
 }
 
 pub fn canonicalize(p: &Path) -> io::Result<PathBuf> {
     let path = getcwd()?.join(p);
     let (fs, fs_path) = get_filesystem(&path)?;
-    let canonicalized = fs.canonicalize(fs_path)?;
-
-    let mut iter = path.components();
-    let prefix = match iter.next() {
-        Some(Component::Prefix(prefix)) => prefix.as_os_str(),
-        _ => panic!("If path is absolute, it should start with prefix")
-    };
-    let mut ret = PathBuf::from(prefix);
+    let canonicalized = fs.canonicalize(&fs_path)?;
+
+    let mut ret = PathBuf::from(path.components().next().unwrap().as_os_str());
     ret.push(canonicalized);
     Ok(ret)
 }
@@ -406,3 +375,36 @@ pub fn copy(from: &Path, to: &Path) -> io::Result<u64> {
     writer.set_permissions(perm)?;
     Ok(ret)
 }
+
+#[cfg(all(test, not(target_os = "switch")))]
+mod tests {
+    use super::*;
+    use path::Path;
+
+    // These go through `services::sdmc()`, which the whole test process
+    // shares, so each test only uses names of its own. The paths are relative
+    // to the current directory, `sdmc:/`: the host `std::path` doesn't parse
+    // device prefixes, so it can't join absolute Switch paths.
+
+    #[test]
+    fn sdmc_round_trip() {
+        let mut opts = OpenOptions::new();
+        opts.write(true);
+        opts.create_new(true);
+        let file = File::open(Path::new("sdmc-round-trip"), &opts).unwrap();
+        assert_eq!(file.write(b"hello").unwrap(), 5);
+        drop(file);
+
+        rename(Path::new("sdmc-round-trip"), Path::new("sdmc-round-trip-2")).unwrap();
+        let mut opts = OpenOptions::new();
+        opts.read(true);
+        let file = File::open(Path::new("sdmc-round-trip-2"), &opts).unwrap();
+        let mut buf = [0; 8];
+        assert_eq!(file.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"hello");
+        drop(file);
+
+        unlink(Path::new("sdmc-round-trip-2")).unwrap();
+        assert!(File::open(Path::new("sdmc-round-trip-2"), &opts).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A host build of the Switch sys layer.
+//!
+//! This pulls in the modules of `sys::switch` that only reach Horizon through
+//! `services`, with the host stand-ins from `services/host.rs` behind them,
+//! and provides the few sibling items they expect from `sys::switch`. The
+//! modules' own tests then run in the normal libstd test suite.
+
+#![allow(dead_code, missing_docs, bad_style)]
+
+use io;
+
+#[path = "alloc.rs"]
+pub mod alloc;
+#[path = "fs/mod.rs"]
+pub mod fs;
+#[path = "net/mod.rs"]
+pub mod net;
+#[path = "path.rs"]
+pub mod path;
+#[path = "services/mod.rs"]
+pub mod services;
+
+pub use sys::time;
+
+pub mod os {
+    use io;
+    use path::PathBuf;
+
+    pub fn getcwd() -> io::Result<PathBuf> {
+        Ok(PathBuf::from("sdmc:/"))
+    }
+}
+
+/// Stands in for the `SetHeapSize` syscall: there is no heap to grow on the
+/// host, so `alloc` can only be tested on memory handed to it directly.
+pub unsafe fn set_heap_size(_addr: &mut *mut u8, _size: u64) -> u32 {
+    1
+}
+
+pub fn unsupported<T>() -> io::Result<T> {
+    Err(unsupported_err())
+}
+
+pub fn unsupported_err() -> io::Error {
+    io::Error::new(io::ErrorKind::Other,
+                   "operation not supported on switch yet")
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub enum Void {}
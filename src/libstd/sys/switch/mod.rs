@@ -24,6 +24,7 @@ extern crate runwind;
 use io::{self, ErrorKind};
 use megaton_hammer::error::{Error, Module};
 
+pub mod alloc;
 pub mod args;
 #[cfg(feature = "backtrace")]
 pub mod backtrace;
@@ -42,6 +43,7 @@ pub mod path;
 pub mod pipe;
 pub mod process;
 pub mod rwlock;
+pub mod services;
 pub mod stack_overflow;
 pub mod stdio;
 pub mod thread;
@@ -0,0 +1,401 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use fmt;
+use io;
+use net::{SocketAddr, Shutdown, Ipv4Addr, Ipv6Addr};
+use time::Duration;
+use sync::Arc;
+use super::{unsupported, Void};
+use super::services::{self, Bsd};
+
+#[cfg(target_os = "switch")]
+mod resolver;
+#[cfg(target_os = "switch")]
+pub use self::resolver::{lookup_host, netc, sockaddr_to_addr, LookupHost};
+
+pub struct TcpStream(Arc<Bsd>, u32);
+
+impl TcpStream {
+    pub fn connect(addr: &SocketAddr) -> io::Result<TcpStream> {
+        if let &SocketAddr::V6(_) = addr {
+            return unsupported()
+        }
+
+        let bsd = services::bsd()?;
+        let socket = TcpStream(bsd.clone(), bsd.socket(services::AF_INET, services::SOCK_STREAM, 0)?);
+        bsd.connect(socket.1, addr)?;
+        Ok(socket)
+    }
+
+    pub fn connect_timeout(_: &SocketAddr, _: Duration) -> io::Result<TcpStream> {
+        // TODO: Use set_nonblocking, which itself does some magic...
+        unsupported()
+    }
+
+    pub fn set_read_timeout(&self, _: Option<Duration>) -> io::Result<()> {
+        // TODO: setsockopt magic stuff
+        unsupported()
+    }
+
+    pub fn set_write_timeout(&self, _: Option<Duration>) -> io::Result<()> {
+        // TODO: setsockopt magic stuff
+        unsupported()
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        // TODO: setsockopt magic stuff
+        unsupported()
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        // TODO: setsockopt magic stuff
+        unsupported()
+    }
+
+    fn recv_with_flags(&self, buf: &mut [u8], flags: u32) -> io::Result<usize> {
+        self.0.recv(self.1, flags, buf)
+    }
+
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv_with_flags(buf, services::MSG_PEEK)
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv_with_flags(buf, 0)
+    }
+
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        // TODO: let len = cmp::min(buf.len(), <wrlen_t>::max_value() as usize) as wrlen_t;
+        // TODO: The standard implementation uses MSG_NOSIGNAL, which avoids
+        // generating SIGPIPE on EOF. The switch has no notion of signals
+        // however, so this seems to be a NOOP.
+        self.0.send(self.1, 0, buf)
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.0.peer_name(self.1)
+    }
+
+    pub fn socket_addr(&self) -> io::Result<SocketAddr> {
+        self.0.sock_name(self.1)
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(self.1, how)
+    }
+
+    pub fn duplicate(&self) -> io::Result<TcpStream> {
+        Ok(TcpStream(self.0.clone(), self.0.duplicate(self.1)?))
+    }
+
+    pub fn set_nodelay(&self, _: bool) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn nodelay(&self) -> io::Result<bool> {
+        unsupported()
+    }
+
+    pub fn set_ttl(&self, _: u32) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        unsupported()
+    }
+
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        unsupported()
+    }
+
+    pub fn set_nonblocking(&self, _: bool) -> io::Result<()> {
+        unsupported()
+    }
+}
+
+impl Drop for TcpStream {
+    fn drop(&mut self) {
+        let _ = self.0.close(self.1);
+    }
+}
+
+impl fmt::Debug for TcpStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut res = f.debug_struct("TcpStream");
+
+        if let Ok(addr) = self.socket_addr() {
+            res.field("addr", &addr);
+        }
+
+        if let Ok(peer) = self.peer_addr() {
+            res.field("peer", &peer);
+        }
+
+        res.field("fd", &self.1)
+            .finish()
+    }
+}
+
+pub struct TcpListener(Arc<Bsd>, u32);
+
+impl TcpListener {
+    pub fn bind(addr: &SocketAddr) -> io::Result<TcpListener> {
+        if let &SocketAddr::V6(_) = addr {
+            return unsupported()
+        }
+
+        let bsd = services::bsd()?;
+        let socket = TcpListener(bsd.clone(), bsd.socket(services::AF_INET, services::SOCK_STREAM, 0)?);
+        bsd.bind(socket.1, addr)?;
+        bsd.listen(socket.1, 128)?;
+        Ok(socket)
+    }
+
+    pub fn socket_addr(&self) -> io::Result<SocketAddr> {
+        self.0.sock_name(self.1)
+    }
+
+    pub fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let (socket, addr) = self.0.accept(self.1)?;
+        Ok((TcpStream(self.0.clone(), socket), addr))
+    }
+
+    pub fn duplicate(&self) -> io::Result<TcpListener> {
+        Ok(TcpListener(self.0.clone(), self.0.duplicate(self.1)?))
+    }
+
+    pub fn set_ttl(&self, _: u32) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        unsupported()
+    }
+
+    pub fn set_only_v6(&self, _: bool) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn only_v6(&self) -> io::Result<bool> {
+        unsupported()
+    }
+
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        unsupported()
+    }
+
+    pub fn set_nonblocking(&self, _: bool) -> io::Result<()> {
+        unsupported()
+    }
+}
+
+impl Drop for TcpListener {
+    fn drop(&mut self) {
+        let _ = self.0.close(self.1);
+    }
+}
+
+impl fmt::Debug for TcpListener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut res = f.debug_struct("TcpListener");
+
+        if let Ok(addr) = self.socket_addr() {
+            res.field("addr", &addr);
+        }
+
+        res.field("fd", &self.1)
+            .finish()
+    }
+}
+
+pub struct UdpSocket(Void);
+
+impl UdpSocket {
+    pub fn bind(_: &SocketAddr) -> io::Result<UdpSocket> {
+        unsupported()
+    }
+
+    pub fn socket_addr(&self) -> io::Result<SocketAddr> {
+        match self.0 {}
+    }
+
+    pub fn recv_from(&self, _: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self.0 {}
+    }
+
+    pub fn peek_from(&self, _: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self.0 {}
+    }
+
+    pub fn send_to(&self, _: &[u8], _: &SocketAddr) -> io::Result<usize> {
+        match self.0 {}
+    }
+
+    pub fn duplicate(&self) -> io::Result<UdpSocket> {
+        match self.0 {}
+    }
+
+    pub fn set_read_timeout(&self, _: Option<Duration>) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn set_write_timeout(&self, _: Option<Duration>) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        match self.0 {}
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        match self.0 {}
+    }
+
+    pub fn set_broadcast(&self, _: bool) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn broadcast(&self) -> io::Result<bool> {
+        match self.0 {}
+    }
+
+    pub fn set_multicast_loop_v4(&self, _: bool) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn multicast_loop_v4(&self) -> io::Result<bool> {
+        match self.0 {}
+    }
+
+    pub fn set_multicast_ttl_v4(&self, _: u32) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn multicast_ttl_v4(&self) -> io::Result<u32> {
+        match self.0 {}
+    }
+
+    pub fn set_multicast_loop_v6(&self, _: bool) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn multicast_loop_v6(&self) -> io::Result<bool> {
+        match self.0 {}
+    }
+
+    pub fn join_multicast_v4(&self, _: &Ipv4Addr, _: &Ipv4Addr)
+                         -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn join_multicast_v6(&self, _: &Ipv6Addr, _: u32)
+                         -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn leave_multicast_v4(&self, _: &Ipv4Addr, _: &Ipv4Addr)
+                          -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn leave_multicast_v6(&self, _: &Ipv6Addr, _: u32)
+                          -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn set_ttl(&self, _: u32) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        match self.0 {}
+    }
+
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        match self.0 {}
+    }
+
+    pub fn set_nonblocking(&self, _: bool) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn recv(&self, _: &mut [u8]) -> io::Result<usize> {
+        match self.0 {}
+    }
+
+    pub fn peek(&self, _: &mut [u8]) -> io::Result<usize> {
+        match self.0 {}
+    }
+
+    pub fn send(&self, _: &[u8]) -> io::Result<usize> {
+        match self.0 {}
+    }
+
+    pub fn connect(&self, _: &SocketAddr) -> io::Result<()> {
+        match self.0 {}
+    }
+}
+
+impl fmt::Debug for UdpSocket {
+    fn fmt(&self, _f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {}
+    }
+}
+
+
+#[cfg(all(test, not(target_os = "switch")))]
+mod tests {
+    use super::*;
+    use net::Shutdown;
+
+    fn pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.socket_addr().unwrap();
+        let client = TcpStream::connect(&addr).unwrap();
+        let (server, peer) = listener.accept().unwrap();
+        assert_eq!(peer, client.socket_addr().unwrap());
+        assert_eq!(client.peer_addr().unwrap(), addr);
+        (client, server)
+    }
+
+    #[test]
+    fn read_write() {
+        let (client, server) = pair();
+        assert_eq!(client.write(b"ping").unwrap(), 4);
+
+        let mut buf = [0; 4];
+        assert_eq!(server.peek(&mut buf).unwrap(), 4);
+        assert_eq!(server.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[test]
+    fn shutdown_write() {
+        let (client, server) = pair();
+        client.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(server.read(&mut [0; 4]).unwrap(), 0);
+    }
+
+    #[test]
+    fn duplicate() {
+        let (client, server) = pair();
+        let dup = client.duplicate().unwrap();
+        drop(client);
+        dup.write(b"x").unwrap();
+        let mut buf = [0; 1];
+        assert_eq!(server.read(&mut buf).unwrap(), 1);
+    }
+
+    #[test]
+    fn ipv6_unsupported() {
+        assert!(TcpListener::bind(&"[::1]:0".parse().unwrap()).is_err());
+    }
+}
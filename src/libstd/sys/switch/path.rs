@@ -8,8 +8,29 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+//! Path handling for the Switch.
+//!
+//! Every filesystem on the Switch is mounted under a device name, and paths
+//! look like `sdmc:/switch/app.nro` or `romfs:/data.bin`. The `device:` part is
+//! parsed as a `Prefix::DeviceNS` holding the device name, so that paths on
+//! different devices never compare equal. A device prefix always refers to
+//! the root of that device: `sdmc:foo` and `sdmc:/foo` name the same file, and
+//! both are absolute. A path with a root but no prefix, like `/foo`, is
+//! relative to the device of the current directory.
+
+use error;
 use ffi::OsStr;
-use path::{PrefixComponent, Prefix};
+use fmt;
+use io;
+use mem;
+use path::{Component, Path, PathBuf, PrefixComponent, Prefix};
+
+fn os_str_as_u8_slice(s: &OsStr) -> &[u8] {
+    unsafe { mem::transmute(s) }
+}
+unsafe fn u8_slice_as_os_str(s: &[u8]) -> &OsStr {
+    mem::transmute(s)
+}
 
 #[inline]
 pub fn is_sep_byte(b: u8) -> bool {
@@ -21,15 +42,223 @@ pub fn is_verbatim_sep(b: u8) -> bool {
     b == b'/'
 }
 
+#[inline]
+fn is_device_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'-'
+}
+
+/// The length of the device name `bytes` start with, if they start with a
+/// `device:` prefix.
+fn device_name_len(bytes: &[u8]) -> Option<usize> {
+    let len = bytes.iter().take_while(|&&b| is_device_byte(b)).count();
+    if len == 0 || bytes.get(len) != Some(&b':') {
+        return None;
+    }
+    Some(len)
+}
+
 pub fn parse_prefix(path: &OsStr) -> Option<PrefixComponent> {
-    if let Some(path_str) = path.to_str() {
-        path_str.split('/').next()
-            .and_then(|s| s.bytes().position(|v| v == b':'))
-            .map(|idx| PrefixComponent::from_os_str_kind(OsStr::new(&path_str[..idx + 1]), Prefix::Disk(0)))
-    } else {
-        None
+    let bytes = os_str_as_u8_slice(path);
+    let len = device_name_len(bytes)?;
+    unsafe {
+        // Both slices end on ASCII bytes of an existing `OsStr`, so they are
+        // valid `OsStr`s themselves.
+        let raw = u8_slice_as_os_str(&bytes[..len + 1]);
+        let name = u8_slice_as_os_str(&bytes[..len]);
+        Some(PrefixComponent::from_os_str_kind(raw, Prefix::DeviceNS(name)))
+    }
+}
+
+/// Splits `path` into its device name, if it has a device prefix, and the
+/// rest of it.
+///
+/// This parses the prefix itself rather than going through
+/// `Path::components`, so that it behaves the same in host builds of this
+/// module, where `std::path` knows nothing about device prefixes.
+fn split_prefix(path: &Path) -> (Option<&OsStr>, &Path) {
+    let bytes = os_str_as_u8_slice(path.as_os_str());
+    match device_name_len(bytes) {
+        // As in `parse_prefix`, both slices are valid `OsStr`s.
+        Some(len) => unsafe {
+            (Some(u8_slice_as_os_str(&bytes[..len])),
+             Path::new(u8_slice_as_os_str(&bytes[len + 1..])))
+        },
+        None => (None, path),
+    }
+}
+
+/// Returns the device name of `path`, without the trailing colon.
+pub fn device(path: &Path) -> Option<&OsStr> {
+    split_prefix(path).0
+}
+
+/// Lexically normalizes `path` into an absolute path on its device, with the
+/// device prefix removed: `sdmc:/a/./b/../c` becomes `/a/c`.
+///
+/// `..` never climbs above the device root, so `sdmc:/../a` is `/a`.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut ret = PathBuf::from("/");
+    for component in split_prefix(path).1.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => (),
+            Component::ParentDir => { ret.pop(); },
+            Component::Normal(p) => ret.push(p),
+        }
+    }
+    ret
+}
+
+/// Splits an absolute `path` into its device name and its normalized path on
+/// that device.
+pub fn split_device(path: &Path) -> io::Result<(&OsStr, PathBuf)> {
+    match device(path) {
+        Some(device) => Ok((device, normalize(path))),
+        None => Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                   "path does not start with a device prefix")),
     }
 }
 
+/// The payload of the errors returned by `cross_device_err`.
+#[derive(Debug)]
+struct CrossDevice;
+
+impl fmt::Display for CrossDevice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("cannot move a file across devices")
+    }
+}
+
+impl error::Error for CrossDevice {
+    fn description(&self) -> &str {
+        "cannot move a file across devices"
+    }
+}
+
+/// The error returned when an operation, such as a rename, would need to move
+/// a file from one device to another, the equivalent of `EXDEV`.
+///
+/// Its kind is `ErrorKind::Other`, unlike the `ErrorKind::InvalidInput` of a
+/// path without a device prefix; programs tell it apart from other errors of
+/// that kind with `std::os::switch::fs::is_cross_device`.
+pub fn cross_device_err() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, CrossDevice)
+}
+
+/// Whether `err` was created by `cross_device_err`.
+pub fn is_cross_device_err(err: &io::Error) -> bool {
+    err.get_ref().map_or(false, |e| e.is::<CrossDevice>())
+}
+
 pub const MAIN_SEP_STR: &'static str = "/";
 pub const MAIN_SEP: char = '/';
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+
+    use super::*;
+    use ffi::OsStr;
+    use io::ErrorKind;
+    use path::{Component, Path, PathBuf};
+
+    #[test]
+    fn parse_device_prefix() {
+        let prefix = parse_prefix(OsStr::new("sdmc:/switch/app.nro")).unwrap();
+        assert_eq!(prefix.as_os_str(), "sdmc:");
+        assert_eq!(prefix.kind(), Prefix::DeviceNS(OsStr::new("sdmc")));
+
+        let prefix = parse_prefix(OsStr::new("romfs:")).unwrap();
+        assert_eq!(prefix.as_os_str(), "romfs:");
+
+        assert!(parse_prefix(OsStr::new("/switch/app.nro")).is_none());
+        assert!(parse_prefix(OsStr::new("switch/a:b")).is_none());
+        assert!(parse_prefix(OsStr::new(":/foo")).is_none());
+        assert!(parse_prefix(OsStr::new("sdmc")).is_none());
+    }
+
+    #[test]
+    fn normalization_without_prefix() {
+        assert_eq!(normalize(Path::new("/a/./b/../c")), Path::new("/a/c"));
+        assert_eq!(normalize(Path::new("/../a")), Path::new("/a"));
+        assert_eq!(normalize(Path::new("a/b")), Path::new("/a/b"));
+    }
+
+    #[test]
+    fn cross_device_is_distinct() {
+        let cross_device = cross_device_err();
+        assert_eq!(cross_device.kind(), ErrorKind::Other);
+        assert!(is_cross_device_err(&cross_device));
+
+        let no_prefix = split_device(Path::new("/b")).unwrap_err();
+        assert_eq!(no_prefix.kind(), ErrorKind::InvalidInput);
+        assert!(!is_cross_device_err(&no_prefix));
+        assert!(!is_cross_device_err(&io::Error::new(ErrorKind::Other, "other")));
+    }
+
+    #[test]
+    fn devices_are_distinct() {
+        assert_eq!(device(Path::new("sdmc:/a")), Some(OsStr::new("sdmc")));
+        assert_eq!(device(Path::new("romfs:a")), Some(OsStr::new("romfs")));
+        assert_eq!(device(Path::new("/a")), None);
+        assert_eq!(device(Path::new("a/b:c")), None);
+
+        let (sdmc, sdmc_path) = split_device(Path::new("sdmc:/a")).unwrap();
+        let (romfs, romfs_path) = split_device(Path::new("romfs:/a")).unwrap();
+        assert_eq!(sdmc_path, romfs_path);
+        assert!(sdmc != romfs);
+    }
+
+    #[test]
+    fn prefix_is_root() {
+        let (device, path) = split_device(Path::new("sdmc:a")).unwrap();
+        assert_eq!(device, "sdmc");
+        assert_eq!(path, Path::new("/a"));
+        assert_eq!(normalize(Path::new("sdmc:a/b")), normalize(Path::new("sdmc:/a/b")));
+    }
+
+    #[test]
+    fn normalization() {
+        assert_eq!(normalize(Path::new("sdmc:/a/./b/../c")), Path::new("/a/c"));
+        assert_eq!(normalize(Path::new("sdmc:/../a")), Path::new("/a"));
+        assert_eq!(normalize(Path::new("sdmc:/a/b/../../..")), Path::new("/"));
+        assert_eq!(normalize(Path::new("sdmc:")), Path::new("/"));
+    }
+
+    #[test]
+    fn split() {
+        let (device, path) = split_device(Path::new("sdmc:/a/../b")).unwrap();
+        assert_eq!(device, "sdmc");
+        assert_eq!(path, Path::new("/b"));
+
+        let err = split_device(Path::new("/b")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    // This one checks how `std::path` itself handles the prefixes returned by
+    // `parse_prefix`, so it can only run when `std::path` is the Switch one.
+    #[test]
+    #[cfg(target_os = "switch")]
+    fn std_path() {
+        assert!(Path::new("sdmc:/a") != Path::new("romfs:/a"));
+        assert_eq!(Path::new("sdmc:a"), Path::new("sdmc:/a"));
+
+        assert!(Path::new("sdmc:/").is_absolute());
+        assert!(Path::new("sdmc:/a/b").is_absolute());
+        assert!(Path::new("sdmc:a").is_absolute());
+        assert!(!Path::new("/a/b").is_absolute());
+        assert!(Path::new("/a/b").has_root());
+        assert!(!Path::new("a/b").is_absolute());
+
+        let comps: Vec<_> = Path::new("sdmc:/a/b").components().collect();
+        assert_eq!(comps.len(), 4);
+        assert_eq!(comps[1], Component::RootDir);
+        assert_eq!(comps[2], Component::Normal(OsStr::new("a")));
+        assert_eq!(comps[3], Component::Normal(OsStr::new("b")));
+
+        let cwd = PathBuf::from("romfs:/data");
+        assert_eq!(cwd.join("a"), Path::new("romfs:/data/a"));
+        assert_eq!(cwd.join("/a"), Path::new("romfs:/a"));
+        assert_eq!(cwd.join("sdmc:/a"), Path::new("sdmc:/a"));
+        assert_eq!(PathBuf::from("sdmc:").join("a"), Path::new("sdmc:/a"));
+    }
+}
@@ -0,0 +1,311 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The services, backed by the real Horizon IPC interfaces.
+
+use ffi::OsStr;
+use io;
+use mem;
+use net::{Shutdown, SocketAddr};
+use path::Path;
+use slice;
+use sync::Arc;
+use sys::ext::ffi::OsStrExt;
+use sys::net::netc as c;
+use sys::net::sockaddr_to_addr;
+use sys_common::IntoInner;
+
+use megaton_hammer::error::{Error, Module};
+use megaton_hammer::ipcdefs::nn;
+use megaton_hammer::ipcdefs::nn::fssrv::sf::{IDirectory, IDirectoryEntry, IFile, IFileSystem,
+                                             IFileSystemProxy, DirectoryEntryType};
+use megaton_hammer::ipcdefs::nn::socket::sf::IClient;
+use megaton_hammer::kernel::{KObject, Session, TransferMemory};
+use megaton_hammer::loader::SocketKind;
+
+use super::{Bsd, Directory, DirectoryEntry, EntryType, File, FileSystem};
+
+lazy_static! {
+    static ref SDMC: Result<Arc<IFileSystem<Session>>, Error> = {
+        let ifs = IFileSystemProxy::new(|init| init(0))?;
+        Ok(Arc::new(ifs.open_sd_card_file_system()?))
+    };
+
+    static ref BSD_U: Result<Arc<IClient<Session>>, Error> = {
+        Ok(Arc::new(IClient::new_bsd_u(bsd_init_args)?))
+    };
+
+    static ref BSD_S: Result<Arc<IClient<Session>>, Error> = {
+        Ok(Arc::new(IClient::new_bsd_s(bsd_init_args)?))
+    };
+}
+
+type BsdResult = ::megaton_hammer::error::Result<IClient<Session>>;
+
+/// Sets up the buffers of a new bsd session.
+fn bsd_init_args(cb: fn(nn::socket::BsdBufferConfig, u64, u64, &KObject) -> BsdResult)
+                 -> BsdResult {
+    let transfer_mem = TransferMemory::new(4 * 256 * 2 * 1024)
+        .expect("TransferMem creation to succeed");
+    cb(nn::socket::BsdBufferConfig {
+        version: 1,
+        tcp_tx_buf_size: 0x8000,
+        tcp_rx_buf_size: 0x10_000,
+        tcp_tx_buf_max_size: 0x40_000,
+        tcp_rx_buf_max_size: 0x40_000,
+        udp_tx_buf_size: 0x2400,
+        udp_rx_buf_size: 0xA500,
+        sb_efficiency: 4,
+    }, 0, 4 * 256 * 2 * 1024, transfer_mem.as_ref())
+}
+
+/// Returns the filesystem of the SD card, mounted as `sdmc:`.
+pub fn sdmc() -> io::Result<Arc<FileSystem>> {
+    match *SDMC {
+        Ok(ref fs) => Ok(fs.clone()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Returns the process-wide bsd session: the one of `bsd:u` if it can be
+/// opened, otherwise the one of `bsd:s`.
+pub fn bsd() -> io::Result<Arc<Bsd>> {
+    bsd_of_kind(SocketKind::BsdU).or_else(|_| bsd_of_kind(SocketKind::BsdS))
+}
+
+/// Returns the process-wide session of the given bsd service. Sockets only
+/// exist in the service that created them, so a socket the loader hands over
+/// must be used through the session of the kind it comes with.
+pub fn bsd_of_kind(kind: SocketKind) -> io::Result<Arc<Bsd>> {
+    let session = match kind {
+        SocketKind::BsdU => &*BSD_U,
+        SocketKind::BsdS => &*BSD_S,
+    };
+    match *session {
+        Ok(ref bsd) => Ok(bsd.clone()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// fsp-srv takes paths as fixed-size, NUL-terminated buffers.
+fn path_arr(path: &Path) -> io::Result<[u8; 0x301]> {
+    let bytes = path.as_os_str().as_bytes();
+    if bytes.len() > 0x300 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "path is too long"));
+    }
+    let mut arr = [0u8; 0x301];
+    (&mut arr[..bytes.len()]).copy_from_slice(bytes);
+    Ok(arr)
+}
+
+impl From<DirectoryEntryType> for EntryType {
+    fn from(ty: DirectoryEntryType) -> EntryType {
+        match ty {
+            DirectoryEntryType::File => EntryType::File,
+            DirectoryEntryType::Directory => EntryType::Directory,
+        }
+    }
+}
+
+impl FileSystem for IFileSystem<Session> {
+    fn create_file(&self, path: &Path, size: u64) -> io::Result<()> {
+        IFileSystem::create_file(self, 0, size, &path_arr(path)?)?;
+        Ok(())
+    }
+
+    fn delete_file(&self, path: &Path) -> io::Result<()> {
+        IFileSystem::delete_file(self, &path_arr(path)?)?;
+        Ok(())
+    }
+
+    fn create_directory(&self, path: &Path) -> io::Result<()> {
+        IFileSystem::create_directory(self, &path_arr(path)?)?;
+        Ok(())
+    }
+
+    fn delete_directory(&self, path: &Path) -> io::Result<()> {
+        IFileSystem::delete_directory(self, &path_arr(path)?)?;
+        Ok(())
+    }
+
+    fn delete_directory_recursively(&self, path: &Path) -> io::Result<()> {
+        IFileSystem::delete_directory_recursively(self, &path_arr(path)?)?;
+        Ok(())
+    }
+
+    fn rename_file(&self, old: &Path, new: &Path) -> io::Result<()> {
+        IFileSystem::rename_file(self, &path_arr(old)?, &path_arr(new)?)?;
+        Ok(())
+    }
+
+    fn get_entry_type(&self, path: &Path) -> io::Result<EntryType> {
+        Ok(IFileSystem::get_entry_type(self, &path_arr(path)?)?.into())
+    }
+
+    fn open_file(&self, path: &Path, mode: u32) -> io::Result<Box<File>> {
+        Ok(Box::new(IFileSystem::open_file(self, mode, &path_arr(path)?)?))
+    }
+
+    fn open_directory(&self, path: &Path) -> io::Result<Box<Directory>> {
+        // 3 asks for both files and directories.
+        Ok(Box::new(IFileSystem::open_directory(self, 3, &path_arr(path)?)?))
+    }
+}
+
+impl File for IFile<Session> {
+    fn read(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len() as u64;
+        Ok(IFile::read(self, 0, offset, len, buf)? as usize)
+    }
+
+    fn write(&self, offset: u64, buf: &[u8]) -> io::Result<()> {
+        IFile::write(self, 0, offset, buf.len() as u64, buf)?;
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        IFile::flush(self)?;
+        Ok(())
+    }
+
+    fn get_size(&self) -> io::Result<u64> {
+        Ok(IFile::get_size(self)?)
+    }
+
+    fn set_size(&self, size: u64) -> io::Result<()> {
+        IFile::set_size(self, size)?;
+        Ok(())
+    }
+}
+
+impl Directory for IDirectory<Session> {
+    fn next_entry(&mut self) -> io::Result<Option<DirectoryEntry>> {
+        let mut entry: [IDirectoryEntry; 1] = [IDirectoryEntry {
+            path: [0; 0x300],
+            unk1: 0,
+            directory_entry_type: DirectoryEntryType::File,
+            filesize: 0,
+        }];
+        if IDirectory::read(self, &mut entry)? == 0 {
+            return Ok(None);
+        }
+        let size = entry[0].path.iter().position(|c| *c == b'\0').unwrap_or(0x300);
+        Ok(Some(DirectoryEntry {
+            name: OsStr::from_bytes(&entry[0].path[..size]).into(),
+            entry_type: entry[0].directory_entry_type.into(),
+            size: entry[0].filesize,
+        }))
+    }
+}
+
+macro_rules! handle_err {
+    ($x: expr) => {{
+        let val = $x?;
+        if val.0 == -1 {
+            Err(Error::from_module_description(Module::MegatonHammerLinux, val.1))?
+        } else {
+            val
+        }
+    }}
+}
+
+fn sockname<F>(f: F) -> io::Result<SocketAddr>
+    where F: FnOnce(&mut c::sockaddr) -> io::Result<()>
+{
+    unsafe {
+        let mut storage: c::sockaddr_storage = mem::zeroed();
+        let len = mem::size_of_val(&storage);
+        f(&mut storage)?;
+        sockaddr_to_addr(&storage, len)
+    }
+}
+
+impl Bsd for IClient<Session> {
+    fn socket(&self, domain: u32, ty: u32, protocol: u32) -> io::Result<u32> {
+        let (fd, _) = handle_err!(IClient::socket(self, domain, ty, protocol));
+        Ok(fd as u32)
+    }
+
+    fn connect(&self, fd: u32, addr: &SocketAddr) -> io::Result<()> {
+        let (addrp, _) = addr.into_inner();
+        handle_err!(IClient::connect(self, fd, unsafe { &*addrp }));
+        Ok(())
+    }
+
+    fn bind(&self, fd: u32, addr: &SocketAddr) -> io::Result<()> {
+        let (addrp, _) = addr.into_inner();
+        handle_err!(IClient::bind(self, fd, unsafe { &*addrp }));
+        Ok(())
+    }
+
+    fn listen(&self, fd: u32, backlog: u32) -> io::Result<()> {
+        handle_err!(IClient::listen(self, fd, backlog));
+        Ok(())
+    }
+
+    fn accept(&self, fd: u32) -> io::Result<(u32, SocketAddr)> {
+        let mut new_fd = 0;
+        let addr = sockname(|buf| {
+            let (ret, _) = handle_err!(IClient::accept(self, fd, buf));
+            new_fd = ret as u32;
+            Ok(())
+        })?;
+        Ok((new_fd, addr))
+    }
+
+    fn recv(&self, fd: u32, flags: u32, buf: &mut [u8]) -> io::Result<usize> {
+        let (ret, _) = handle_err!(IClient::recv(self, fd, flags, unsafe {
+            slice::from_raw_parts_mut(buf as *mut [u8] as *mut u8 as *mut i8, buf.len())
+        }));
+        Ok(ret as usize)
+    }
+
+    fn send(&self, fd: u32, flags: u32, buf: &[u8]) -> io::Result<usize> {
+        let (ret, _) = handle_err!(IClient::send(self, fd, flags, unsafe {
+            slice::from_raw_parts(buf as *const [u8] as *const u8 as *const i8, buf.len())
+        }));
+        Ok(ret as usize)
+    }
+
+    fn shutdown(&self, fd: u32, how: Shutdown) -> io::Result<()> {
+        // True in both freebsd and linux
+        let how = match how {
+            Shutdown::Write => 1, // SHUT_WR
+            Shutdown::Read => 0,
+            Shutdown::Both => 2,
+        };
+        handle_err!(IClient::shutdown(self, fd, how));
+        Ok(())
+    }
+
+    fn sock_name(&self, fd: u32) -> io::Result<SocketAddr> {
+        sockname(|buf| {
+            handle_err!(IClient::get_sock_name(self, fd, buf));
+            Ok(())
+        })
+    }
+
+    fn peer_name(&self, fd: u32) -> io::Result<SocketAddr> {
+        sockname(|buf| {
+            handle_err!(IClient::get_peer_name(self, fd, buf));
+            Ok(())
+        })
+    }
+
+    fn duplicate(&self, fd: u32) -> io::Result<u32> {
+        let (new_fd, _) = handle_err!(IClient::duplicate_socket(self, fd, 0));
+        Ok(new_fd as u32)
+    }
+
+    fn close(&self, fd: u32) -> io::Result<()> {
+        handle_err!(IClient::close(self, fd));
+        Ok(())
+    }
+}
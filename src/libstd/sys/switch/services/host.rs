@@ -0,0 +1,331 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Stand-ins for the Horizon services on a unix host.
+//!
+//! `HostFileSystem` behaves like an `IFileSystem` mounted on a fresh temporary
+//! directory, and `HostBsd` hands out file descriptors for host sockets. Only
+//! the behaviour the sys layer relies on is emulated: for instance, fsp-srv
+//! refuses to create a file that already exists, so this does too.
+
+use collections::HashMap;
+use fs;
+use io::{self, ErrorKind, Read, Write};
+use net::{self, Shutdown, SocketAddr};
+use path::{Path, PathBuf};
+use sync::{Arc, Mutex, Once};
+use sys::ext::fs::FileExt;
+use sys_common::io::test::{tmpdir, TempDir};
+
+use super::{Bsd, Directory, DirectoryEntry, EntryType, File, FileSystem};
+use super::{OPEN_APPEND, OPEN_READ, OPEN_WRITE, MSG_PEEK};
+
+/// Returns the process-wide stand-in for the SD card: a `HostFileSystem` on
+/// a temporary directory created on first use. Since it's never dropped, the
+/// directory outlives the process; tests needing a clean filesystem create
+/// their own `HostFileSystem`.
+pub fn sdmc() -> io::Result<Arc<FileSystem>> {
+    static INIT: Once = Once::new();
+    static mut SDMC: Option<Arc<HostFileSystem>> = None;
+    unsafe {
+        INIT.call_once(|| SDMC = Some(Arc::new(HostFileSystem::new())));
+        let sdmc: Arc<FileSystem> = SDMC.as_ref().unwrap().clone();
+        Ok(sdmc)
+    }
+}
+
+/// Returns the process-wide bsd session, so that, like on Horizon, all the
+/// sockets share one table of file descriptors.
+pub fn bsd() -> io::Result<Arc<Bsd>> {
+    static INIT: Once = Once::new();
+    static mut BSD: Option<Arc<HostBsd>> = None;
+    unsafe {
+        INIT.call_once(|| BSD = Some(Arc::new(HostBsd::new())));
+        let bsd: Arc<Bsd> = BSD.as_ref().unwrap().clone();
+        Ok(bsd)
+    }
+}
+
+#[derive(Debug)]
+pub struct HostFileSystem {
+    root: TempDir,
+}
+
+impl HostFileSystem {
+    /// Creates a filesystem backed by a new, empty temporary directory, which
+    /// is removed when the filesystem is dropped.
+    pub fn new() -> HostFileSystem {
+        HostFileSystem { root: tmpdir() }
+    }
+
+    /// The host path backing `path` on this filesystem.
+    pub fn host_path(&self, path: &Path) -> PathBuf {
+        assert!(path.has_root(), "filesystem paths are absolute");
+        self.root.path().join(path.strip_prefix("/").unwrap())
+    }
+}
+
+impl FileSystem for HostFileSystem {
+    fn create_file(&self, path: &Path, size: u64) -> io::Result<()> {
+        let file = fs::OpenOptions::new().write(true).create_new(true)
+            .open(self.host_path(path))?;
+        file.set_len(size)
+    }
+
+    fn delete_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(self.host_path(path))
+    }
+
+    fn create_directory(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(self.host_path(path))
+    }
+
+    fn delete_directory(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(self.host_path(path))
+    }
+
+    fn delete_directory_recursively(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(self.host_path(path))
+    }
+
+    fn rename_file(&self, old: &Path, new: &Path) -> io::Result<()> {
+        fs::rename(self.host_path(old), self.host_path(new))
+    }
+
+    fn get_entry_type(&self, path: &Path) -> io::Result<EntryType> {
+        if fs::metadata(self.host_path(path))?.is_dir() {
+            Ok(EntryType::Directory)
+        } else {
+            Ok(EntryType::File)
+        }
+    }
+
+    fn open_file(&self, path: &Path, mode: u32) -> io::Result<Box<File>> {
+        let file = fs::OpenOptions::new()
+            .read(mode & OPEN_READ != 0)
+            .write(mode & OPEN_WRITE != 0)
+            .open(self.host_path(path))?;
+        Ok(Box::new(HostFile { file, append: mode & OPEN_APPEND != 0 }))
+    }
+
+    fn open_directory(&self, path: &Path) -> io::Result<Box<Directory>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(self.host_path(path))? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            entries.push(DirectoryEntry {
+                name: entry.file_name(),
+                entry_type: if metadata.is_dir() {
+                    EntryType::Directory
+                } else {
+                    EntryType::File
+                },
+                size: if metadata.is_dir() { 0 } else { metadata.len() },
+            });
+        }
+        entries.reverse();
+        Ok(Box::new(HostDirectory(entries)))
+    }
+}
+
+#[derive(Debug)]
+struct HostFile {
+    file: fs::File,
+    append: bool,
+}
+
+impl File for HostFile {
+    fn read(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read_at(buf, offset)
+    }
+
+    fn write(&self, offset: u64, buf: &[u8]) -> io::Result<()> {
+        // Without OPEN_APPEND, fsp-srv refuses writes that would grow the file.
+        if !self.append && offset + buf.len() as u64 > self.get_size()? {
+            return Err(io::Error::new(ErrorKind::InvalidInput,
+                                      "write past the end of a file opened without append"));
+        }
+        let mut written = 0;
+        while written < buf.len() {
+            written += self.file.write_at(&buf[written..], offset + written as u64)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+
+    fn get_size(&self) -> io::Result<u64> {
+        Ok(self.file.metadata()?.len())
+    }
+
+    fn set_size(&self, size: u64) -> io::Result<()> {
+        self.file.set_len(size)
+    }
+}
+
+#[derive(Debug)]
+struct HostDirectory(Vec<DirectoryEntry>);
+
+impl Directory for HostDirectory {
+    fn next_entry(&mut self) -> io::Result<Option<DirectoryEntry>> {
+        Ok(self.0.pop())
+    }
+}
+
+enum HostSocket {
+    /// Created with `socket` but neither connected nor bound yet.
+    Unbound,
+    Bound(SocketAddr),
+    Listener(net::TcpListener),
+    Stream(net::TcpStream),
+}
+
+/// Emulates a bsd session with host TCP sockets. File descriptors are never
+/// reused, which makes use-after-close bugs show up as `EBADF`-like errors.
+pub struct HostBsd {
+    sockets: Mutex<HashMap<u32, HostSocket>>,
+    next_fd: Mutex<u32>,
+}
+
+fn bad_fd() -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, "bad file descriptor")
+}
+
+impl HostBsd {
+    pub fn new() -> HostBsd {
+        HostBsd {
+            sockets: Mutex::new(HashMap::new()),
+            next_fd: Mutex::new(0),
+        }
+    }
+
+    fn insert(&self, socket: HostSocket) -> u32 {
+        let mut next_fd = self.next_fd.lock().unwrap();
+        let fd = *next_fd;
+        *next_fd += 1;
+        self.sockets.lock().unwrap().insert(fd, socket);
+        fd
+    }
+
+    fn with_stream<F, T>(&self, fd: u32, f: F) -> io::Result<T>
+        where F: FnOnce(&mut net::TcpStream) -> io::Result<T>
+    {
+        match self.sockets.lock().unwrap().get_mut(&fd) {
+            Some(HostSocket::Stream(stream)) => f(stream),
+            Some(_) => Err(io::Error::from(ErrorKind::NotConnected)),
+            None => Err(bad_fd()),
+        }
+    }
+
+    /// Clones the host stream behind `fd`, so that blocking operations on it
+    /// don't hold the lock on the whole socket table.
+    fn stream(&self, fd: u32) -> io::Result<net::TcpStream> {
+        self.with_stream(fd, |stream| stream.try_clone())
+    }
+}
+
+impl Bsd for HostBsd {
+    fn socket(&self, _domain: u32, _ty: u32, _protocol: u32) -> io::Result<u32> {
+        Ok(self.insert(HostSocket::Unbound))
+    }
+
+    fn connect(&self, fd: u32, addr: &SocketAddr) -> io::Result<()> {
+        let stream = net::TcpStream::connect(addr)?;
+        match self.sockets.lock().unwrap().get_mut(&fd) {
+            Some(socket) => {
+                *socket = HostSocket::Stream(stream);
+                Ok(())
+            }
+            None => Err(bad_fd()),
+        }
+    }
+
+    fn bind(&self, fd: u32, addr: &SocketAddr) -> io::Result<()> {
+        match self.sockets.lock().unwrap().get_mut(&fd) {
+            Some(socket) => {
+                *socket = HostSocket::Bound(*addr);
+                Ok(())
+            }
+            None => Err(bad_fd()),
+        }
+    }
+
+    fn listen(&self, fd: u32, _backlog: u32) -> io::Result<()> {
+        let mut sockets = self.sockets.lock().unwrap();
+        let addr = match sockets.get(&fd) {
+            Some(HostSocket::Bound(addr)) => *addr,
+            Some(_) => return Err(io::Error::from(ErrorKind::InvalidInput)),
+            None => return Err(bad_fd()),
+        };
+        sockets.insert(fd, HostSocket::Listener(net::TcpListener::bind(addr)?));
+        Ok(())
+    }
+
+    fn accept(&self, fd: u32) -> io::Result<(u32, SocketAddr)> {
+        let listener = match self.sockets.lock().unwrap().get(&fd) {
+            Some(HostSocket::Listener(listener)) => listener.try_clone()?,
+            Some(_) => return Err(io::Error::from(ErrorKind::InvalidInput)),
+            None => return Err(bad_fd()),
+        };
+        let (stream, addr) = listener.accept()?;
+        Ok((self.insert(HostSocket::Stream(stream)), addr))
+    }
+
+    fn recv(&self, fd: u32, flags: u32, buf: &mut [u8]) -> io::Result<usize> {
+        let mut stream = self.stream(fd)?;
+        if flags & MSG_PEEK != 0 {
+            stream.peek(buf)
+        } else {
+            stream.read(buf)
+        }
+    }
+
+    fn send(&self, fd: u32, _flags: u32, buf: &[u8]) -> io::Result<usize> {
+        self.stream(fd)?.write(buf)
+    }
+
+    fn shutdown(&self, fd: u32, how: Shutdown) -> io::Result<()> {
+        self.with_stream(fd, |stream| stream.shutdown(how))
+    }
+
+    fn sock_name(&self, fd: u32) -> io::Result<SocketAddr> {
+        match self.sockets.lock().unwrap().get(&fd) {
+            Some(HostSocket::Listener(listener)) => listener.local_addr(),
+            Some(HostSocket::Stream(stream)) => stream.local_addr(),
+            Some(HostSocket::Bound(addr)) => Ok(*addr),
+            Some(HostSocket::Unbound) => Err(io::Error::from(ErrorKind::InvalidInput)),
+            None => Err(bad_fd()),
+        }
+    }
+
+    fn peer_name(&self, fd: u32) -> io::Result<SocketAddr> {
+        self.with_stream(fd, |stream| stream.peer_addr())
+    }
+
+    fn duplicate(&self, fd: u32) -> io::Result<u32> {
+        let socket = match self.sockets.lock().unwrap().get(&fd) {
+            Some(HostSocket::Listener(listener)) => HostSocket::Listener(listener.try_clone()?),
+            Some(HostSocket::Stream(stream)) => HostSocket::Stream(stream.try_clone()?),
+            Some(HostSocket::Bound(addr)) => HostSocket::Bound(*addr),
+            Some(HostSocket::Unbound) => HostSocket::Unbound,
+            None => return Err(bad_fd()),
+        };
+        Ok(self.insert(socket))
+    }
+
+    fn close(&self, fd: u32) -> io::Result<()> {
+        match self.sockets.lock().unwrap().remove(&fd) {
+            Some(_) => Ok(()),
+            None => Err(bad_fd()),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The Horizon services the sys layer talks to.
+//!
+//! `fs` and `net` never touch megaton-hammer sessions directly. Instead, they
+//! go through the traits in this module, which mirror the subset of the
+//! `fsp-srv` and `bsd` IPC interfaces we actually use, with paths, addresses
+//! and errors already translated to their `std` counterparts.
+//!
+//! On the Switch, the traits are implemented on top of the real IPC clients
+//! in `horizon`. Everywhere else (which in practice means test builds of the
+//! Switch sys layer on a Linux host, see `sys/switch/host.rs`) they are backed
+//! by a temporary directory and host sockets in `host`.
+
+use fmt::Debug;
+use ffi::OsString;
+use io;
+use net::{Shutdown, SocketAddr};
+use path::Path;
+
+#[cfg(target_os = "switch")]
+mod horizon;
+#[cfg(target_os = "switch")]
+pub use self::horizon::{bsd, bsd_of_kind, sdmc};
+
+#[cfg(not(target_os = "switch"))]
+pub mod host;
+#[cfg(not(target_os = "switch"))]
+pub use self::host::{bsd, sdmc};
+
+/// Open the file for reading.
+pub const OPEN_READ: u32 = 1 << 0;
+/// Open the file for writing.
+pub const OPEN_WRITE: u32 = 1 << 1;
+/// Allow writes past the end of the file to grow it.
+pub const OPEN_APPEND: u32 = 1 << 2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryType {
+    File,
+    Directory,
+}
+
+#[derive(Debug, Clone)]
+pub struct DirectoryEntry {
+    pub name: OsString,
+    pub entry_type: EntryType,
+    pub size: u64,
+}
+
+/// A mounted filesystem, as exposed by `nn::fssrv::sf::IFileSystem`.
+///
+/// Every path is absolute on the filesystem, without a device prefix, and
+/// already normalized (see `sys::path::normalize`).
+pub trait FileSystem: Debug + Send + Sync {
+    fn create_file(&self, path: &Path, size: u64) -> io::Result<()>;
+    fn delete_file(&self, path: &Path) -> io::Result<()>;
+    fn create_directory(&self, path: &Path) -> io::Result<()>;
+    fn delete_directory(&self, path: &Path) -> io::Result<()>;
+    fn delete_directory_recursively(&self, path: &Path) -> io::Result<()>;
+    fn rename_file(&self, old: &Path, new: &Path) -> io::Result<()>;
+    fn get_entry_type(&self, path: &Path) -> io::Result<EntryType>;
+    /// `mode` is a combination of the `OPEN_*` flags.
+    fn open_file(&self, path: &Path, mode: u32) -> io::Result<Box<File>>;
+    fn open_directory(&self, path: &Path) -> io::Result<Box<Directory>>;
+}
+
+/// An open file, as exposed by `nn::fssrv::sf::IFile`. Files have no cursor:
+/// every access is done at an explicit offset.
+pub trait File: Debug + Send + Sync {
+    fn read(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>;
+    fn write(&self, offset: u64, buf: &[u8]) -> io::Result<()>;
+    fn flush(&self) -> io::Result<()>;
+    fn get_size(&self) -> io::Result<u64>;
+    fn set_size(&self, size: u64) -> io::Result<()>;
+}
+
+/// An open directory, as exposed by `nn::fssrv::sf::IDirectory`.
+pub trait Directory: Debug + Send {
+    /// Returns the next entry, or `None` once all of them have been read.
+    fn next_entry(&mut self) -> io::Result<Option<DirectoryEntry>>;
+}
+
+/// The BSD socket service, as exposed by `nn::socket::sf::IClient`.
+///
+/// Sockets are identified by the file descriptors `bsd` hands out. Errors
+/// reported through the bsd errno are already converted to `io::Error`s.
+pub trait Bsd: Send + Sync {
+    fn socket(&self, domain: u32, ty: u32, protocol: u32) -> io::Result<u32>;
+    fn connect(&self, fd: u32, addr: &SocketAddr) -> io::Result<()>;
+    fn bind(&self, fd: u32, addr: &SocketAddr) -> io::Result<()>;
+    fn listen(&self, fd: u32, backlog: u32) -> io::Result<()>;
+    fn accept(&self, fd: u32) -> io::Result<(u32, SocketAddr)>;
+    fn recv(&self, fd: u32, flags: u32, buf: &mut [u8]) -> io::Result<usize>;
+    fn send(&self, fd: u32, flags: u32, buf: &[u8]) -> io::Result<usize>;
+    fn shutdown(&self, fd: u32, how: Shutdown) -> io::Result<()>;
+    fn sock_name(&self, fd: u32) -> io::Result<SocketAddr>;
+    fn peer_name(&self, fd: u32) -> io::Result<SocketAddr>;
+    fn duplicate(&self, fd: u32) -> io::Result<u32>;
+    fn close(&self, fd: u32) -> io::Result<()>;
+}
+
+/// `MSG_PEEK`, as defined by linux and freebsd.
+pub const MSG_PEEK: u32 = 2;
+/// `SOCK_STREAM`.
+pub const SOCK_STREAM: u32 = 1;
+/// `AF_INET`.
+pub const AF_INET: u32 = 2;
@@ -9,12 +9,10 @@
 // except according to those terms.
 
 use io;
+use sys::services;
 use sys::unsupported;
-use slice;
 
 use megaton_hammer::loader::{self, Logger, SocketKind};
-use megaton_hammer::kernel::TransferMemory;
-use megaton_hammer::ipcdefs::nn;
 
 pub struct Stdin;
 pub struct Stdout;
@@ -36,33 +34,10 @@ impl Stdout {
     }
 
     pub fn write(&self, data: &[u8]) -> io::Result<usize> {
-        use megaton_hammer::ipcdefs::nn::socket::sf::IClient;
-
         Logger.write(&data[..data.len()]);
-        let msg_len = if let Some((kind, stdout)) = loader::get_stdout_socket() {
-            let init_args = |cb: fn(_, _, _, &_) -> _| {
-                let transfer_mem = TransferMemory::new(4 * 256 * 2 * 1024).expect("TransferMem creation to succeed");
-                cb(nn::socket::BsdBufferConfig {
-                    version: 1,
-                    tcp_tx_buf_size: 0x8000,
-                    tcp_rx_buf_size: 0x10_000,
-                    tcp_tx_buf_max_size: 0x40_000,
-                    tcp_rx_buf_max_size: 0x40_000,
-                    udp_tx_buf_size: 0x2400,
-                    udp_rx_buf_size: 0xA500,
-                    sb_efficiency: 4,
-                }, 0, 4 * 256 * 2 * 1024, transfer_mem.as_ref())
-            };
-            let client = match kind {
-                SocketKind::BsdU => IClient::new_bsd_u(init_args),
-                SocketKind::BsdS => IClient::new_bsd_s(init_args)
-            };
-            match client.and_then(|client| client.write(stdout, unsafe { slice::from_raw_parts(data.as_ptr() as *const i8, data.len()) })) {
-                Ok((ret, _bsd_errno)) if ret >= 0 => ret as usize,
-                _ => data.len()
-            }
-        } else {
-            data.len()
+        let msg_len = match loader::get_stdout_socket() {
+            Some((kind, stdout)) => write_socket(kind, stdout as u32, data),
+            None => data.len(),
         };
         Ok(msg_len)
     }
@@ -78,33 +53,9 @@ impl Stderr {
     }
 
     pub fn write(&self, data: &[u8]) -> io::Result<usize> {
-        use megaton_hammer::ipcdefs::nn::socket::sf::IClient;
-
-        let msg_len = if let Some((kind, stderr)) = loader::get_stderr_socket() {
-            let init_args = |cb: fn(_, _, _, &_) -> _| {
-                let transfer_mem = TransferMemory::new(4 * 256 * 2 * 1024).expect("TransferMem creation to succeed");
-                cb(nn::socket::BsdBufferConfig {
-                    version: 1,
-                    tcp_tx_buf_size: 0x8000,
-                    tcp_rx_buf_size: 0x10_000,
-                    tcp_tx_buf_max_size: 0x40_000,
-                    tcp_rx_buf_max_size: 0x40_000,
-                    udp_tx_buf_size: 0x2400,
-                    udp_rx_buf_size: 0xA500,
-                    sb_efficiency: 4,
-                }, 0, 4 * 256 * 2 * 1024, transfer_mem.as_ref())
-            };
-            let client = match kind {
-                SocketKind::BsdU => IClient::new_bsd_u(init_args),
-                SocketKind::BsdS => IClient::new_bsd_s(init_args)
-            };
-            // Should be already initialized.
-            match client.and_then(|client| client.write(stderr, unsafe { slice::from_raw_parts(data.as_ptr() as *const i8, data.len()) } )) {
-                Ok((ret, _bsd_errno)) if ret >= 0 => ret as usize,
-                _ => data.len()
-            }
-        } else {
-            data.len()
+        let msg_len = match loader::get_stderr_socket() {
+            Some((kind, stderr)) => write_socket(kind, stderr as u32, data),
+            None => data.len(),
         };
         Logger.write(&data[..msg_len]);
         Ok(msg_len)
@@ -124,6 +75,17 @@ impl io::Write for Stderr {
     }
 }
 
+/// Sends `data` to the socket `fd` the loader set up for stdout or stderr,
+/// through the session of the bsd service `kind` the socket belongs to. If
+/// that fails, the data is still reported as written: it has gone to the
+/// logger anyway.
+fn write_socket(kind: SocketKind, fd: u32, data: &[u8]) -> usize {
+    match services::bsd_of_kind(kind).and_then(|bsd| bsd.send(fd, 0, data)) {
+        Ok(len) => len,
+        Err(_) => data.len(),
+    }
+}
+
 pub const STDIN_BUF_SIZE: usize = 0;
 
 pub fn is_ebadf(_err: &io::Error) -> bool {
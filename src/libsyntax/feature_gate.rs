@@ -477,6 +477,9 @@ declare_features! (
     (active, alloc_error_handler, "1.29.0", Some(51540), None),
 
     (active, abi_amdgpu_kernel, "1.29.0", Some(51575), None),
+
+    // Allows a test to set its own time limit with #[test_timeout = "<seconds>"]
+    (active, test_timeout, "1.29.0", None, None),
 );
 
 declare_features! (
@@ -1036,6 +1039,11 @@ pub const BUILTIN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeG
                                  "allow_fail attribute is currently unstable",
                                  cfg_fn!(allow_fail))),
 
+    ("test_timeout", Normal, Gated(Stability::Unstable,
+                                   "test_timeout",
+                                   "the `#[test_timeout]` attribute is an experimental feature",
+                                   cfg_fn!(test_timeout))),
+
     ("rustc_std_internal_symbol", Whitelisted, Gated(Stability::Unstable,
                                      "rustc_attrs",
                                      "this is an internal attribute that will \
@@ -0,0 +1,293 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Applying machine-applicable suggestions, for `-Z apply-suggestions`.
+//!
+//! `FixEmitter` wraps the emitter of the session: diagnostics are emitted as
+//! usual, and the suggestions marked `Applicability::MachineApplicable` are
+//! recorded. When the emitter is dropped along with its `Handler`, at the end
+//! of the compilation, the suggestions are applied to the source files, and a
+//! summary of the fixes is printed, unless the diagnostics are in a format
+//! meant for tools, like JSON, which the summary would corrupt.
+//!
+//! A suggestion is only applied when it's unambiguous and safe to apply:
+//!
+//! * it has a single alternative,
+//! * all of its spans are in files of the crate, that is files under the
+//!   directory of the crate root, and not in the expansion of a macro,
+//! * none of its spans overlap the spans of a suggestion applied before it,
+//! * its files haven't changed since they were read by the compiler.
+//!
+//! Everything else is left alone, and counted as skipped in the summary.
+
+use codemap::CodeMap;
+use syntax_pos::{FileName, Span, SyntaxContext};
+use errors::{Applicability, CodeSuggestion, DiagnosticBuilder};
+use errors::emitter::Emitter;
+
+use rustc_data_structures::sync::{self, Lrc};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+/// A replacement of the bytes `lo..hi` of a file with `snippet`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Edit {
+    lo: usize,
+    hi: usize,
+    snippet: String,
+}
+
+impl Edit {
+    /// Whether `self` and `other` can't both be applied: they replace some of
+    /// the same bytes, or they insert text at the same position, where the
+    /// order of the insertions would be arbitrary.
+    fn overlaps(&self, other: &Edit) -> bool {
+        if self.lo == self.hi || other.lo == other.hi {
+            self.lo <= other.hi && other.lo <= self.hi
+        } else {
+            self.lo < other.hi && other.lo < self.hi
+        }
+    }
+}
+
+/// An edit, with the path and the source of its file.
+type FileEdit = (PathBuf, Lrc<String>, Edit);
+
+/// A suggestion that can be applied.
+#[derive(Clone, PartialEq)]
+struct Fix {
+    msg: String,
+    /// The line of the first edit, for the summary.
+    line: usize,
+    edits: Vec<FileEdit>,
+}
+
+pub struct FixEmitter {
+    emitter: Box<dyn Emitter + sync::Send>,
+    cm: Lrc<CodeMap>,
+    /// The directory of the crate root; no other files are touched.
+    root: Option<PathBuf>,
+    dst: Box<dyn Write + Send>,
+    fixes: Vec<Fix>,
+    /// The number of machine-applicable suggestions that can't be applied.
+    skipped: usize,
+}
+
+impl FixEmitter {
+    /// The summary is printed to stderr if `summary` is set, and dropped
+    /// otherwise.
+    pub fn new(emitter: Box<dyn Emitter + sync::Send>,
+               code_map: Lrc<CodeMap>,
+               crate_root: Option<&Path>,
+               summary: bool) -> FixEmitter {
+        let root = crate_root.and_then(|path| {
+            let dir = match path.parent() {
+                Some(dir) if dir != Path::new("") => dir,
+                _ => Path::new("."),
+            };
+            dir.canonicalize().ok()
+        });
+        FixEmitter {
+            emitter,
+            cm: code_map,
+            root,
+            dst: if summary { Box::new(io::stderr()) } else { Box::new(io::sink()) },
+            fixes: vec![],
+            skipped: 0,
+        }
+    }
+
+    /// The file and the edit replacing `span`, unless it's outside the crate.
+    fn edit(&self, span: Span, snippet: &str) -> Option<FileEdit> {
+        if span.is_dummy() || span.ctxt() != SyntaxContext::empty() {
+            return None;
+        }
+        let lo = self.cm.lookup_byte_offset(span.lo());
+        let hi = self.cm.lookup_byte_offset(span.hi());
+        if !Lrc::ptr_eq(&lo.fm, &hi.fm) {
+            return None;
+        }
+        let src = lo.fm.src.clone()?;
+        let path = match lo.fm.unmapped_path {
+            Some(FileName::Real(ref path)) => path.canonicalize().ok()?,
+            _ => return None,
+        };
+        if !path.starts_with(self.root.as_ref()?) {
+            return None;
+        }
+        let edit = Edit {
+            lo: lo.pos.0 as usize,
+            hi: hi.pos.0 as usize,
+            snippet: snippet.to_owned(),
+        };
+        Some((path, src, edit))
+    }
+
+    fn fix(&self, suggestion: &CodeSuggestion) -> Option<Fix> {
+        if suggestion.substitutions.len() != 1 {
+            return None;
+        }
+        let parts = &suggestion.substitutions[0].parts;
+        let edits = parts.iter()
+                         .map(|part| self.edit(part.span, &part.snippet))
+                         .collect::<Option<Vec<_>>>()?;
+        let first = parts.iter().map(|part| part.span.lo()).min()?;
+        Some(Fix {
+            msg: suggestion.msg.clone(),
+            line: self.cm.lookup_char_pos(first).line,
+            edits,
+        })
+    }
+
+    /// Apply the fixes that don't overlap the fixes before them, and print
+    /// the summary.
+    fn apply(&mut self) -> io::Result<()> {
+        let mut applied: Vec<&Fix> = vec![];
+        let mut skipped = self.skipped;
+        for fix in &self.fixes {
+            let overlaps = |a: &FileEdit, b: &FileEdit| a.0 == b.0 && a.2.overlaps(&b.2);
+            let conflicts = fix.edits.iter().enumerate().any(|(i, edit)| {
+                fix.edits[i + 1..].iter().any(|other| overlaps(edit, other)) ||
+                applied.iter().any(|prev| prev.edits.iter().any(|other| overlaps(edit, other)))
+            });
+            if conflicts {
+                skipped += 1;
+            } else {
+                applied.push(fix);
+            }
+        }
+
+        // The edits and the fixes, by file, in the order the files were
+        // first fixed.
+        let mut files: Vec<(&Path, &Lrc<String>, Vec<&Edit>, Vec<&Fix>)> = vec![];
+        for &fix in &applied {
+            for &(ref path, ref src, ref edit) in &fix.edits {
+                let i = match files.iter().position(|f| f.0 == &**path) {
+                    Some(i) => i,
+                    None => {
+                        files.push((path, src, vec![], vec![]));
+                        files.len() - 1
+                    }
+                };
+                files[i].2.push(edit);
+                if files[i].3.last().map_or(true, |&last| !ptr::eq(last, fix)) {
+                    files[i].3.push(fix);
+                }
+            }
+        }
+
+        let mut fixed = 0;
+        let mut fixed_files = 0;
+        for (path, src, mut edits, fixes) in files {
+            let mut contents = fs::read_to_string(path)?;
+            // The compiler doesn't see the byte order mark.
+            let bom = if contents.starts_with("\u{feff}") { 3 } else { 0 };
+            if contents[bom..] != **src {
+                writeln!(self.dst, "warning: not fixing `{}`, which changed during the \
+                                    compilation", path.display())?;
+                skipped += fixes.len();
+                continue;
+            }
+
+            // From the end, so that the offsets of the edits left stay valid.
+            edits.sort();
+            for edit in edits.iter().rev() {
+                contents.replace_range(bom + edit.lo..bom + edit.hi, &edit.snippet);
+            }
+            fs::write(path, contents)?;
+
+            let display = self.root.as_ref()
+                                   .and_then(|root| path.strip_prefix(root).ok())
+                                   .unwrap_or(path);
+            writeln!(self.dst, "fixed `{}`:", display.display())?;
+            for fix in &fixes {
+                writeln!(self.dst, "    line {}: {}", fix.line, fix.msg)?;
+            }
+            fixed += fixes.len();
+            fixed_files += 1;
+        }
+
+        write!(self.dst, "applied {} suggestion{} to {} file{}",
+               fixed, if fixed == 1 { "" } else { "s" },
+               fixed_files, if fixed_files == 1 { "" } else { "s" })?;
+        if skipped > 0 {
+            write!(self.dst, "; skipped {} suggestion{} that couldn't be applied safely",
+                   skipped, if skipped == 1 { "" } else { "s" })?;
+        }
+        writeln!(self.dst)?;
+        self.dst.flush()
+    }
+}
+
+impl Emitter for FixEmitter {
+    fn emit(&mut self, db: &DiagnosticBuilder) {
+        self.emitter.emit(db);
+
+        for suggestion in &db.suggestions {
+            if suggestion.applicability != Applicability::MachineApplicable {
+                continue;
+            }
+            match self.fix(suggestion) {
+                // The same lint can be emitted more than once for a span.
+                Some(fix) => if !self.fixes.contains(&fix) {
+                    self.fixes.push(fix);
+                },
+                None => self.skipped += 1,
+            }
+        }
+    }
+
+    fn should_show_explain(&self) -> bool {
+        self.emitter.should_show_explain()
+    }
+}
+
+impl Drop for FixEmitter {
+    fn drop(&mut self) {
+        if self.fixes.is_empty() && self.skipped == 0 {
+            return;
+        }
+        if let Err(e) = self.apply() {
+            // Like the other emitters, this happens while unwinding from a
+            // fatal error, where panicking would abort.
+            let _ = writeln!(self.dst, "error: failed to apply suggestions: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Edit;
+
+    fn edit(lo: usize, hi: usize) -> Edit {
+        Edit { lo, hi, snippet: String::new() }
+    }
+
+    #[test]
+    fn overlapping_edits() {
+        assert!(edit(0, 4).overlaps(&edit(2, 6)));
+        assert!(edit(2, 6).overlaps(&edit(0, 4)));
+        assert!(edit(0, 6).overlaps(&edit(2, 4)));
+        assert!(!edit(0, 2).overlaps(&edit(2, 4)));
+        assert!(!edit(2, 4).overlaps(&edit(0, 2)));
+    }
+
+    #[test]
+    fn overlapping_insertions() {
+        assert!(edit(2, 2).overlaps(&edit(2, 2)));
+        assert!(edit(2, 2).overlaps(&edit(0, 4)));
+        assert!(edit(2, 2).overlaps(&edit(2, 4)));
+        assert!(edit(4, 4).overlaps(&edit(2, 4)));
+        assert!(!edit(2, 2).overlaps(&edit(3, 3)));
+        assert!(!edit(0, 1).overlaps(&edit(2, 2)));
+    }
+}
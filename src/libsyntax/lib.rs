@@ -133,7 +133,9 @@ pub mod util {
     pub use self::rc_slice::RcSlice;
 }
 
+pub mod fix;
 pub mod json;
+pub mod sarif;
 
 pub mod syntax {
     pub use ext;
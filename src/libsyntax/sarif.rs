@@ -0,0 +1,409 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A SARIF emitter for errors, for `--error-format=sarif`.
+//!
+//! SARIF 2.1.0 is the format of the results of static analysis tools. A whole
+//! compilation is a single SARIF log, so diagnostics are collected as they are
+//! emitted, and the log is written when the emitter is dropped along with its
+//! `Handler`.
+//!
+//! Every diagnostic with a span in a source file is a result. Its primary
+//! spans are its locations, and its other spans, and the notes and help with
+//! spans of their own, are related locations; the notes and help without
+//! spans are added to its message. Each alternative of a suggestion is a fix.
+//! Error codes and lints are the rules of the results, and the rules of error
+//! codes have the explanation from the `Registry`. Diagnostics without a span,
+//! like "aborting due to previous error", aren't about the code, and are
+//! notifications of the invocation.
+//!
+//! The warnings emitted before the session is built, by `early_warn`, are
+//! kept for the log of the session, so that the process still writes a single
+//! log. The rules of lints are described with the descriptions the lint store
+//! registers here, as it doesn't know about the emitter.
+
+use codemap::{CodeMap, FilePathMapping};
+use syntax_pos::{FileName, Span};
+use errors::registry::Registry;
+use errors::{CodeSuggestion, CodeMapper, DiagnosticBuilder, DiagnosticId, Level};
+use errors::emitter::Emitter;
+
+use rustc_data_structures::sync::{self, Lrc};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, Once, ONCE_INIT};
+use std::thread;
+
+use rustc_serialize::json::{Json, ToJson};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// What the emitters of the process share.
+#[derive(Default)]
+struct Shared {
+    /// The notifications of the early emitters, for the next log.
+    early: Vec<Json>,
+    /// The descriptions of the lints, by name.
+    lints: HashMap<String, &'static str>,
+}
+
+fn shared() -> MutexGuard<'static, Shared> {
+    static INIT: Once = ONCE_INIT;
+    static mut SHARED: *mut Mutex<Shared> = 0 as *mut _;
+    unsafe {
+        INIT.call_once(|| {
+            SHARED = Box::into_raw(Box::new(Mutex::new(Shared::default())));
+        });
+        // The log is written while unwinding too, so ignore poisoning.
+        match (*SHARED).lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Records the description of the lint `name`, for its rule.
+pub fn register_lint(name: String, desc: &'static str) {
+    shared().lints.insert(name, desc);
+}
+
+pub struct SarifEmitter {
+    dst: Box<dyn Write + Send>,
+    registry: Option<Registry>,
+    cm: Lrc<dyn CodeMapper + sync::Send + sync::Sync>,
+    /// The rules of the results so far, in the order of their `ruleIndex`.
+    rules: Vec<DiagnosticId>,
+    results: Vec<Json>,
+    notifications: Vec<Json>,
+    /// Whether an error was emitted.
+    failed: bool,
+    /// Whether the notifications are kept for the next log instead of being
+    /// written, see `early`.
+    deferred: bool,
+}
+
+impl SarifEmitter {
+    pub fn stderr(registry: Option<Registry>, code_map: Lrc<CodeMap>) -> SarifEmitter {
+        SarifEmitter::new(Box::new(io::stderr()), registry, code_map)
+    }
+
+    pub fn basic() -> SarifEmitter {
+        let file_path_mapping = FilePathMapping::empty();
+        SarifEmitter::stderr(None, Lrc::new(CodeMap::new(file_path_mapping)))
+    }
+
+    /// An emitter for the warnings emitted before the session is built,
+    /// which don't have spans. They are added to the log of the next emitter
+    /// rather than written on their own.
+    pub fn early() -> SarifEmitter {
+        let mut emitter = SarifEmitter::basic();
+        // It takes the notifications of the early emitters before it too, and
+        // gives them back along with its own.
+        emitter.deferred = true;
+        emitter
+    }
+
+    pub fn new(dst: Box<dyn Write + Send>,
+               registry: Option<Registry>,
+               code_map: Lrc<CodeMap>) -> SarifEmitter {
+        SarifEmitter {
+            dst,
+            registry,
+            cm: code_map,
+            rules: vec![],
+            results: vec![],
+            notifications: shared().early.drain(..).collect(),
+            failed: false,
+            deferred: false,
+        }
+    }
+
+    /// The index of the rule `id` in `self.rules`, adding it if it's new.
+    fn rule_index(&mut self, id: &DiagnosticId) -> usize {
+        match self.rules.iter().position(|rule| rule == id) {
+            Some(index) => index,
+            None => {
+                self.rules.push(id.clone());
+                self.rules.len() - 1
+            }
+        }
+    }
+
+    fn rule(&self, id: &DiagnosticId) -> Json {
+        let mut rule = BTreeMap::new();
+        match *id {
+            DiagnosticId::Error(ref code) => {
+                rule.insert("id".to_owned(), code.to_json());
+                let explanation = self.registry
+                                      .as_ref()
+                                      .and_then(|registry| registry.find_description(code));
+                if let Some(explanation) = explanation {
+                    let explanation = explanation.trim();
+                    let mut description = BTreeMap::new();
+                    description.insert("text".to_owned(), explanation.to_json());
+                    description.insert("markdown".to_owned(), explanation.to_json());
+                    rule.insert("fullDescription".to_owned(), Json::Object(description));
+                    rule.insert("helpUri".to_owned(),
+                                format!("https://doc.rust-lang.org/error-index.html#{}", code)
+                                    .to_json());
+                }
+            }
+            DiagnosticId::Lint(ref name) => {
+                rule.insert("id".to_owned(), name.to_json());
+                if let Some(desc) = shared().lints.get(name) {
+                    rule.insert("shortDescription".to_owned(), message(desc));
+                    rule.insert("fullDescription".to_owned(), message(desc));
+                }
+                let mut properties = BTreeMap::new();
+                properties.insert("tags".to_owned(), vec!["lint".to_owned()].to_json());
+                rule.insert("properties".to_owned(), Json::Object(properties));
+            }
+        }
+        Json::Object(rule)
+    }
+
+    /// The file and the region of `span`, unless it isn't in a source file.
+    fn region(&self, span: Span) -> Option<(Json, Json)> {
+        if span.is_dummy() {
+            return None;
+        }
+        let start = self.cm.lookup_char_pos(span.lo());
+        let end = self.cm.lookup_char_pos(span.hi());
+        let artifact = match start.file.name {
+            FileName::Real(ref path) => artifact_location(path),
+            _ => return None,
+        };
+
+        let mut region = BTreeMap::new();
+        region.insert("startLine".to_owned(), start.line.to_json());
+        region.insert("startColumn".to_owned(), (start.col.0 + 1).to_json());
+        region.insert("endLine".to_owned(), end.line.to_json());
+        region.insert("endColumn".to_owned(), (end.col.0 + 1).to_json());
+        region.insert("byteOffset".to_owned(),
+                      (span.lo().0 - start.file.start_pos.0).to_json());
+        region.insert("byteLength".to_owned(), (span.hi().0 - span.lo().0).to_json());
+        Some((artifact, Json::Object(region)))
+    }
+
+    fn location(&self, span: Span, label: Option<String>) -> Option<Json> {
+        let (artifact, region) = self.region(span)?;
+        let mut physical = BTreeMap::new();
+        physical.insert("artifactLocation".to_owned(), artifact);
+        physical.insert("region".to_owned(), region);
+        let mut location = BTreeMap::new();
+        location.insert("physicalLocation".to_owned(), Json::Object(physical));
+        if let Some(label) = label {
+            if !label.is_empty() {
+                location.insert("message".to_owned(), message(&label));
+            }
+        }
+        Some(Json::Object(location))
+    }
+
+    /// The fixes of `suggestion`, one for each of its alternatives.
+    fn fixes(&self, suggestion: &CodeSuggestion) -> Vec<Json> {
+        suggestion.substitutions.iter().filter_map(|substitution| {
+            // The replacements, by file.
+            let mut changes: Vec<(Json, Vec<Json>)> = vec![];
+            for part in &substitution.parts {
+                let (artifact, region) = self.region(part.span)?;
+                let mut replacement = BTreeMap::new();
+                replacement.insert("deletedRegion".to_owned(), region);
+                replacement.insert("insertedContent".to_owned(), message(&part.snippet));
+                let replacement = Json::Object(replacement);
+                match changes.iter().position(|&(ref a, _)| *a == artifact) {
+                    Some(i) => changes[i].1.push(replacement),
+                    None => changes.push((artifact, vec![replacement])),
+                }
+            }
+
+            let changes = changes.into_iter().map(|(artifact, replacements)| {
+                let mut change = BTreeMap::new();
+                change.insert("artifactLocation".to_owned(), artifact);
+                change.insert("replacements".to_owned(), Json::Array(replacements));
+                Json::Object(change)
+            }).collect::<Vec<_>>();
+            let mut properties = BTreeMap::new();
+            properties.insert("applicability".to_owned(),
+                              format!("{:?}", suggestion.applicability).to_json());
+            let mut fix = BTreeMap::new();
+            fix.insert("description".to_owned(), message(&suggestion.msg));
+            fix.insert("artifactChanges".to_owned(), Json::Array(changes));
+            fix.insert("properties".to_owned(), Json::Object(properties));
+            Some(Json::Object(fix))
+        }).collect()
+    }
+
+    /// The whole log, with the results emitted so far.
+    fn log(&self) -> Json {
+        let mut driver = BTreeMap::new();
+        driver.insert("name".to_owned(), "rustc".to_json());
+        driver.insert("informationUri".to_owned(), "https://www.rust-lang.org/".to_json());
+        if let Some(version) = option_env!("CFG_RELEASE") {
+            driver.insert("version".to_owned(), version.to_json());
+        }
+        driver.insert("rules".to_owned(),
+                      Json::Array(self.rules.iter().map(|id| self.rule(id)).collect()));
+        let mut tool = BTreeMap::new();
+        tool.insert("driver".to_owned(), Json::Object(driver));
+
+        let mut invocation = BTreeMap::new();
+        invocation.insert("executionSuccessful".to_owned(), (!self.failed).to_json());
+        invocation.insert("toolExecutionNotifications".to_owned(),
+                          Json::Array(self.notifications.clone()));
+
+        let mut run = BTreeMap::new();
+        run.insert("tool".to_owned(), Json::Object(tool));
+        run.insert("invocations".to_owned(), Json::Array(vec![Json::Object(invocation)]));
+        run.insert("columnKind".to_owned(), "unicodeCodePoints".to_json());
+        run.insert("results".to_owned(), Json::Array(self.results.clone()));
+
+        let mut log = BTreeMap::new();
+        log.insert("$schema".to_owned(), SCHEMA.to_json());
+        log.insert("version".to_owned(), "2.1.0".to_json());
+        log.insert("runs".to_owned(), Json::Array(vec![Json::Object(run)]));
+        Json::Object(log)
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit(&mut self, db: &DiagnosticBuilder) {
+        match db.level {
+            // Pointers to `rustc --explain`, whose explanations are in the
+            // rules already.
+            Level::FailureNote | Level::Cancelled => return,
+            Level::Bug | Level::Fatal | Level::PhaseFatal | Level::Error => self.failed = true,
+            Level::Warning | Level::Note | Level::Help => {}
+        }
+
+        let mut text = db.message();
+        let mut locations = vec![];
+        let mut related_locations = vec![];
+        for span_label in db.span.span_labels() {
+            if let Some(location) = self.location(span_label.span, span_label.label) {
+                if span_label.is_primary {
+                    locations.push(location);
+                } else {
+                    related_locations.push(location);
+                }
+            }
+        }
+        for child in &db.children {
+            let child_text = format!("{}: {}", child.level, child.message());
+            let span = child.render_span.as_ref().unwrap_or(&child.span);
+            let child_locations = span.primary_spans().iter().filter_map(|&sp| {
+                self.location(sp, Some(child_text.clone()))
+            }).collect::<Vec<_>>();
+            if child_locations.is_empty() {
+                text.push('\n');
+                text.push_str(&child_text);
+            } else {
+                related_locations.extend(child_locations);
+            }
+        }
+
+        let mut result = BTreeMap::new();
+        result.insert("level".to_owned(), level(db.level).to_json());
+        result.insert("message".to_owned(), message(&text));
+        if let Some(ref id) = db.code {
+            let rule_index = self.rule_index(id);
+            let rule_id = match *id {
+                DiagnosticId::Error(ref s) | DiagnosticId::Lint(ref s) => s,
+            };
+            result.insert("ruleId".to_owned(), rule_id.to_json());
+            result.insert("ruleIndex".to_owned(), rule_index.to_json());
+        }
+
+        if locations.is_empty() {
+            self.notifications.push(Json::Object(result));
+            return;
+        }
+
+        result.insert("locations".to_owned(), Json::Array(locations));
+        if !related_locations.is_empty() {
+            result.insert("relatedLocations".to_owned(), Json::Array(related_locations));
+        }
+        let fixes = db.suggestions.iter()
+                                  .flat_map(|suggestion| self.fixes(suggestion))
+                                  .collect::<Vec<_>>();
+        if !fixes.is_empty() {
+            result.insert("fixes".to_owned(), Json::Array(fixes));
+        }
+        self.results.push(Json::Object(result));
+    }
+
+    fn should_show_explain(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        if self.deferred {
+            shared().early.extend(self.notifications.drain(..));
+            return;
+        }
+        let log = self.log();
+        if let Err(e) = writeln!(&mut self.dst, "{}", log.pretty()) {
+            // The handler is usually dropped while unwinding from a fatal
+            // error, where panicking again would abort.
+            if !thread::panicking() {
+                panic!("failed to print diagnostics: {:?}", e);
+            }
+        }
+    }
+}
+
+fn level(level: Level) -> &'static str {
+    match level {
+        Level::Bug | Level::Fatal | Level::PhaseFatal | Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note | Level::Help | Level::Cancelled | Level::FailureNote => "note",
+    }
+}
+
+/// A SARIF message, or the content of a replacement, which are both objects
+/// with the text in `text`.
+fn message(s: &str) -> Json {
+    let mut message = BTreeMap::new();
+    message.insert("text".to_owned(), s.to_json());
+    Json::Object(message)
+}
+
+/// The location of a file, as a URI: relative paths are relative to the root
+/// of the sources, which SARIF calls `%SRCROOT%`.
+fn artifact_location(path: &Path) -> Json {
+    let mut uri = String::new();
+    let absolute = path.is_absolute();
+    let path = path.to_string_lossy().replace('\\', "/");
+    if absolute {
+        uri.push_str("file://");
+        // Windows paths start with the drive.
+        if !path.starts_with('/') {
+            uri.push('/');
+        }
+    }
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' |
+            b'-' | b'.' | b'_' | b'~' | b'/' | b':' => uri.push(b as char),
+            _ => write!(uri, "%{:02X}", b).unwrap(),
+        }
+    }
+
+    let mut location = BTreeMap::new();
+    location.insert("uri".to_owned(), uri.to_json());
+    if !absolute {
+        location.insert("uriBaseId".to_owned(), "%SRCROOT%".to_json());
+    }
+    Json::Object(location)
+}
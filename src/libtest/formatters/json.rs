@@ -12,11 +12,12 @@ use super::*;
 
 pub(crate) struct JsonFormatter<T> {
     out: OutputLocation<T>,
+    shuffle_seed: Option<u64>,
 }
 
 impl<T: Write> JsonFormatter<T> {
-    pub fn new(out: OutputLocation<T>) -> Self {
-        Self { out }
+    pub fn new(out: OutputLocation<T>, shuffle_seed: Option<u64>) -> Self {
+        Self { out, shuffle_seed }
     }
 
     fn write_message(&mut self, s: &str) -> io::Result<()> {
@@ -49,9 +50,13 @@ impl<T: Write> JsonFormatter<T> {
 
 impl<T: Write> OutputFormatter for JsonFormatter<T> {
     fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+        let shuffle_seed = match self.shuffle_seed {
+            Some(seed) => format!(r#", "shuffle_seed": {}"#, seed),
+            None => String::new(),
+        };
         self.write_message(&*format!(
-            r#"{{ "type": "suite", "event": "started", "test_count": "{}" }}"#,
-            test_count
+            r#"{{ "type": "suite", "event": "started", "test_count": "{}"{} }}"#,
+            test_count, shuffle_seed
         ))
     }
 
@@ -97,6 +102,13 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
                 self.write_event("test", desc.name.as_slice(), "allowed_failure", None)
             }
 
+            TrTimedOut(secs) => self.write_event(
+                "test",
+                desc.name.as_slice(),
+                "timed_out",
+                Some(format!(r#""timeout": {}"#, secs)),
+            ),
+
             TrBench(ref bs) => {
                 let median = bs.ns_iter_summ.median as usize;
                 let deviation = (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as usize;
@@ -128,25 +140,70 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
     }
 
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        let success = state.failed == 0 && state.regressions() == 0;
+        let baseline = match state.baseline {
+            Some((ref name, _)) => {
+                for (bench, change) in state.baseline_changes() {
+                    // A benchmark that used to take no time at all changed by
+                    // an infinite percentage, which JSON can't represent.
+                    let percent = if change.percent.is_finite() {
+                        format!("{:.2}", change.percent)
+                    } else {
+                        "null".to_owned()
+                    };
+                    self.write_message(&*format!(
+                        "{{ \"type\": \"bench\", \
+                         \"event\": \"compared\", \
+                         \"name\": \"{}\", \
+                         \"baseline\": \"{}\", \
+                         \"old_median\": {}, \
+                         \"new_median\": {}, \
+                         \"change_percent\": {}, \
+                         \"confidence\": \"{}\", \
+                         \"regressed\": {} }}",
+                        EscapedString(bench),
+                        EscapedString(name),
+                        change.old as usize,
+                        change.new as usize,
+                        percent,
+                        change.confidence.as_str(),
+                        state.is_regression(&change)
+                    ))?;
+                }
+                format!(r#", "regressed": {}"#, state.regressions())
+            }
+            None => String::new(),
+        };
+        let shard = match state.shard {
+            Some(shard) => format!(
+                r#", "shard_index": {}, "shard_count": {}, "in_other_shards": {}"#,
+                shard.index, shard.count, state.in_other_shards
+            ),
+            None => String::new(),
+        };
         self.write_message(&*format!(
             "{{ \"type\": \"suite\", \
              \"event\": \"{}\", \
              \"passed\": {}, \
              \"failed\": {}, \
              \"allowed_fail\": {}, \
+             \"timed_out\": {}, \
              \"ignored\": {}, \
              \"measured\": {}, \
-             \"filtered_out\": \"{}\" }}",
-            if state.failed == 0 { "ok" } else { "failed" },
+             \"filtered_out\": \"{}\"{}{} }}",
+            if success { "ok" } else { "failed" },
             state.passed,
             state.failed + state.allowed_fail,
             state.allowed_fail,
+            state.timed_out,
             state.ignored,
             state.measured,
-            state.filtered_out
+            state.filtered_out,
+            baseline,
+            shard
         ))?;
 
-        Ok(state.failed == 0)
+        Ok(success)
     }
 }
 
@@ -0,0 +1,215 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::*;
+
+use std::collections::HashMap;
+
+/// The outcome of a single test, as recorded in the report.
+enum Outcome {
+    Passed,
+    Failed(Option<String>),
+    Skipped(&'static str),
+}
+
+struct TestCase {
+    name: String,
+    duration: Duration,
+    outcome: Outcome,
+    stdout: Vec<u8>,
+}
+
+/// Writes a JUnit XML report.
+///
+/// The `testsuite` element carries the totals of the run, so nothing can be
+/// written before the last test finished: results are collected as they come
+/// in, and the whole document is written by `write_run_finish`.
+pub(crate) struct JunitFormatter<T> {
+    out: OutputLocation<T>,
+    suite_name: String,
+    run_start: Option<Instant>,
+    started: HashMap<TestDesc, Instant>,
+    results: Vec<TestCase>,
+}
+
+impl<T: Write> JunitFormatter<T> {
+    pub fn new(out: OutputLocation<T>, suite_name: String) -> Self {
+        JunitFormatter {
+            out,
+            suite_name,
+            run_start: None,
+            started: HashMap::new(),
+            results: Vec::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
+    fn write_line(&mut self, s: &str) -> io::Result<()> {
+        self.out.write_all(s.as_bytes())?;
+        self.out.write_all(b"\n")
+    }
+}
+
+impl<T: Write> OutputFormatter for JunitFormatter<T> {
+    fn write_run_start(&mut self, _test_count: usize) -> io::Result<()> {
+        self.run_start = Some(Instant::now());
+        Ok(())
+    }
+
+    fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.started.insert(desc.clone(), Instant::now());
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // A slow test is only a warning; its result will still come in.
+        Ok(())
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        stdout: &[u8],
+    ) -> io::Result<()> {
+        let duration = match self.started.remove(desc) {
+            Some(start) => start.elapsed(),
+            None => Duration::new(0, 0),
+        };
+        let mut stdout = stdout.to_vec();
+        let outcome = match *result {
+            TrOk => Outcome::Passed,
+            TrFailed => Outcome::Failed(None),
+            TrFailedMsg(ref msg) => Outcome::Failed(Some(msg.clone())),
+            TrIgnored => Outcome::Skipped("ignored"),
+            TrAllowedFail => Outcome::Skipped("failed (allowed)"),
+            TrTimedOut(secs) => {
+                Outcome::Failed(Some(format!("test did not finish within {} seconds", secs)))
+            }
+            TrBench(ref bs) => {
+                stdout.extend_from_slice(fmt_bench_samples(bs).trim().as_bytes());
+                Outcome::Passed
+            }
+        };
+        self.results.push(TestCase {
+            name: desc.name.as_slice().to_owned(),
+            duration,
+            outcome,
+            stdout,
+        });
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        let elapsed = match self.run_start {
+            Some(start) => start.elapsed(),
+            None => Duration::new(0, 0),
+        };
+        let results = ::std::mem::replace(&mut self.results, Vec::new());
+        let failures = results.iter().filter(|t| match t.outcome {
+            Outcome::Failed(_) => true,
+            _ => false,
+        }).count();
+        let skipped = results.iter().filter(|t| match t.outcome {
+            Outcome::Skipped(_) => true,
+            _ => false,
+        }).count();
+
+        self.write_line(r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        self.write_line("<testsuites>")?;
+        let suite = format!(
+            r#"<testsuite name="{}" errors="0" failures="{}" skipped="{}" tests="{}" time="{}">"#,
+            EscapedXml(&self.suite_name),
+            failures,
+            skipped,
+            results.len(),
+            fmt_seconds(elapsed)
+        );
+        self.write_line(&suite)?;
+
+        for test in &results {
+            // `a::b::c` is reported as test `c` of class `a::b`.
+            let (class, name) = match test.name.rfind("::") {
+                Some(i) => (&test.name[..i], &test.name[i + 2..]),
+                None => (&self.suite_name[..], &test.name[..]),
+            };
+            let case = format!(
+                r#"<testcase classname="{}" name="{}" time="{}""#,
+                EscapedXml(class),
+                EscapedXml(name),
+                fmt_seconds(test.duration)
+            );
+
+            let mut body = Vec::new();
+            match test.outcome {
+                Outcome::Passed => {}
+                Outcome::Failed(Some(ref msg)) => body.push(format!(
+                    r#"<failure type="assert" message="{}"/>"#,
+                    EscapedXml(msg)
+                )),
+                Outcome::Failed(None) => body.push(r#"<failure type="assert"/>"#.to_owned()),
+                Outcome::Skipped(why) => body.push(format!(r#"<skipped message="{}"/>"#, why)),
+            }
+            if !test.stdout.is_empty() {
+                body.push(format!(
+                    "<system-out>{}</system-out>",
+                    EscapedXml(String::from_utf8_lossy(&test.stdout))
+                ));
+            }
+
+            if body.is_empty() {
+                self.write_line(&format!("{}/>", case))?;
+            } else {
+                self.write_line(&format!("{}>", case))?;
+                for line in &body {
+                    self.write_line(line)?;
+                }
+                self.write_line("</testcase>")?;
+            }
+        }
+
+        self.write_line("</testsuite>")?;
+        self.write_line("</testsuites>")?;
+
+        Ok(state.failed == 0 && state.regressions() == 0)
+    }
+}
+
+/// Seconds with millisecond precision, as JUnit consumers expect.
+fn fmt_seconds(d: Duration) -> String {
+    format!("{}.{:03}", d.as_secs(), d.subsec_nanos() / 1_000_000)
+}
+
+/// A formatting utility used to print strings as XML attribute values or
+/// character data.
+struct EscapedXml<S: AsRef<str>>(S);
+
+impl<S: AsRef<str>> ::std::fmt::Display for EscapedXml<S> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        for c in self.0.as_ref().chars() {
+            match c {
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '&' => f.write_str("&amp;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                '\t' | '\n' | '\r' => write!(f, "&#{};", c as u32)?,
+                // Other control characters can't appear in XML 1.0 at all.
+                c if c < ' ' => f.write_str("\u{fffd}")?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
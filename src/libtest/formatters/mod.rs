@@ -13,10 +13,12 @@ use super::*;
 mod pretty;
 mod json;
 mod terse;
+mod junit;
 
 pub(crate) use self::pretty::PrettyFormatter;
 pub(crate) use self::json::JsonFormatter;
 pub(crate) use self::terse::TerseFormatter;
+pub(crate) use self::junit::JunitFormatter;
 
 pub(crate) trait OutputFormatter {
     fn write_run_start(&mut self, test_count: usize) -> io::Result<()>;
@@ -30,3 +32,66 @@ pub(crate) trait OutputFormatter {
     ) -> io::Result<()>;
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool>;
 }
+
+/// The failure count of the summary line, along with how many of the failures
+/// were allowed or timed out: `3 failed (1 allowed, 1 timed out)`.
+pub(crate) fn fmt_failed(state: &ConsoleTestState) -> String {
+    let mut notes = Vec::new();
+    if state.allowed_fail > 0 {
+        notes.push(format!("{} allowed", state.allowed_fail));
+    }
+    if state.timed_out > 0 {
+        notes.push(format!("{} timed out", state.timed_out));
+    }
+
+    let failed = state.failed + state.allowed_fail;
+    if notes.is_empty() {
+        format!("{} failed", failed)
+    } else {
+        format!("{} failed ({})", failed, notes.join(", "))
+    }
+}
+
+/// The benchmarks of this run compared with the ones of the `--baseline`, or
+/// `None` if there is no baseline:
+///
+/// ```text
+/// benchmarks compared with baseline `main`:
+///     bench_a       1,200 ->       1,500 ns/iter  +25.00% (high confidence) REGRESSED
+/// ```
+pub(crate) fn fmt_baseline_comparison(state: &ConsoleTestState) -> Option<String> {
+    let baseline = match state.baseline {
+        Some((ref name, _)) => name,
+        None => return None,
+    };
+    let changes = state.baseline_changes();
+    let name_len = changes.keys().map(|name| name.len()).max().unwrap_or(0);
+
+    let mut s = format!("\nbenchmarks compared with baseline `{}`:\n", baseline);
+    if changes.is_empty() {
+        s.push_str("    none of the benchmarks are in the baseline\n");
+    }
+    for (name, change) in &changes {
+        s.push_str(&format!(
+            "    {:<width$} {:>11} -> {:>11} ns/iter  {:+.2}% ({} confidence){}\n",
+            name,
+            fmt_thousands_sep(change.old as usize, ','),
+            fmt_thousands_sep(change.new as usize, ','),
+            change.percent,
+            change.confidence.as_str(),
+            if state.is_regression(change) { " REGRESSED" } else { "" },
+            width = name_len
+        ));
+    }
+
+    let regressions = state.regressions();
+    if regressions > 0 {
+        s.push_str(&format!(
+            "\n{} {} slower than the baseline by more than {}%\n",
+            regressions,
+            if regressions == 1 { "benchmark got" } else { "benchmarks got" },
+            state.regression_threshold
+        ));
+    }
+    Some(s)
+}
@@ -48,6 +48,10 @@ impl<T: Write> TerseFormatter<T> {
         self.write_short_result("i", term::color::YELLOW)
     }
 
+    pub fn write_timed_out(&mut self) -> io::Result<()> {
+        self.write_short_result("T", term::color::RED)
+    }
+
     pub fn write_allowed_fail(&mut self) -> io::Result<()> {
         self.write_short_result("a", term::color::YELLOW)
     }
@@ -182,6 +186,7 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
             TrFailed | TrFailedMsg(_) => self.write_failed(),
             TrIgnored => self.write_ignored(),
             TrAllowedFail => self.write_allowed_fail(),
+            TrTimedOut(_) => self.write_timed_out(),
             TrBench(ref bs) => {
                 if self.is_multithreaded {
                     self.write_test_name(desc)?;
@@ -203,11 +208,23 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
         if state.options.display_output {
             self.write_outputs(state)?;
         }
-        let success = state.failed == 0;
-        if !success {
+        let success = state.failed == 0 && state.regressions() == 0;
+        if state.failed != 0 {
             self.write_failures(state)?;
         }
 
+        if let Some(comparison) = fmt_baseline_comparison(state) {
+            self.write_plain(&comparison)?;
+        }
+
+        if let Some(seed) = state.shuffle_seed {
+            self.write_plain(&format!(
+                "\ntests ran in a random order, rerun with --shuffle-seed {} \
+                 to get the same order\n",
+                seed
+            ))?;
+        }
+
         self.write_plain("\ntest result: ")?;
 
         if success {
@@ -217,22 +234,21 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
             self.write_pretty("FAILED", term::color::RED)?;
         }
 
-        let s = if state.allowed_fail > 0 {
-            format!(
-                ". {} passed; {} failed ({} allowed); {} ignored; {} measured; {} filtered out\n\n",
-                state.passed,
-                state.failed + state.allowed_fail,
-                state.allowed_fail,
-                state.ignored,
-                state.measured,
-                state.filtered_out
-            )
-        } else {
-            format!(
-                ". {} passed; {} failed; {} ignored; {} measured; {} filtered out\n\n",
-                state.passed, state.failed, state.ignored, state.measured, state.filtered_out
-            )
-        };
+        let mut s = format!(
+            ". {} passed; {}; {} ignored; {} measured; {} filtered out",
+            state.passed,
+            fmt_failed(state),
+            state.ignored,
+            state.measured,
+            state.filtered_out
+        );
+        if let Some(shard) = state.shard {
+            s.push_str(&format!(
+                "; {} in other shards (this is shard {} of {})",
+                state.in_other_shards, shard.index, shard.count
+            ));
+        }
+        s.push_str("\n\n");
 
         self.write_plain(&s)?;
 
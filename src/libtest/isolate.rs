@@ -0,0 +1,324 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Running tests in a process of their own: every test with `--isolate`, and
+//! otherwise the tests with a time limit, so that they can be killed.
+//!
+//! The harness re-executes its own binary with `RUST_TEST_ISOLATED_TEST` set
+//! to the name of the test to run. The child runs that single test the usual
+//! way, then prints the result after a marker on its standard output, where
+//! the parent picks it up. A child that exits without printing a result has
+//! crashed (it aborted, was killed by a signal or called `process::exit`), and
+//! the test is reported as failed with the child's exit status.
+
+use super::*;
+
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::str;
+
+const TEST_VAR: &'static str = "RUST_TEST_ISOLATED_TEST";
+
+/// Precedes the result printed by a child. It starts with a newline so that
+/// it can be found even after uncaptured output that doesn't end with one.
+const MARKER: &'static [u8] = b"\n#rust-test-result ";
+
+/// The name of the test to run, if this process was started by a harness
+/// running with `--isolate`.
+pub(crate) fn child_test() -> Option<String> {
+    let name = env::var(TEST_VAR).ok()?;
+    // Don't let the test see it, nor pass it on to the processes it starts.
+    env::remove_var(TEST_VAR);
+    Some(name)
+}
+
+/// Runs the test called `name` and prints its result for the parent harness.
+pub(crate) fn run_child(opts: &TestOpts, tests: Vec<TestDescAndFn>, name: &str) -> io::Result<()> {
+    let test = match tests.into_iter().find(|t| t.desc.name.as_slice() == name) {
+        Some(test) => test,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no test named `{}`", name),
+            ))
+        }
+    };
+    let mut test = convert_benchmarks_to_tests(vec![test]).pop().unwrap();
+    // The parent already decided to run this test, even if it is ignored.
+    test.desc.ignore = false;
+
+    let (tx, rx) = channel();
+    run_test(opts, false, test, tx);
+    let (_, result, stdout) = rx.recv().unwrap();
+
+    let (kind, msg) = match result {
+        TrOk => ("ok", String::new()),
+        TrFailed => ("failed", String::new()),
+        TrFailedMsg(msg) => ("failed_msg", msg),
+        TrIgnored => ("ignored", String::new()),
+        TrAllowedFail => ("allowed_fail", String::new()),
+        TrBench(_) | TrTimedOut(_) => unreachable!(),
+    };
+
+    let out = io::stdout();
+    let mut out = out.lock();
+    out.write_all(MARKER)?;
+    writeln!(out, "{} {} {}", kind, msg.len(), stdout.len())?;
+    out.write_all(msg.as_bytes())?;
+    out.write_all(&stdout)?;
+    out.flush()
+}
+
+/// Starts `test` in a child process, and sends its result to `monitor_ch`
+/// once the child exits.
+///
+/// Returns the child, so that it can be killed if it runs out of time.
+pub(crate) fn spawn_test(
+    opts: &TestOpts,
+    test: TestDescAndFn,
+    monitor_ch: Sender<MonitorMsg>,
+) -> Option<Arc<Mutex<Child>>> {
+    let desc = test.desc;
+    if !opts.run_tests || desc.ignore {
+        monitor_ch.send((desc, TrIgnored, Vec::new())).unwrap();
+        return None;
+    }
+
+    let mut cmd = match env::current_exe() {
+        Ok(exe) => Command::new(exe),
+        Err(e) => {
+            let msg = format!("could not find the test binary: {}", e);
+            monitor_ch.send((desc, TrFailedMsg(msg), Vec::new())).unwrap();
+            return None;
+        }
+    };
+    cmd.env(TEST_VAR, desc.name.as_slice())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped());
+    if opts.nocapture {
+        cmd.arg("--nocapture").stderr(Stdio::inherit());
+    } else {
+        cmd.stderr(Stdio::piped());
+    }
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let msg = format!("could not start the test process: {}", e);
+            monitor_ch.send((desc, TrFailedMsg(msg), Vec::new())).unwrap();
+            return None;
+        }
+    };
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let child = Arc::new(Mutex::new(child));
+    let waited = child.clone();
+    let nocapture = opts.nocapture;
+
+    let cfg = thread::Builder::new().name(desc.name.as_slice().to_owned());
+    cfg.spawn(move || {
+        // Drain stderr on its own thread, so that a child filling up one pipe
+        // can't block while we wait on the other one.
+        let stderr = stderr.map(|mut stderr| {
+            thread::spawn(move || {
+                let mut buf = Vec::new();
+                let _ = stderr.read_to_end(&mut buf);
+                buf
+            })
+        });
+        let mut out = Vec::new();
+        if let Some(mut stdout) = stdout {
+            let _ = stdout.read_to_end(&mut out);
+        }
+        let err = stderr
+            .and_then(|stderr| stderr.join().ok())
+            .unwrap_or_default();
+
+        // The pipes are closed, so the child is gone or about to be. It could
+        // still hang after closing them, so poll rather than wait while
+        // holding the lock, which would keep a timeout from killing it.
+        let status = loop {
+            match waited.lock().unwrap().try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => {}
+                Err(e) => break Err(e),
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        let (result, mut output) = parse_output(&desc, out, nocapture, status);
+        output.extend_from_slice(&err);
+        monitor_ch.send((desc, result, output)).unwrap();
+    }).unwrap();
+
+    Some(child)
+}
+
+/// Extracts the result printed by a child from its standard output `out`,
+/// along with the output captured while the test ran.
+fn parse_output(
+    desc: &TestDesc,
+    out: Vec<u8>,
+    nocapture: bool,
+    status: io::Result<ExitStatus>,
+) -> (TestResult, Vec<u8>) {
+    // The captured output could contain the marker too, so look for the last
+    // one that is followed by a well-formed record.
+    let parsed = out.windows(MARKER.len())
+        .enumerate()
+        .rev()
+        .filter(|&(_, w)| w == MARKER)
+        .filter_map(|(start, _)| {
+            parse_record(&out[start + MARKER.len()..]).map(|record| (start, record))
+        })
+        .next();
+    let (start, result, captured) = match parsed {
+        Some((start, (result, captured))) => (start, result, captured),
+        None => {
+            let msg = crash_message(status);
+            let result = if desc.allow_fail {
+                TrAllowedFail
+            } else {
+                TrFailedMsg(msg)
+            };
+            return (result, out);
+        }
+    };
+
+    // Whatever came before the result wasn't captured by the child, so it
+    // was meant to be seen right away.
+    let uncaptured = &out[..start];
+    if nocapture {
+        let _ = io::stdout().write_all(uncaptured);
+        (result, captured.to_vec())
+    } else {
+        let mut output = uncaptured.to_vec();
+        output.extend_from_slice(captured);
+        (result, output)
+    }
+}
+
+/// Parses `<kind> <message length> <output length>\n<message><captured output>`,
+/// which must span all of `record`.
+fn parse_record(record: &[u8]) -> Option<(TestResult, &[u8])> {
+    let newline = record.iter().position(|&b| b == b'\n')?;
+    let header = str::from_utf8(&record[..newline]).ok()?;
+    let mut parts = header.split(' ');
+    let kind = parts.next()?;
+    let msg_len: usize = parts.next()?.parse().ok()?;
+    let captured_len: usize = parts.next()?.parse().ok()?;
+
+    let rest = &record[newline + 1..];
+    if Some(rest.len()) != msg_len.checked_add(captured_len) {
+        return None;
+    }
+    let (msg, captured) = rest.split_at(msg_len);
+    let result = match kind {
+        "ok" => TrOk,
+        "failed" => TrFailed,
+        "failed_msg" => TrFailedMsg(String::from_utf8_lossy(msg).into_owned()),
+        "ignored" => TrIgnored,
+        "allowed_fail" => TrAllowedFail,
+        _ => return None,
+    };
+    Some((result, captured))
+}
+
+fn crash_message(status: io::Result<ExitStatus>) -> String {
+    let status = match status {
+        Ok(status) => status,
+        Err(e) => return format!("could not wait for the test process: {}", e),
+    };
+    if let Some(code) = status.code() {
+        return format!("test process exited with code {} without reporting a result", code);
+    }
+    match signal(&status) {
+        Some(signal) => format!("test process was killed by signal {}", signal),
+        None => format!("test process crashed ({})", status),
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn desc() -> TestDesc {
+        TestDesc {
+            name: StaticTestName("a"),
+            ignore: false,
+            should_panic: ShouldPanic::No,
+            allow_fail: false,
+            timeout: None,
+        }
+    }
+
+    fn record(kind: &str, msg: &str, captured: &str) -> Vec<u8> {
+        let mut out = MARKER.to_vec();
+        let record = format!("{} {} {}\n{}{}", kind, msg.len(), captured.len(), msg, captured);
+        out.extend_from_slice(record.as_bytes());
+        out
+    }
+
+    #[test]
+    fn parse_child_results() {
+        let out = record("ok", "", "captured");
+        let (result, output) = parse_output(&desc(), out, false, Err(io::ErrorKind::Other.into()));
+        assert!(result == TrOk);
+        assert_eq!(output, b"captured");
+
+        let out = record("failed", "", "\n#rust-test-result ok 0 0\nmore");
+        let (result, output) = parse_output(&desc(), out, false, Err(io::ErrorKind::Other.into()));
+        assert!(result == TrFailed);
+        assert_eq!(output, b"\n#rust-test-result ok 0 0\nmore");
+
+        let mut out = b"early\n#rust-test-result is not a marker".to_vec();
+        out.extend(record("failed_msg", "line 1\nline 2", ""));
+        let (result, output) = parse_output(&desc(), out, false, Err(io::ErrorKind::Other.into()));
+        assert!(result == TrFailedMsg("line 1\nline 2".to_owned()));
+        assert_eq!(output, b"early\n#rust-test-result is not a marker");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn report_crashes() {
+        use std::os::unix::process::ExitStatusExt;
+
+        // Killed by SIGABRT, as when a test panics with panic=abort.
+        let status = ExitStatus::from_raw(6);
+        let (result, _) = parse_output(&desc(), b"partial".to_vec(), false, Ok(status));
+        assert!(result == TrFailedMsg("test process was killed by signal 6".to_owned()));
+
+        // process::exit(3)
+        let status = ExitStatus::from_raw(3 << 8);
+        let (result, _) = parse_output(&desc(), Vec::new(), false, Ok(status));
+        assert!(
+            result
+                == TrFailedMsg(
+                    "test process exited with code 3 without reporting a result".to_owned()
+                )
+        );
+
+        let mut allowed = desc();
+        allowed.allow_fail = true;
+        let (result, _) = parse_output(&allowed, Vec::new(), false, Ok(status));
+        assert!(result == TrAllowedFail);
+    }
+}
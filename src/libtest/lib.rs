@@ -61,11 +61,11 @@ use std::cmp;
 use std::collections::BTreeMap;
 use std::env;
 use std::fmt;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io;
-use std::path::PathBuf;
-use std::process::Termination;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Termination};
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -75,19 +75,22 @@ use std::process;
 
 const TEST_WARN_TIMEOUT_S: u64 = 60;
 const QUIET_MODE_MAX_COLUMN: usize = 100; // insert a '\n' after 100 tests in quiet mode
+const DEFAULT_REGRESSION_THRESHOLD: f64 = 5.0; // percent, see --regression-threshold
 
 // to be used by rustc to compile tests in libtest
 pub mod test {
     pub use {assert_test_result, filter_tests, parse_opts, run_test, test_main, test_main_static,
-             Bencher, DynTestFn, DynTestName, Metric, MetricMap, Options, ShouldPanic,
-             StaticBenchFn, StaticTestFn, StaticTestName, TestDesc, TestDescAndFn, TestName,
-             TestOpts, TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk};
+             Bencher, Confidence, DynTestFn, DynTestName, Metric, MetricChange, MetricMap,
+             Options, ShouldPanic,
+             Shard, StaticBenchFn, StaticTestFn, StaticTestName, TestDesc, TestDescAndFn, TestName,
+             TestOpts, TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk, TrTimedOut};
 }
 
 pub mod stats;
 mod formatters;
+mod isolate;
 
-use formatters::{JsonFormatter, OutputFormatter, PrettyFormatter, TerseFormatter};
+use formatters::{JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TerseFormatter};
 
 // The name of a test. By convention this follows the rules for rust
 // paths; i.e. it should be a series of identifiers separated by double
@@ -224,6 +227,9 @@ pub struct TestDesc {
     pub ignore: bool,
     pub should_panic: ShouldPanic,
     pub allow_fail: bool,
+    /// Number of seconds after which the test is killed and reported as timed
+    /// out, overriding `--test-timeout`.
+    pub timeout: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -244,6 +250,41 @@ impl Metric {
     }
 }
 
+/// How likely it is that a difference between two measurements of a metric is
+/// real, rather than noise.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    /// The difference is within the noise of the measurements.
+    Low,
+    /// The difference is larger than the noise, but less than twice as large.
+    Medium,
+    /// The difference is at least twice as large as the noise.
+    High,
+}
+
+impl Confidence {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        }
+    }
+}
+
+/// How a metric changed from one run to the next, see
+/// `MetricMap::compare_to_old`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MetricChange {
+    pub old: f64,
+    pub new: f64,
+    /// The difference, in percent of the old value.
+    pub percent: f64,
+    pub confidence: Confidence,
+    /// Whether the metric moved in the direction it shouldn't have.
+    pub worse: bool,
+}
+
 /// In case we want to add other options as well, just add them in this struct.
 #[derive(Copy, Clone, Debug)]
 pub struct Options {
@@ -276,6 +317,13 @@ pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Options) {
     };
 
     opts.options = options;
+    if let Some(name) = isolate::child_test() {
+        if let Err(e) = isolate::run_child(&opts, tests, &name) {
+            eprintln!("error: {}", e);
+            process::exit(101);
+        }
+        return;
+    }
     if opts.list {
         if let Err(e) = list_tests_console(&opts, tests) {
             eprintln!("error: io error when listing tests: {:?}", e);
@@ -338,6 +386,14 @@ pub enum OutputFormat {
     Pretty,
     Terse,
     Json,
+    Junit,
+}
+
+/// One of `count` disjoint parts of a test suite, see `--shard-index`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
 }
 
 #[derive(Debug)]
@@ -353,6 +409,19 @@ pub struct TestOpts {
     pub color: ColorConfig,
     pub format: OutputFormat,
     pub test_threads: Option<usize>,
+    pub test_timeout: Option<u64>,
+    pub isolate: bool,
+    /// If set, tests run in a random order determined by this seed.
+    pub shuffle_seed: Option<u64>,
+    pub shard: Option<Shard>,
+    /// Save the benchmark results of this run under this name.
+    pub save_baseline: Option<String>,
+    /// Compare the benchmark results of this run with the ones saved under
+    /// this name.
+    pub baseline: Option<String>,
+    /// How much slower than the baseline, in percent, a benchmark may get
+    /// before the run fails.
+    pub regression_threshold: f64,
     pub skip: Vec<String>,
     pub options: Options,
 }
@@ -372,6 +441,13 @@ impl TestOpts {
             color: AutoColor,
             format: OutputFormat::Pretty,
             test_threads: None,
+            test_timeout: None,
+            isolate: false,
+            shuffle_seed: None,
+            shard: None,
+            save_baseline: None,
+            baseline: None,
+            regression_threshold: DEFAULT_REGRESSION_THRESHOLD,
             skip: vec![],
             options: Options::new(),
         }
@@ -408,6 +484,68 @@ fn optgroups() -> getopts::Options {
              in parallel",
             "n_threads",
         )
+        .optopt(
+            "",
+            "test-timeout",
+            "Fail tests that run for longer than this many seconds. \
+             Tests with a time limit run in a process of their own, \
+             which is killed when it runs out (unstable)",
+            "SECONDS",
+        )
+        .optflag(
+            "",
+            "isolate",
+            "Run each test in a process of its own, so that a test \
+             crashing doesn't take the others down (unstable)",
+        )
+        .optflag(
+            "",
+            "shuffle",
+            "Run tests in a random order, printing the seed used \
+             (unstable)",
+        )
+        .optopt(
+            "",
+            "shuffle-seed",
+            "Run tests in the random order given by this seed, as \
+             printed by an earlier run with --shuffle (unstable)",
+            "SEED",
+        )
+        .optopt(
+            "",
+            "shard-index",
+            "Only run the tests of this shard, numbered from 0 \
+             (unstable, requires --shard-count)",
+            "INDEX",
+        )
+        .optopt(
+            "",
+            "shard-count",
+            "Split the tests into this many shards (unstable, \
+             requires --shard-index)",
+            "COUNT",
+        )
+        .optopt(
+            "",
+            "save-baseline",
+            "Save the benchmark results under this name, to compare \
+             later runs against (unstable)",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline",
+            "Compare the benchmark results with the ones saved under \
+             this name (unstable)",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "regression-threshold",
+            "Fail if a benchmark got slower than the baseline by more \
+             than this many percent (unstable, default 5)",
+            "PERCENT",
+        )
         .optmulti(
             "",
             "skip",
@@ -441,8 +579,9 @@ fn optgroups() -> getopts::Options {
             "Configure formatting of output:
             pretty = Print verbose output;
             terse  = Display one character per test;
-            json   = Output a json document",
-            "pretty|terse|json",
+            json   = Output a json document;
+            junit  = Output a JUnit XML report",
+            "pretty|terse|json|junit",
         )
         .optopt(
             "Z",
@@ -483,7 +622,13 @@ Test Attributes:
     #[ignore]      - When applied to a function which is already attributed as a
                      test, then the test runner will ignore these tests during
                      normal test runs. Running with --ignored will run these
-                     tests."#,
+                     tests.
+    #[test_timeout = "N"] - Fail the test if it is still running after N
+                     seconds, whatever the value of --test-timeout.
+
+Benchmark baselines are saved in the directory named by RUST_BENCH_BASELINE_DIR,
+or in the bench-baselines directory of CARGO_TARGET_DIR if it isn't set. One of
+them must be set to an absolute path to use --baseline or --save-baseline."#,
         usage = options.usage(&message)
     );
 }
@@ -570,6 +715,114 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         None => None,
     };
 
+    let test_timeout = match matches.opt_str("test-timeout") {
+        Some(_) if !allow_unstable => {
+            return Some(Err(
+                "The \"test-timeout\" flag is only accepted on the nightly compiler".into(),
+            ))
+        }
+        Some(n_str) => match n_str.parse::<u64>() {
+            Ok(0) => return Some(Err("argument for --test-timeout must not be 0".to_string())),
+            Ok(n) => Some(n),
+            Err(e) => {
+                return Some(Err(format!(
+                    "argument for --test-timeout must be a number > 0 \
+                     (error: {})",
+                    e
+                )))
+            }
+        },
+        None => None,
+    };
+
+    let isolate = matches.opt_present("isolate");
+    if isolate && !allow_unstable {
+        return Some(Err(
+            "The \"isolate\" flag is only accepted on the nightly compiler".into(),
+        ));
+    }
+
+    let shuffle_seed = match matches.opt_str("shuffle-seed") {
+        Some(_) if !allow_unstable => {
+            return Some(Err(
+                "The \"shuffle-seed\" flag is only accepted on the nightly compiler".into(),
+            ))
+        }
+        Some(n_str) => match n_str.parse::<u64>() {
+            Ok(n) => Some(n),
+            Err(e) => {
+                return Some(Err(format!(
+                    "argument for --shuffle-seed must be a number \
+                     (error: {})",
+                    e
+                )))
+            }
+        },
+        None if matches.opt_present("shuffle") => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"shuffle\" flag is only accepted on the nightly compiler".into(),
+                ));
+            }
+            Some(random_seed())
+        }
+        None => None,
+    };
+
+    let shard = match (matches.opt_str("shard-index"), matches.opt_str("shard-count")) {
+        (None, None) => None,
+        (Some(_), Some(_)) if !allow_unstable => {
+            return Some(Err(
+                "The \"shard-index\" and \"shard-count\" flags are only accepted on the \
+                 nightly compiler".into(),
+            ))
+        }
+        (Some(index), Some(count)) => match (index.parse::<usize>(), count.parse::<usize>()) {
+            (Ok(index), Ok(count)) if index < count => Some(Shard { index, count }),
+            _ => {
+                return Some(Err(format!(
+                    "arguments for --shard-index and --shard-count must be numbers \
+                     with 0 <= INDEX < COUNT (were {} and {})",
+                    index, count
+                )))
+            }
+        },
+        _ => {
+            return Some(Err(
+                "--shard-index and --shard-count must be used together".into(),
+            ))
+        }
+    };
+
+    let save_baseline = match baseline_name(&matches, "save-baseline", allow_unstable) {
+        Ok(name) => name,
+        Err(e) => return Some(Err(e)),
+    };
+    let baseline = match baseline_name(&matches, "baseline", allow_unstable) {
+        Ok(name) => name,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let regression_threshold = match matches.opt_str("regression-threshold") {
+        Some(_) if !allow_unstable => {
+            return Some(Err(
+                "The \"regression-threshold\" flag is only accepted on the nightly compiler"
+                    .into(),
+            ))
+        }
+        Some(n_str) => match n_str.parse::<f64>() {
+            Ok(n) if n >= 0.0 => n,
+            _ => {
+                return Some(Err(format!(
+                    "argument for --regression-threshold must be a non-negative \
+                     number (was {})",
+                    n_str
+                )))
+            }
+        },
+        None => DEFAULT_REGRESSION_THRESHOLD,
+    };
+
     let color = match matches.opt_str("color").as_ref().map(|s| &**s) {
         Some("auto") | None => AutoColor,
         Some("always") => AlwaysColor,
@@ -596,10 +849,18 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
             }
             OutputFormat::Json
         }
+        Some("junit") => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"junit\" format is only accepted on the nightly compiler".into(),
+                ));
+            }
+            OutputFormat::Junit
+        }
 
         Some(v) => {
             return Some(Err(format!(
-                "argument for --format must be pretty, terse, or json (was \
+                "argument for --format must be pretty, terse, json, or junit (was \
                  {})",
                 v
             )))
@@ -618,6 +879,13 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         color,
         format,
         test_threads,
+        test_timeout,
+        isolate,
+        shuffle_seed,
+        shard,
+        save_baseline,
+        baseline,
+        regression_threshold,
         skip: matches.opt_strs("skip"),
         options: Options::new(),
     };
@@ -625,6 +893,53 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
     Some(Ok(test_opts))
 }
 
+/// The baseline named by `--<flag>`. Names are used as file names, so they
+/// can't contain path separators.
+fn baseline_name(
+    matches: &getopts::Matches,
+    flag: &str,
+    allow_unstable: bool,
+) -> Result<Option<String>, String> {
+    match matches.opt_str(flag) {
+        Some(_) if !allow_unstable => Err(format!(
+            "The \"{}\" flag is only accepted on the nightly compiler",
+            flag
+        )),
+        Some(ref name) if name.is_empty() || name.contains(|c| c == '/' || c == '\\') => Err(
+            format!("argument for --{} must be a name, not a path (was {})", flag, name),
+        ),
+        name => Ok(name),
+    }
+}
+
+/// Where the benchmark baseline called `name` is saved.
+///
+/// Benchmarks run in the directory of their package, which may be anywhere
+/// below the workspace and its target directory, so the directory must be
+/// given by an absolute path.
+fn baseline_path(name: &str) -> io::Result<PathBuf> {
+    let dir = match env::var_os("RUST_BENCH_BASELINE_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => match env::var_os("CARGO_TARGET_DIR") {
+            Some(dir) => Path::new(&dir).join("bench-baselines"),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "set RUST_BENCH_BASELINE_DIR or CARGO_TARGET_DIR to say where \
+                     benchmark baselines are saved",
+                ))
+            }
+        },
+    };
+    if !dir.is_absolute() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("the benchmark baseline directory `{}` isn't an absolute path", dir.display()),
+        ));
+    }
+    Ok(dir.join(name))
+}
+
 #[derive(Clone, PartialEq)]
 pub struct BenchSamples {
     ns_iter_summ: stats::Summary,
@@ -639,6 +954,9 @@ pub enum TestResult {
     TrIgnored,
     TrAllowedFail,
     TrBench(BenchSamples),
+    /// The test was still running after the given number of seconds, and its
+    /// process was killed.
+    TrTimedOut(u64),
 }
 
 unsafe impl Send for TestResult {}
@@ -671,11 +989,18 @@ struct ConsoleTestState {
     failed: usize,
     ignored: usize,
     allowed_fail: usize,
+    timed_out: usize,
     filtered_out: usize,
+    in_other_shards: usize,
     measured: usize,
     metrics: MetricMap,
     failures: Vec<(TestDesc, Vec<u8>)>,
     not_failures: Vec<(TestDesc, Vec<u8>)>,
+    shuffle_seed: Option<u64>,
+    shard: Option<Shard>,
+    /// The `--baseline` to compare benchmarks with, and its name.
+    baseline: Option<(String, MetricMap)>,
+    regression_threshold: f64,
     options: Options,
 }
 
@@ -686,6 +1011,19 @@ impl ConsoleTestState {
             None => None,
         };
 
+        let baseline = match opts.baseline {
+            Some(ref name) => match MetricMap::load(&baseline_path(name)?) {
+                Ok(metrics) => Some((name.clone(), metrics)),
+                Err(e) => {
+                    return Err(io::Error::new(
+                        e.kind(),
+                        format!("could not read benchmark baseline `{}`: {}", name, e),
+                    ))
+                }
+            },
+            None => None,
+        };
+
         Ok(ConsoleTestState {
             log_out,
             total: 0,
@@ -693,15 +1031,43 @@ impl ConsoleTestState {
             failed: 0,
             ignored: 0,
             allowed_fail: 0,
+            timed_out: 0,
             filtered_out: 0,
+            in_other_shards: 0,
             measured: 0,
             metrics: MetricMap::new(),
             failures: Vec::new(),
             not_failures: Vec::new(),
+            shuffle_seed: opts.shuffle_seed,
+            shard: opts.shard,
+            baseline,
+            regression_threshold: opts.regression_threshold,
             options: opts.options,
         })
     }
 
+    /// How the benchmarks that are also in the baseline changed.
+    pub fn baseline_changes(&self) -> BTreeMap<String, MetricChange> {
+        match self.baseline {
+            Some((_, ref old)) => self.metrics.compare_to_old(old),
+            None => BTreeMap::new(),
+        }
+    }
+
+    /// Whether `change` is a regression large and certain enough to fail the
+    /// run.
+    pub fn is_regression(&self, change: &MetricChange) -> bool {
+        change.worse && change.confidence > Confidence::Low
+            && change.percent.abs() > self.regression_threshold
+    }
+
+    pub fn regressions(&self) -> usize {
+        self.baseline_changes()
+            .values()
+            .filter(|change| self.is_regression(change))
+            .count()
+    }
+
     pub fn write_log<S: AsRef<str>>(&mut self, msg: S) -> io::Result<()> {
         let msg = msg.as_ref();
         match self.log_out {
@@ -720,6 +1086,7 @@ impl ConsoleTestState {
                 TrIgnored => "ignored".to_owned(),
                 TrAllowedFail => "failed (allowed)".to_owned(),
                 TrBench(ref bs) => fmt_bench_samples(bs),
+                TrTimedOut(secs) => format!("timed out after {} seconds", secs),
             },
             test.name
         ))
@@ -789,7 +1156,7 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
     let mut ntest = 0;
     let mut nbench = 0;
 
-    for test in filter_tests(&opts, tests) {
+    for test in shard_tests(&opts, filter_tests(&opts, tests)) {
         use TestFn::*;
 
         let TestDescAndFn {
@@ -848,6 +1215,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
                 out.write_run_start(filtered_tests.len())
             }
             TeFilteredOut(filtered_out) => Ok(st.filtered_out = filtered_out),
+            TeInOtherShards(in_other_shards) => Ok(st.in_other_shards = in_other_shards),
             TeWait(ref test) => out.write_test_start(test),
             TeTimeout(ref test) => out.write_timeout(test),
             TeResult(test, result, stdout) => {
@@ -878,6 +1246,15 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
                         stdout.extend_from_slice(format!("note: {}", msg).as_bytes());
                         st.failures.push((test, stdout));
                     }
+                    TrTimedOut(secs) => {
+                        st.failed += 1;
+                        st.timed_out += 1;
+                        let mut stdout = stdout;
+                        stdout.extend_from_slice(
+                            format!("note: test did not finish within {} seconds", secs).as_bytes(),
+                        );
+                        st.failures.push((test, stdout));
+                    }
                 }
                 Ok(())
             }
@@ -910,7 +1287,8 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
             max_name_len,
             is_multithreaded,
         )),
-        OutputFormat::Json => Box::new(JsonFormatter::new(output)),
+        OutputFormat::Json => Box::new(JsonFormatter::new(output, opts.shuffle_seed)),
+        OutputFormat::Junit => Box::new(JunitFormatter::new(output, suite_name())),
     };
     let mut st = ConsoleTestState::new(opts)?;
     fn len_if_padded(t: &TestDescAndFn) -> usize {
@@ -920,10 +1298,23 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
         }
     }
 
+    // Before running the benchmarks, which can take long.
+    let save_path = match opts.save_baseline {
+        Some(ref name) => Some(baseline_path(name)?),
+        None => None,
+    };
+
     run_tests(opts, tests, |x| callback(&x, &mut st, &mut *out))?;
 
     assert!(st.current_test_count() == st.total);
 
+    if let Some(path) = save_path {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        st.metrics.save(&path)?;
+    }
+
     return out.write_run_finish(&st);
 }
 
@@ -934,6 +1325,7 @@ fn should_sort_failures_before_printing_them() {
         ignore: false,
         should_panic: ShouldPanic::No,
         allow_fail: false,
+        timeout: None,
     };
 
     let test_b = TestDesc {
@@ -941,6 +1333,7 @@ fn should_sort_failures_before_printing_them() {
         ignore: false,
         should_panic: ShouldPanic::No,
         allow_fail: false,
+        timeout: None,
     };
 
     let mut out = PrettyFormatter::new(Raw(Vec::new()), false, 10, false);
@@ -952,12 +1345,18 @@ fn should_sort_failures_before_printing_them() {
         failed: 0,
         ignored: 0,
         allowed_fail: 0,
+        timed_out: 0,
         filtered_out: 0,
+        in_other_shards: 0,
         measured: 0,
         metrics: MetricMap::new(),
         failures: vec![(test_b, Vec::new()), (test_a, Vec::new())],
         options: Options::new(),
         not_failures: Vec::new(),
+        shuffle_seed: None,
+        shard: None,
+        baseline: None,
+        regression_threshold: DEFAULT_REGRESSION_THRESHOLD,
     };
 
     out.write_failures(&st).unwrap();
@@ -971,6 +1370,17 @@ fn should_sort_failures_before_printing_them() {
     assert!(apos < bpos);
 }
 
+// The name of the test binary, without its extension, used to name the suite
+// in reports.
+fn suite_name() -> String {
+    env::args()
+        .next()
+        .as_ref()
+        .and_then(|arg0| Path::new(arg0).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "test".to_owned())
+}
+
 fn use_color(opts: &TestOpts) -> bool {
     match opts.color {
         AutoColor => !opts.nocapture && stdout_isatty(),
@@ -1014,6 +1424,7 @@ pub enum TestEvent {
     TeResult(TestDesc, TestResult, Vec<u8>),
     TeTimeout(TestDesc),
     TeFilteredOut(usize),
+    TeInOtherShards(usize),
 }
 
 pub type MonitorMsg = (TestDesc, TestResult, Vec<u8>);
@@ -1032,12 +1443,15 @@ pub fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F)
 where
     F: FnMut(TestEvent) -> io::Result<()>,
 {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::sync::mpsc::RecvTimeoutError;
 
     let tests_len = tests.len();
 
-    let mut filtered_tests = filter_tests(opts, tests);
+    let filtered_tests = filter_tests(opts, tests);
+    let filtered_len = filtered_tests.len();
+    let mut filtered_tests = shard_tests(opts, filtered_tests);
+    let in_other_shards = filtered_len - filtered_tests.len();
     if !opts.bench_benchmarks {
         filtered_tests = convert_benchmarks_to_tests(filtered_tests);
     }
@@ -1051,8 +1465,11 @@ where
         filtered_tests
     };
 
-    let filtered_out = tests_len - filtered_tests.len();
+    let filtered_out = tests_len - filtered_len;
     callback(TeFilteredOut(filtered_out))?;
+    if opts.shard.is_some() {
+        callback(TeInOtherShards(in_other_shards))?;
+    }
 
     let filtered_descs = filtered_tests.iter().map(|t| t.desc.clone()).collect();
 
@@ -1073,6 +1490,13 @@ where
     let (tx, rx) = channel::<MonitorMsg>();
 
     let mut running_tests: HashMap<TestDesc, Instant> = HashMap::new();
+    // Tests with a hard time limit, and the instant at which it runs out.
+    let mut deadlines: HashMap<TestDesc, Instant> = HashMap::new();
+    // Tests that were killed after running out of time. The thread waiting on
+    // the process still reports it as crashed, and that result must be ignored.
+    let mut abandoned: HashSet<TestDesc> = HashSet::new();
+    // The processes running tests, which can be killed.
+    let mut children: HashMap<TestDesc, Arc<Mutex<Child>>> = HashMap::new();
 
     fn get_timed_out_tests(running_tests: &mut HashMap<TestDesc, Instant>) -> Vec<TestDesc> {
         let now = Instant::now();
@@ -1092,8 +1516,11 @@ where
         timed_out
     };
 
-    fn calc_timeout(running_tests: &HashMap<TestDesc, Instant>) -> Option<Duration> {
-        running_tests.values().min().map(|next_timeout| {
+    fn calc_timeout(
+        running_tests: &HashMap<TestDesc, Instant>,
+        deadlines: &HashMap<TestDesc, Instant>,
+    ) -> Option<Duration> {
+        running_tests.values().chain(deadlines.values()).min().map(|next_timeout| {
             let now = Instant::now();
             if *next_timeout >= now {
                 *next_timeout - now
@@ -1103,47 +1530,72 @@ where
         })
     };
 
-    if concurrency == 1 {
-        while !remaining.is_empty() {
+    while pending > 0 || !remaining.is_empty() {
+        while pending < concurrency && !remaining.is_empty() {
             let test = remaining.pop().unwrap();
-            callback(TeWait(test.desc.clone()))?;
-            run_test(opts, !opts.run_tests, test, tx.clone());
-            let (test, result, stdout) = rx.recv().unwrap();
-            callback(TeResult(test, result, stdout))?;
-        }
-    } else {
-        while pending > 0 || !remaining.is_empty() {
-            while pending < concurrency && !remaining.is_empty() {
-                let test = remaining.pop().unwrap();
-                let timeout = Instant::now() + Duration::from_secs(TEST_WARN_TIMEOUT_S);
+            let now = Instant::now();
+            if concurrency > 1 {
+                let timeout = now + Duration::from_secs(TEST_WARN_TIMEOUT_S);
                 running_tests.insert(test.desc.clone(), timeout);
-                callback(TeWait(test.desc.clone()))?; //here no pad
-                run_test(opts, !opts.run_tests, test, tx.clone());
-                pending += 1;
             }
-
-            let mut res;
-            loop {
-                if let Some(timeout) = calc_timeout(&running_tests) {
-                    res = rx.recv_timeout(timeout);
-                    for test in get_timed_out_tests(&mut running_tests) {
-                        callback(TeTimeout(test))?;
-                    }
-                    if res != Err(RecvTimeoutError::Timeout) {
-                        break;
-                    }
-                } else {
-                    res = rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
-                    break;
+            let timeout = test.desc.timeout.or(opts.test_timeout);
+            if let Some(secs) = timeout {
+                deadlines.insert(test.desc.clone(), now + Duration::from_secs(secs));
+            }
+            callback(TeWait(test.desc.clone()))?; //here no pad
+            // A thread can't be stopped, so a test with a time limit always
+            // runs in a process of its own, which can be killed.
+            if opts.isolate || timeout.is_some() {
+                let desc = test.desc.clone();
+                if let Some(child) = isolate::spawn_test(opts, test, tx.clone()) {
+                    children.insert(desc, child);
                 }
+            } else {
+                run_test(opts, !opts.run_tests, test, tx.clone());
             }
+            pending += 1;
+        }
+
+        let res = match calc_timeout(&running_tests, &deadlines) {
+            Some(timeout) => rx.recv_timeout(timeout),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
 
-            let (desc, result, stdout) = res.unwrap();
+        // Take in every result that has come in before looking at the
+        // deadlines, so that a test which finished in time isn't reported as
+        // timed out because its deadline expired while we were waiting.
+        let mut results = Vec::new();
+        match res {
+            Ok(msg) => results.push(msg),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => unreachable!(),
+        }
+        results.extend(rx.try_iter());
+        for (desc, result, stdout) in results {
+            if abandoned.remove(&desc) {
+                continue;
+            }
             running_tests.remove(&desc);
+            deadlines.remove(&desc);
+            children.remove(&desc);
 
             callback(TeResult(desc, result, stdout))?;
             pending -= 1;
         }
+
+        for desc in get_timed_out_tests(&mut deadlines) {
+            running_tests.remove(&desc);
+            if let Some(child) = children.remove(&desc) {
+                let _ = child.lock().unwrap().kill();
+            }
+            let secs = desc.timeout.or(opts.test_timeout).unwrap();
+            abandoned.insert(desc.clone());
+            callback(TeResult(desc, TrTimedOut(secs), Vec::new()))?;
+            pending -= 1;
+        }
+        for test in get_timed_out_tests(&mut running_tests) {
+            callback(TeTimeout(test))?;
+        }
     }
 
     if opts.bench_benchmarks {
@@ -1151,15 +1603,22 @@ where
         for b in filtered_benchs {
             callback(TeWait(b.desc.clone()))?;
             run_test(opts, false, b, tx.clone());
-            let (test, result, stdout) = rx.recv().unwrap();
+            let (test, result, stdout) = loop {
+                let msg = rx.recv().unwrap();
+                if !abandoned.remove(&msg.0) {
+                    break msg;
+                }
+            };
             callback(TeResult(test, result, stdout))?;
         }
     }
     Ok(())
 }
 
+/// The number of tests run at once by default: `RUST_TEST_THREADS` if it is
+/// set, and otherwise the number of CPUs.
 #[allow(deprecated)]
-fn get_concurrency() -> usize {
+pub fn get_concurrency() -> usize {
     return match env::var("RUST_TEST_THREADS") {
         Ok(s) => {
             let opt_n: Option<usize> = s.parse().ok();
@@ -1341,9 +1800,66 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
     // Sort the tests alphabetically
     filtered.sort_by(|t1, t2| t1.desc.name.as_slice().cmp(t2.desc.name.as_slice()));
 
+    // Then shuffle them. Starting from the sorted list makes the order only
+    // depend on the seed and on the set of tests.
+    if let Some(seed) = opts.shuffle_seed {
+        shuffle_tests(seed, &mut filtered);
+    }
+
     filtered
 }
 
+fn random_seed() -> u64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    // `RandomState` is seeded by the OS, which is random enough to pick an
+    // order, and saves us a dependency.
+    RandomState::new().build_hasher().finish()
+}
+
+/// Shuffles `tests` with a Fisher-Yates shuffle driven by splitmix64, a PRNG
+/// that is trivial to implement and whose output won't change under us.
+fn shuffle_tests(seed: u64, tests: &mut [TestDescAndFn]) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    };
+    for i in (1..tests.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        tests.swap(i, j);
+    }
+}
+
+/// Keeps the tests of the shard selected by `--shard-index`, if any.
+///
+/// A test belongs to the shard given by the hash of its name, so shards stay
+/// the same whatever the order of the tests, and adding a test doesn't move
+/// the other ones around.
+pub fn shard_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
+    let shard = match opts.shard {
+        Some(shard) => shard,
+        None => return tests,
+    };
+
+    // 64-bit FNV-1a. The hashers of the standard library don't promise to
+    // give the same result from one release to the next.
+    fn hash(name: &str) -> u64 {
+        name.bytes().fold(0xcbf29ce484222325, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    tests
+        .into_iter()
+        .filter(|t| hash(t.desc.name.as_slice()) % shard.count as u64 == shard.index as u64)
+        .collect()
+}
+
 pub fn convert_benchmarks_to_tests(tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     // convert benchmarks to tests, if we're not benchmarking them
     tests
@@ -1516,8 +2032,95 @@ impl MetricMap {
             .collect::<Vec<_>>();
         v.join(", ")
     }
+
+    /// Compares the metrics in both `self` and `old`, by name.
+    ///
+    /// The larger noise of the two measurements decides how much of a change
+    /// is within the noise range, and its sign which direction is an
+    /// improvement (see `insert_metric`).
+    pub fn compare_to_old(&self, old: &MetricMap) -> BTreeMap<String, MetricChange> {
+        let mut changes = BTreeMap::new();
+        for (name, new) in &self.0 {
+            let old = match old.0.get(name) {
+                Some(old) => old,
+                None => continue,
+            };
+            let diff = new.value - old.value;
+            let percent = if old.value == 0.0 {
+                if diff == 0.0 { 0.0 } else { diff.signum() * ::std::f64::INFINITY }
+            } else {
+                diff / old.value * 100.0
+            };
+            let noise = if old.noise.abs() >= new.noise.abs() {
+                old.noise
+            } else {
+                new.noise
+            };
+            let confidence = if diff.abs() > 2.0 * noise.abs() {
+                Confidence::High
+            } else if diff.abs() > noise.abs() {
+                Confidence::Medium
+            } else {
+                Confidence::Low
+            };
+            let worse = if noise < 0.0 { diff < 0.0 } else { diff > 0.0 };
+            changes.insert(
+                name.clone(),
+                MetricChange {
+                    old: old.value,
+                    new: new.value,
+                    percent,
+                    confidence,
+                    worse,
+                },
+            );
+        }
+        changes
+    }
+
+    /// Writes the metrics to `path`, one per line, for `load` to read back.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", METRICS_HEADER)?;
+        for (name, m) in &self.0 {
+            writeln!(file, "{} {} {}", m.value, m.noise, name)?;
+        }
+        file.flush()
+    }
+
+    /// Reads metrics written by `save`.
+    pub fn load(path: &Path) -> io::Result<MetricMap> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: invalid metrics line `{}`", path.display(), line),
+            )
+        };
+
+        let mut lines = contents.lines();
+        match lines.next() {
+            Some(METRICS_HEADER) => {}
+            line => return Err(invalid(line.unwrap_or(""))),
+        }
+        let mut map = MetricMap::new();
+        for line in lines {
+            let mut parts = line.splitn(3, ' ');
+            let value = parts.next().and_then(|v| v.parse().ok());
+            let noise = parts.next().and_then(|n| n.parse().ok());
+            match (value, noise, parts.next()) {
+                (Some(value), Some(noise), Some(name)) => map.insert_metric(name, value, noise),
+                _ => return Err(invalid(line)),
+            }
+        }
+        Ok(map)
+    }
 }
 
+/// The first line of a file written by `MetricMap::save`.
+const METRICS_HEADER: &'static str = "# libtest metrics v1";
+
 // Benchmarking
 
 /// A function that is opaque to the optimizer, to allow benchmarks to
@@ -1743,6 +2346,7 @@ mod tests {
                 ignore: true,
                 should_panic: ShouldPanic::No,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1761,6 +2365,7 @@ mod tests {
                 ignore: true,
                 should_panic: ShouldPanic::No,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1781,6 +2386,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::Yes,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1801,6 +2407,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::YesWithMessage("error message"),
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1823,6 +2430,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::YesWithMessage(expected),
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1841,6 +2449,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::Yes,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1864,6 +2473,151 @@ mod tests {
         assert!((opts.run_ignored));
     }
 
+    #[test]
+    fn parse_test_timeout_flag() {
+        let args = vec![
+            "progname".to_string(),
+            "--test-timeout".to_string(),
+            "30".to_string(),
+        ];
+        match parse_opts(&args) {
+            Some(Err(_)) => {}
+            _ => panic!("--test-timeout accepted without -Z unstable-options"),
+        }
+
+        let mut args = args;
+        args.push("-Zunstable-options".to_string());
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_test_timeout_flag"),
+        };
+        assert_eq!(opts.test_timeout, Some(30));
+    }
+
+    #[test]
+    fn parse_shuffle_flags() {
+        let args = vec![
+            "progname".to_string(),
+            "--shuffle-seed".to_string(),
+            "42".to_string(),
+            "-Zunstable-options".to_string(),
+        ];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_shuffle_flags"),
+        };
+        assert_eq!(opts.shuffle_seed, Some(42));
+
+        let args = vec![
+            "progname".to_string(),
+            "--shuffle".to_string(),
+            "-Zunstable-options".to_string(),
+        ];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_shuffle_flags"),
+        };
+        assert!(opts.shuffle_seed.is_some());
+    }
+
+    #[test]
+    fn shuffle_is_reproducible() {
+        fn dummy() {}
+        let names = || {
+            (0..20)
+                .map(|i| TestDescAndFn {
+                    desc: TestDesc {
+                        name: DynTestName(format!("test{:02}", i)),
+                        ignore: false,
+                        should_panic: ShouldPanic::No,
+                        allow_fail: false,
+                        timeout: None,
+                    },
+                    testfn: DynTestFn(Box::new(dummy)),
+                })
+                .collect::<Vec<_>>()
+        };
+        let order = |seed| {
+            let mut opts = TestOpts::new();
+            opts.shuffle_seed = seed;
+            let mut tests = names();
+            // The order must not depend on the order tests are given in.
+            tests.reverse();
+            filter_tests(&opts, tests)
+                .into_iter()
+                .map(|t| t.desc.name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let sorted = order(None);
+        let shuffled = order(Some(42));
+        assert_eq!(shuffled, order(Some(42)));
+        assert!(shuffled != sorted);
+        assert!(shuffled != order(Some(43)));
+
+        let mut resorted = shuffled.clone();
+        resorted.sort();
+        assert_eq!(resorted, sorted);
+    }
+
+    #[test]
+    fn shards_partition_tests() {
+        use {shard_tests, Shard};
+
+        fn dummy() {}
+        let tests = || {
+            (0..50)
+                .map(|i| TestDescAndFn {
+                    desc: TestDesc {
+                        name: DynTestName(format!("test{}", i)),
+                        ignore: false,
+                        should_panic: ShouldPanic::No,
+                        allow_fail: false,
+                        timeout: None,
+                    },
+                    testfn: DynTestFn(Box::new(dummy)),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut seen = Vec::new();
+        for index in 0..3 {
+            let mut opts = TestOpts::new();
+            opts.shard = Some(Shard { index, count: 3 });
+            let shard = shard_tests(&opts, tests());
+            assert!(!shard.is_empty());
+            seen.extend(shard.into_iter().map(|t| t.desc.name.to_string()));
+        }
+        seen.sort();
+        let mut all = tests().into_iter().map(|t| t.desc.name.to_string()).collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(seen, all);
+    }
+
+    #[test]
+    fn parse_shard_flags() {
+        use Shard;
+
+        let args = |index: &str, count: &str| {
+            vec![
+                "progname".to_string(),
+                "--shard-index".to_string(),
+                index.to_string(),
+                "--shard-count".to_string(),
+                count.to_string(),
+                "-Zunstable-options".to_string(),
+            ]
+        };
+        match parse_opts(&args("1", "3")) {
+            Some(Ok(o)) => assert_eq!(o.shard, Some(Shard { index: 1, count: 3 })),
+            _ => panic!("Malformed arg in parse_shard_flags"),
+        }
+        match parse_opts(&args("3", "3")) {
+            Some(Err(_)) => {}
+            _ => panic!("out of range shard accepted"),
+        }
+    }
+
     #[test]
     pub fn filter_for_ignored_option() {
         // When we run ignored tests the test filter should filter out all the
@@ -1880,6 +2634,7 @@ mod tests {
                     ignore: true,
                     should_panic: ShouldPanic::No,
                     allow_fail: false,
+                    timeout: None,
                 },
                 testfn: DynTestFn(Box::new(move || {})),
             },
@@ -1889,6 +2644,7 @@ mod tests {
                     ignore: false,
                     should_panic: ShouldPanic::No,
                     allow_fail: false,
+                    timeout: None,
                 },
                 testfn: DynTestFn(Box::new(move || {})),
             },
@@ -1911,6 +2667,7 @@ mod tests {
                         ignore: false,
                         should_panic: ShouldPanic::No,
                         allow_fail: false,
+                        timeout: None,
                     },
                     testfn: DynTestFn(Box::new(move || {})),
                 })
@@ -2020,6 +2777,7 @@ mod tests {
                         ignore: false,
                         should_panic: ShouldPanic::No,
                         allow_fail: false,
+                        timeout: None,
                     },
                     testfn: DynTestFn(Box::new(testfn)),
                 };
@@ -2046,6 +2804,75 @@ mod tests {
         }
     }
 
+    #[test]
+    fn junit_report() {
+        use {ConsoleTestState, JunitFormatter, Options, OutputFormatter, Pretty, Raw,
+             TrAllowedFail, DEFAULT_REGRESSION_THRESHOLD};
+
+        let desc = |name: &'static str| TestDesc {
+            name: StaticTestName(name),
+            ignore: false,
+            should_panic: ShouldPanic::No,
+            allow_fail: false,
+            timeout: None,
+        };
+
+        let mut out = JunitFormatter::new(Raw(Vec::new()), "suite".to_owned());
+        out.write_run_start(5).unwrap();
+        for &(name, ref result, stdout) in &[
+            ("a::ok", TrOk, ""),
+            ("a::b::failed", TrFailed, "assertion <failed> & \"quoted\"\n"),
+            ("failed_msg", TrFailedMsg("expected panic".to_owned()), ""),
+            ("a::ignored", TrIgnored, ""),
+            ("a::allowed", TrAllowedFail, ""),
+        ] {
+            let desc = desc(name);
+            out.write_test_start(&desc).unwrap();
+            out.write_result(&desc, result, stdout.as_bytes()).unwrap();
+        }
+
+        let st = ConsoleTestState {
+            log_out: None,
+            total: 5,
+            passed: 1,
+            failed: 2,
+            ignored: 1,
+            allowed_fail: 1,
+            timed_out: 0,
+            filtered_out: 0,
+            in_other_shards: 0,
+            measured: 0,
+            metrics: MetricMap::new(),
+            failures: Vec::new(),
+            options: Options::new(),
+            not_failures: Vec::new(),
+            shuffle_seed: None,
+            shard: None,
+            baseline: None,
+            regression_threshold: DEFAULT_REGRESSION_THRESHOLD,
+        };
+        assert!(!out.write_run_finish(&st).unwrap());
+
+        let s = match out.output_location() {
+            &Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+            &Pretty(_) => unreachable!(),
+        };
+        assert!(s.starts_with("<?xml"));
+        assert!(s.contains(
+            r#"<testsuite name="suite" errors="0" failures="2" skipped="2" tests="5""#));
+        assert!(s.contains(r#"<testcase classname="a" name="ok" time=""#));
+        assert!(s.contains(r#"<testcase classname="a::b" name="failed" time=""#));
+        assert!(s.contains(r#"<failure type="assert"/>"#));
+        assert!(s.contains(
+            "<system-out>assertion &lt;failed&gt; &amp; &quot;quoted&quot;&#10;</system-out>"
+        ));
+        assert!(s.contains(r#"<testcase classname="suite" name="failed_msg" time=""#));
+        assert!(s.contains(r#"<failure type="assert" message="expected panic"/>"#));
+        assert!(s.contains(r#"<skipped message="ignored"/>"#));
+        assert!(s.contains(r#"<skipped message="failed (allowed)"/>"#));
+        assert!(s.ends_with("</testsuite>\n</testsuites>\n"));
+    }
+
     #[test]
     pub fn test_metricmap_compare() {
         let mut m1 = MetricMap::new();
@@ -2069,6 +2896,185 @@ mod tests {
         m2.insert_metric("in-both-want-upwards-and-improved", 2000.0, -10.0);
     }
 
+    #[test]
+    pub fn metricmap_compare_to_old() {
+        use Confidence;
+
+        let mut m1 = MetricMap::new();
+        let mut m2 = MetricMap::new();
+        m1.insert_metric("in-both-noise", 1000.0, 200.0);
+        m2.insert_metric("in-both-noise", 1100.0, 200.0);
+
+        m1.insert_metric("in-first-noise", 1000.0, 2.0);
+        m2.insert_metric("in-second-noise", 1000.0, 2.0);
+
+        m1.insert_metric("in-both-want-downwards-but-regressed", 1000.0, 10.0);
+        m2.insert_metric("in-both-want-downwards-but-regressed", 2000.0, 10.0);
+
+        m1.insert_metric("in-both-want-downwards-and-improved", 2000.0, 10.0);
+        m2.insert_metric("in-both-want-downwards-and-improved", 1000.0, 10.0);
+
+        m1.insert_metric("in-both-want-upwards-but-regressed", 2000.0, -10.0);
+        m2.insert_metric("in-both-want-upwards-but-regressed", 1000.0, -10.0);
+
+        m1.insert_metric("in-both-want-upwards-and-improved", 1000.0, -10.0);
+        m2.insert_metric("in-both-want-upwards-and-improved", 2000.0, -10.0);
+
+        let changes = m2.compare_to_old(&m1);
+        assert_eq!(changes.len(), 5);
+        assert!(!changes.contains_key("in-first-noise"));
+        assert!(!changes.contains_key("in-second-noise"));
+
+        let noise = &changes["in-both-noise"];
+        assert_eq!(noise.percent, 10.0);
+        assert_eq!(noise.confidence, Confidence::Low);
+        assert!(noise.worse);
+
+        let change = &changes["in-both-want-downwards-but-regressed"];
+        assert_eq!(change.percent, 100.0);
+        assert_eq!(change.confidence, Confidence::High);
+        assert!(change.worse);
+
+        let change = &changes["in-both-want-downwards-and-improved"];
+        assert_eq!(change.percent, -50.0);
+        assert!(!change.worse);
+
+        let change = &changes["in-both-want-upwards-but-regressed"];
+        assert_eq!(change.percent, -50.0);
+        assert!(change.worse);
+
+        let change = &changes["in-both-want-upwards-and-improved"];
+        assert_eq!(change.percent, 100.0);
+        assert!(!change.worse);
+    }
+
+    #[test]
+    pub fn metricmap_save_and_load() {
+        use std::env;
+        use std::fs::{self, File};
+        use std::io::{self, Write};
+        use std::process;
+
+        let dir = env::temp_dir().join(format!("libtest-metrics-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("baseline");
+
+        let mut m = MetricMap::new();
+        m.insert_metric("bench::a", 1234.5, 67.0);
+        m.insert_metric("bench with spaces", 0.1, -0.25);
+        m.save(&path).unwrap();
+        let loaded = MetricMap::load(&path).unwrap();
+
+        let mut garbage = File::create(&path).unwrap();
+        garbage.write_all(b"1 2 not a header\n").unwrap();
+        let err = MetricMap::load(&path).err().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(loaded == m);
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_baseline_flags() {
+        let args = vec![
+            "progname".to_string(),
+            "--save-baseline".to_string(),
+            "new".to_string(),
+            "--baseline".to_string(),
+            "old".to_string(),
+            "--regression-threshold".to_string(),
+            "2.5".to_string(),
+            "-Zunstable-options".to_string(),
+        ];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_baseline_flags"),
+        };
+        assert_eq!(opts.save_baseline, Some("new".to_string()));
+        assert_eq!(opts.baseline, Some("old".to_string()));
+        assert_eq!(opts.regression_threshold, 2.5);
+
+        let args = vec![
+            "progname".to_string(),
+            "--baseline".to_string(),
+            "../old".to_string(),
+            "-Zunstable-options".to_string(),
+        ];
+        assert!(parse_opts(&args).unwrap().is_err());
+
+        let args = vec![
+            "progname".to_string(),
+            "--baseline".to_string(),
+            "old".to_string(),
+        ];
+        assert!(parse_opts(&args).unwrap().is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn baseline_dir() {
+        use std::env;
+        use std::path::Path;
+        use baseline_path;
+
+        // No other test reads these.
+        let saved = (env::var_os("RUST_BENCH_BASELINE_DIR"), env::var_os("CARGO_TARGET_DIR"));
+        env::remove_var("RUST_BENCH_BASELINE_DIR");
+        env::remove_var("CARGO_TARGET_DIR");
+        let unset = baseline_path("a").is_err();
+        env::set_var("CARGO_TARGET_DIR", "/work/target");
+        let target = baseline_path("a").unwrap();
+        env::set_var("RUST_BENCH_BASELINE_DIR", "/work/baselines");
+        let explicit = baseline_path("a").unwrap();
+        env::set_var("RUST_BENCH_BASELINE_DIR", "baselines");
+        let relative = baseline_path("a").is_err();
+        for &(var, ref value) in &[("RUST_BENCH_BASELINE_DIR", saved.0),
+                                   ("CARGO_TARGET_DIR", saved.1)] {
+            match *value {
+                Some(ref value) => env::set_var(var, value),
+                None => env::remove_var(var),
+            }
+        }
+
+        assert!(unset);
+        assert_eq!(target, Path::new("/work/target/bench-baselines/a"));
+        assert_eq!(explicit, Path::new("/work/baselines/a"));
+        assert!(relative);
+    }
+
+    #[test]
+    fn regressions_need_confidence_and_size() {
+        use {ConsoleTestState, OutputFormatter, PrettyFormatter, Pretty, Raw};
+
+        let mut opts = TestOpts::new();
+        opts.regression_threshold = 20.0;
+        let mut st = ConsoleTestState::new(&opts).unwrap();
+        let mut old = MetricMap::new();
+        old.insert_metric("noisy", 1000.0, 500.0);
+        old.insert_metric("small", 1000.0, 10.0);
+        old.insert_metric("large", 1000.0, 10.0);
+        old.insert_metric("faster", 1000.0, 10.0);
+        st.baseline = Some(("old".to_owned(), old));
+        st.metrics.insert_metric("noisy", 1400.0, 500.0);
+        st.metrics.insert_metric("small", 1100.0, 10.0);
+        st.metrics.insert_metric("large", 1500.0, 10.0);
+        st.metrics.insert_metric("faster", 500.0, 10.0);
+        assert_eq!(st.regressions(), 1);
+
+        let mut out = PrettyFormatter::new(Raw(Vec::new()), false, 10, false);
+        assert!(!out.write_run_finish(&st).unwrap());
+        let s = match out.output_location() {
+            &Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+            &Pretty(_) => unreachable!(),
+        };
+        assert!(s.contains("benchmarks compared with baseline `old`:\n"));
+        assert!(s.contains("large        1,000 ->       1,500 ns/iter  +50.00% \
+                            (high confidence) REGRESSED\n"));
+        assert!(s.contains("noisy        1,000 ->       1,400 ns/iter  +40.00% \
+                            (low confidence)\n"));
+        assert!(s.contains("1 benchmark got slower than the baseline by more than 20%\n"));
+    }
+
     #[test]
     pub fn test_bench_once_no_iter() {
         fn f(_: &mut Bencher) {}
@@ -2094,6 +3100,7 @@ mod tests {
             ignore: false,
             should_panic: ShouldPanic::No,
             allow_fail: false,
+            timeout: None,
         };
 
         ::bench::benchmark(desc, tx, true, f);
@@ -2113,6 +3120,7 @@ mod tests {
             ignore: false,
             should_panic: ShouldPanic::No,
             allow_fail: false,
+            timeout: None,
         };
 
         ::bench::benchmark(desc, tx, true, f);
@@ -0,0 +1,70 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![allow(unused_assignments)]
+
+fn dead_store(x: u32) -> u32 {
+    // The first store is dead, and so is the copy of `x` it reads.
+    let mut y = x ^ 1;
+    y = x & 2;
+    y
+}
+
+fn through_pointer() -> u32 {
+    let mut y = 1;
+    let p = &y as *const u32;
+    // Read through `p`: the store must be kept.
+    y = 2;
+    unsafe { *p }
+}
+
+fn main() {
+    // Make sure the functions actually get instantiated.
+    dead_store(0);
+    through_pointer();
+}
+
+// END RUST SOURCE
+// START rustc.dead_store.DeadStoreElimination.before.mir
+// bb0: {
+//     ...
+//     _3 = _1;
+//     _2 = BitXor(move _3, const 1u32);
+//     ...
+//     _4 = _1;
+//     _2 = BitAnd(move _4, const 2u32);
+//     ...
+//     _0 = _2;
+//     ...
+//     return;
+// }
+// END rustc.dead_store.DeadStoreElimination.before.mir
+// START rustc.dead_store.DeadStoreElimination.after.mir
+// bb0: {
+//     ...
+//     nop;
+//     nop;
+//     ...
+//     _4 = _1;
+//     _2 = BitAnd(move _4, const 2u32);
+//     ...
+//     _0 = _2;
+//     ...
+//     return;
+// }
+// END rustc.dead_store.DeadStoreElimination.after.mir
+// START rustc.through_pointer.DeadStoreElimination.after.mir
+// bb0: {
+//     ...
+//     _1 = const 2u32;
+//     ...
+//     return;
+// }
+// END rustc.through_pointer.DeadStoreElimination.after.mir
@@ -0,0 +1,88 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+struct Big([u64; 16]);
+
+#[inline(never)]
+fn make(n: u64) -> Big {
+    Big([n; 16])
+}
+
+fn forward(n: u64) -> Big {
+    // `make` returns into the return place instead of `big`.
+    let big = make(n);
+    big
+}
+
+fn reassign(mut big: Big, n: u64) -> Big {
+    // `make` returns into the argument instead of a temporary.
+    big = make(n);
+    big
+}
+
+fn main() {
+    // Make sure the functions actually get instantiated.
+    forward(0);
+    reassign(Big([0; 16]), 1);
+}
+
+// END RUST SOURCE
+// START rustc.forward.DestinationPropagation.before.mir
+// bb0: {
+//     ...
+//     _2 = const make(move _1) -> bb1;
+// }
+// bb1: {
+//     ...
+//     _0 = move _2;
+//     ...
+//     return;
+// }
+// END rustc.forward.DestinationPropagation.before.mir
+// START rustc.forward.DestinationPropagation.after.mir
+// bb0: {
+//     ...
+//     _0 = const make(move _1) -> bb1;
+// }
+// bb1: {
+//     ...
+//     nop;
+//     ...
+//     return;
+// }
+// END rustc.forward.DestinationPropagation.after.mir
+// START rustc.reassign.DestinationPropagation.before.mir
+// bb0: {
+//     ...
+//     _3 = const make(move _2) -> bb1;
+// }
+// bb1: {
+//     ...
+//     _1 = move _3;
+//     ...
+//     _0 = move _1;
+//     ...
+//     return;
+// }
+// END rustc.reassign.DestinationPropagation.before.mir
+// START rustc.reassign.DestinationPropagation.after.mir
+// bb0: {
+//     ...
+//     _1 = const make(move _2) -> bb1;
+// }
+// bb1: {
+//     ...
+//     nop;
+//     ...
+//     _0 = move _1;
+//     ...
+//     return;
+// }
+// END rustc.reassign.DestinationPropagation.after.mir
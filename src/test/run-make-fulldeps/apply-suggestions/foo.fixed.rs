@@ -0,0 +1,5 @@
+fn main() {
+    let _x = 1;
+    let _y = 2;
+    println!("{}", 3);
+}
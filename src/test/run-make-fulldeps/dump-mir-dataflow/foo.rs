@@ -0,0 +1,10 @@
+#![feature(nll)]
+
+fn main() {
+    let mut v = vec![1];
+    let first = &v[0];
+    if v.len() > 1 {
+        v.push(2);
+    }
+    println!("{}", first);
+}
@@ -0,0 +1,30 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::env;
+use std::process;
+
+#[test]
+fn aborts() {
+    process::abort();
+}
+
+#[test]
+fn exits() {
+    process::exit(3);
+}
+
+#[test]
+fn passes() {}
+
+#[test]
+fn no_harness_var() {
+    assert!(env::var_os("RUST_TEST_ISOLATED_TEST").is_none());
+}
@@ -0,0 +1,28 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(test_timeout)]
+
+use std::thread;
+use std::time::Duration;
+
+#[test]
+#[test_timeout = "1"]
+fn hangs() {
+    // Much longer than the test runs for if the harness kills it.
+    thread::sleep(Duration::from_secs(600));
+}
+
+#[test]
+#[test_timeout = "60"]
+fn finishes_in_time() {}
+
+#[test]
+fn passes() {}
@@ -0,0 +1,5 @@
+pub fn bar() {
+    let y = 1;
+}
+
+pub struct other_bad_name;
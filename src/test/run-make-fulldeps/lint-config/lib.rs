@@ -0,0 +1,9 @@
+#![crate_type = "lib"]
+
+mod generated;
+
+pub fn foo() {
+    let x = 1;
+}
+
+pub struct bad_name;
@@ -0,0 +1,28 @@
+#!/usr/bin/env python
+
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import json
+import sys
+
+definitions = dict((d["definition"], d) for d in json.load(sys.stdin))
+
+generic = definitions["foo::generic"]
+assert generic["instances"] == 3, generic
+assert generic["triggered_by"] == ["foo"], generic
+assert generic["mir_size"] > 0 and generic["llvm_size"] > 0, generic
+instances = set(item["instance"] for item in generic["items"])
+assert instances == set(["foo::generic<u8>", "foo::generic<u32>",
+                         "foo::generic<alloc::string::String>"]), instances
+assert all(item["copies"] == 1 for item in generic["items"]), generic
+
+main = definitions["foo::main"]
+assert main["instances"] == 1 and main["items"][0]["instance"] == "foo::main", main
@@ -0,0 +1,8 @@
+fn generic<T: Clone + Default>(x: T) -> Vec<T> {
+    vec![x.clone(), T::default()]
+}
+
+fn main() {
+    let n = generic(1u8).len() + generic(2u32).len() + generic(String::new()).len();
+    println!("{}", n);
+}
@@ -0,0 +1,14 @@
+fn consume(buf: &mut [u8]) -> u8 {
+    buf[0] = 1;
+    buf[buf.len() - 1]
+}
+
+fn big() -> u8 {
+    let mut buf = [0u8; 4096];
+    consume(&mut buf)
+}
+
+fn main() {
+    let mut small = [0u8; 16];
+    println!("{} {}", big(), consume(&mut small));
+}
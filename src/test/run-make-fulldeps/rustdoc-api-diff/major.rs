@@ -0,0 +1,47 @@
+pub struct Plain {
+    pub a: u32,
+    pub b: u8,
+}
+
+impl Plain {
+    pub fn new() -> Plain {
+        Plain { a: 0, b: 0 }
+    }
+
+    pub fn with_b(b: u8) -> Plain {
+        Plain { a: 0, b }
+    }
+}
+
+pub enum Kind {
+    A,
+    B,
+}
+
+pub trait Tr {
+    fn required(&self);
+    fn provided(&self) {}
+    fn added(&self);
+}
+
+pub fn changed(x: u64) -> u32 {
+    x as u32
+}
+
+pub fn unchanged() {}
+
+pub fn bounded<T: Clone>(x: T) -> T {
+    x
+}
+
+pub struct Holder<T> {
+    value: T,
+}
+
+pub struct Wrapper<T>(T);
+
+impl Wrapper<u8> {
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+}
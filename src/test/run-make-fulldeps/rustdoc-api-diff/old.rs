@@ -0,0 +1,48 @@
+pub struct Plain {
+    pub a: u32,
+}
+
+impl Plain {
+    pub fn new() -> Plain {
+        Plain { a: 0 }
+    }
+}
+
+pub enum Kind {
+    A,
+}
+
+pub trait Tr {
+    fn required(&self);
+    fn provided(&self) {}
+}
+
+pub fn changed(x: u32) -> u32 {
+    x
+}
+
+pub fn removed() {}
+
+pub fn unchanged() {}
+
+pub fn bounded<T: Clone>(x: T) -> T {
+    x
+}
+
+pub struct Holder<T> {
+    value: T,
+}
+
+pub struct Wrapper<T>(T);
+
+impl Wrapper<u8> {
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+}
+
+impl Wrapper<u16> {
+    pub fn get(&self) -> u16 {
+        self.0
+    }
+}
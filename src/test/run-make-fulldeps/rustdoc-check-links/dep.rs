@@ -0,0 +1,10 @@
+#![crate_type = "lib"]
+
+/// A thing.
+pub struct Thing {
+    pub size: usize,
+}
+
+impl Thing {
+    pub fn grow(&mut self) {}
+}
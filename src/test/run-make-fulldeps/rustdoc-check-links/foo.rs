@@ -0,0 +1,31 @@
+#![crate_type = "lib"]
+
+extern crate dep;
+
+/// Links to [Bar], [Bar#method.new], [Bar#examples], [Bar#fields] and [Bar#methods].
+///
+/// Links to [Missing], [Bar#method.missing] and [Bar#variants].
+pub fn foo() {}
+
+/// Links to [Bar::new#examples] and [Hidden].
+///
+/// Links to [dep::Thing] and [dep::Thing#method.grow].
+pub fn bar() {}
+
+/// A bar.
+///
+/// # Examples
+///
+/// A `Bar` can be made with [`Bar::new`].
+pub struct Bar {
+    pub field: u32,
+}
+
+impl Bar {
+    pub fn new() -> Bar {
+        Bar { field: 0 }
+    }
+}
+
+#[doc(hidden)]
+pub struct Hidden;
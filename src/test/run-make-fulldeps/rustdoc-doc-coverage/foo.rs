@@ -0,0 +1,32 @@
+//! The crate documentation.
+
+/// A documented function.
+///
+/// ```
+/// foo::documented();
+/// ```
+pub fn documented() {}
+
+pub fn undocumented() {}
+
+/// A documented struct.
+pub struct Documented {
+    /// A documented field.
+    pub field: u32,
+    pub undocumented_field: u32,
+}
+
+impl Documented {
+    pub fn new() -> Documented {
+        Documented { field: 0, undocumented_field: 0 }
+    }
+}
+
+/// A documented module.
+pub mod inner {
+    /// A documented function.
+    pub fn inner_fn() {}
+}
+
+// Private items aren't documented, so they aren't counted.
+fn private() {}
@@ -0,0 +1,29 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! ```
+//! assert_eq!(foo::answer(), 42);
+//! ```
+
+/// Only passes without `DOCTEST_FAIL`, to tell whether it ran.
+///
+/// ```
+/// assert!(std::env::var("DOCTEST_FAIL").is_err());
+/// ```
+pub fn answer() -> u32 {
+    42
+}
+
+/// ```should_panic
+/// foo::oops();
+/// ```
+pub fn oops() {
+    panic!("oops")
+}
@@ -0,0 +1,75 @@
+#!/usr/bin/env python
+
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import sys
+import json
+
+with open(sys.argv[1]) as f:
+    doc = json.load(f)
+
+assert doc["format_version"] == 1
+assert doc["crate_name"] == "foo"
+
+index = doc["index"]
+paths = doc["paths"]
+
+
+def by_path(*path):
+    ids = [id for id, p in paths.items() if p["path"] == list(path)]
+    assert len(ids) == 1, path
+    return index[ids[0]]
+
+
+root = index[doc["root"]]
+assert root["kind"] == "mod"
+assert root["inner"]["is_crate"]
+assert root["docs"] == "The crate docs."
+
+foo = by_path("foo", "Foo")
+bar = by_path("foo", "Bar")
+assert foo["kind"] == "struct"
+assert foo["docs"] == "A wrapper around a [Bar]."
+assert foo["links"] == [{"text": "Bar", "id": bar["id"], "fragment": None}]
+assert foo["inner"]["struct_type"] == "plain"
+assert [index[f]["name"] for f in foo["inner"]["fields"]] == ["bar", "value"]
+[param] = foo["inner"]["generics"]["params"]
+assert param["name"] == "T" and param["kind"] == "type"
+assert by_path("foo", "Foo", "bar")["inner"]["type"]["inner"]["id"] == bar["id"]
+
+impls = [item for item in index.values()
+         if item["kind"] == "impl" and item["inner"]["for"]["kind"] == "resolved_path"
+         and item["inner"]["for"]["inner"]["id"] == foo["id"]]
+traits = {}
+for impl in impls:
+    traits[impl["inner"]["trait"]["inner"]["name"]] = impl["inner"]
+assert not traits["Clone"]["synthetic"]
+assert traits["Send"]["synthetic"]
+assert traits["Sync"]["synthetic"]
+assert traits["Trait"]["provided_trait_methods"] == ["provided"]
+[required] = traits["Trait"]["items"]
+assert index[required]["name"] == "required"
+assert index[required]["inner"]["decl"]["output"] == {"kind": "primitive", "inner": "u32"}
+
+trait = by_path("foo", "Trait")
+assert [index[i]["inner"]["has_body"] for i in trait["inner"]["items"]] == [False, True]
+
+generic = by_path("foo", "inner", "generic")
+assert generic["kind"] == "fn"
+decl = generic["inner"]["decl"]
+[[name, ty]] = decl["inputs"]
+assert name == "x"
+assert ty["kind"] == "borrowed_ref" and ty["inner"]["lifetime"] == "'a"
+assert decl["output"]["inner"]["name"] == "Option"
+[pred] = generic["inner"]["generics"]["where_predicates"]
+assert pred["kind"] == "bound_predicate"
+
+assert not [p for p in paths.values() if p["path"] == ["foo", "inner", "private"]]
@@ -0,0 +1,40 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_name = "foo"]
+
+//! The crate docs.
+
+/// A wrapper around a [Bar].
+#[derive(Clone)]
+pub struct Foo<T: Copy> {
+    pub bar: Bar,
+    pub value: T,
+}
+
+/// The thing wrapped by [Foo].
+pub struct Bar;
+
+pub trait Trait {
+    fn required(&self) -> u32;
+    fn provided(&self) {}
+}
+
+impl<T: Copy> Trait for Foo<T> {
+    fn required(&self) -> u32 { 0 }
+}
+
+pub mod inner {
+    pub fn generic<'a, T>(x: &'a T) -> Option<&'a T> where T: ::std::fmt::Debug {
+        Some(x)
+    }
+
+    fn private() {}
+}
@@ -0,0 +1,20 @@
+#!/usr/bin/env python
+
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import json
+import sys
+
+events = json.load(sys.stdin)["traceEvents"]
+categories = set(e.get("cat") for e in events)
+assert set(["pass", "query", "codegen"]) <= categories, categories
+assert all(e["ph"] in ("X", "i", "M") for e in events)
+assert all(e["dur"] >= 0 for e in events if e["ph"] == "X")
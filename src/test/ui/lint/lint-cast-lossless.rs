@@ -0,0 +1,37 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test the suggestions of `From` for the casts which can't lose information
+
+#![deny(cast_lossless)]
+#![allow(dead_code)]
+
+fn ints(a: u8, b: i16, c: u16, d: u32, e: i64) {
+    let _ = a as u32; //~ ERROR casting `u8` to `u32` can't lose information
+    let _ = a as i16; //~ ERROR casting `u8` to `i16` can't lose information
+    let _ = b as isize; //~ ERROR casting `i16` to `isize` can't lose information
+    let _ = c as usize; //~ ERROR casting `u16` to `usize` can't lose information
+    let _ = c as isize;
+    let _ = d as usize;
+    let _ = b as u32;
+    let _ = e as i128; //~ ERROR casting `i64` to `i128` can't lose information
+}
+
+fn others(a: bool, b: u8, c: f32, d: i32, e: i64, f: u16) {
+    let _ = a as u8; //~ ERROR casting `bool` to `u8` can't lose information
+    let _ = b as char; //~ ERROR casting `u8` to `char` can't lose information
+    let _ = c as f64; //~ ERROR casting `f32` to `f64` can't lose information
+    let _ = d as f64; //~ ERROR casting `i32` to `f64` can't lose information
+    let _ = d as f32;
+    let _ = e as f64;
+    let _ = f as f32; //~ ERROR casting `u16` to `f32` can't lose information
+}
+
+fn main() {}
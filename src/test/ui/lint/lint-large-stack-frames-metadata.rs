@@ -0,0 +1,28 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: --emit=metadata -Z stack-size-limit=1024
+// no-prefer-dynamic
+
+// Test that large frames are linted without codegen, and in generic
+// functions which are never instantiated
+
+#![deny(large_stack_frames)]
+#![allow(dead_code)]
+
+fn consume(_: &[u8; 2048]) {}
+
+fn big_generic<T>(x: T) -> T { //~ ERROR this function is estimated to be 2056 bytes
+    let buf = [1u8; 2048];
+    consume(&buf);
+    x
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z stack-size-limit=1024
+
+#![deny(large_stack_frames)]
+
+fn consume(_: &[u8; 2048]) {}
+
+fn big_frame() { //~ ERROR the stack frame of this function is estimated to be 2056 bytes
+    let buf = [1u8; 2048];
+    consume(&buf);
+}
+
+fn big_move(buf: [u8; 2048]) -> [u8; 2048] {
+    buf //~ ERROR moving 2048 bytes
+}
+
+fn small_frame() {
+    let buf = [1u8; 512];
+    consume_small(buf);
+}
+
+fn consume_small(_: [u8; 512]) {}
+
+#[allow(large_stack_frames)]
+fn main() {
+    big_frame();
+    big_move([0; 2048]);
+    small_frame();
+}
@@ -0,0 +1,22 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test that the lossy cast lints only suggest `TryFrom` where it's enabled
+
+#![feature(try_from)]
+#![deny(lossy_casts)]
+#![allow(dead_code)]
+
+fn f(x: u64, y: i32) {
+    let _ = x as u32; //~ ERROR casting `u64` to `u32` may truncate the value
+    let _ = y as u32; //~ ERROR casting `i32` to `u32` may lose the sign of the value
+}
+
+fn main() {}
@@ -0,0 +1,54 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// only-64bit
+
+#![deny(lossy_casts)]
+#![allow(dead_code)]
+
+const SMALL: u64 = 200;
+const LARGE: u64 = 70_000;
+
+fn truncation(x: u64, y: usize) {
+    let _ = x as u32; //~ ERROR casting `u64` to `u32` may truncate the value
+    let _ = y as u32; //~ ERROR casting `usize` to `u32` may truncate the value
+    let _ = x as usize;
+    let _ = 300 as u16;
+    let _ = SMALL as u8;
+    let _ = LARGE as u16; //~ ERROR casting `u64` to `u16` may truncate the value
+}
+
+fn sign_loss(x: i32, y: u64) {
+    let _ = x as u32; //~ ERROR casting `i32` to `u32` may lose the sign of the value
+    let _ = x as u64; //~ ERROR casting `i32` to `u64` may lose the sign of the value
+    let _ = y as i64; //~ ERROR casting `u64` to `i64` may wrap around to a negative value
+    let _ = y as i128;
+    let _ = -1i32 as u32; //~ ERROR casting `i32` to `u32` may lose the sign of the value
+    let _ = 7i32 as u32;
+}
+
+fn float_to_int(x: f64) {
+    let _ = x as i32; //~ ERROR casting `f64` to `i32` may lose the value
+    let _ = 255.0 as u8;
+    let _ = 256.0 as u8; //~ ERROR casting `f64` to `u8` may lose the value
+}
+
+fn ptr_to_int(p: *const u8) {
+    let _ = p as u32; //~ ERROR casting `*const u8` to `u32` truncates the address
+    let _ = p as usize;
+}
+
+fn char_truncation(c: char) {
+    let _ = c as u8; //~ ERROR casting `char` to `u8` may truncate the value
+    let _ = c as u32;
+    let _ = 'a' as u8;
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: --test
+
+#![feature(test_timeout)]
+
+#[test]
+#[test_timeout = "soon"]
+//~^ ERROR attribute must be of the form:
+fn test1() {}
+
+#[test]
+#[test_timeout(10)]
+//~^ ERROR attribute must be of the form:
+fn test2() {}
+
+#[test]
+#[test_timeout = "-1"]
+//~^ ERROR attribute must be of the form:
+fn test3() {}
@@ -659,6 +659,7 @@ pub fn make_test(config: &Config, testpaths: &TestPaths) -> Vec<test::TestDescAn
                     ignore,
                     should_panic,
                     allow_fail: false,
+                    timeout: None,
                 },
                 testfn: make_test_closure(config, testpaths, revision),
             }